@@ -0,0 +1,47 @@
+#import bevy_sprite::mesh2d_vertex_output::VertexOutput
+
+struct BlendMaterial {
+    // x, y: grid size in cells, z: blend width as a share of a cell
+    grid: vec4<f32>,
+};
+
+@group(#{MATERIAL_BIND_GROUP}) @binding(0) var<uniform> material: BlendMaterial;
+@group(#{MATERIAL_BIND_GROUP}) @binding(1) var cells: texture_2d<f32>;
+
+// Color of a cell, or `fallback` off the grid and for uncollapsed cells.
+fn cell_color(cell: vec2<i32>, fallback: vec4<f32>) -> vec4<f32> {
+    let size = vec2<i32>(material.grid.xy);
+    if any(cell < vec2<i32>(0)) || any(cell >= size) {
+        return fallback;
+    }
+    let color = textureLoad(cells, cell, 0);
+    return select(fallback, color, color.a > 0.0);
+}
+
+@fragment
+fn fragment(mesh: VertexOutput) -> @location(0) vec4<f32> {
+    let p = mesh.uv * material.grid.xy;
+    let cell = vec2<i32>(floor(p));
+    let local = fract(p) - 0.5;
+
+    let own = textureLoad(cells, cell, 0);
+    if own.a <= 0.0 {
+        return vec4<f32>(0.0);
+    }
+
+    // Mix toward each side's neighbor, reaching half way at the shared edge.
+    var color = own;
+    var sides = array<vec2<i32>, 4>(
+        vec2<i32>(1, 0),
+        vec2<i32>(-1, 0),
+        vec2<i32>(0, 1),
+        vec2<i32>(0, -1),
+    );
+    for (var i = 0; i < 4; i++) {
+        let side = sides[i];
+        let to_edge = 0.5 - dot(local, vec2<f32>(side));
+        let weight = 0.5 * (1.0 - smoothstep(0.0, material.grid.z, to_edge));
+        color = mix(color, cell_color(cell + side, own), weight);
+    }
+    return color;
+}
@@ -0,0 +1,66 @@
+// One pass of arc consistency over the grid, run several times per dispatch.
+//
+// domains: [round, changed, done, cells...]. Each cell is a bitmask of its
+// possible tiles, with FIXED set once it's collapsed.
+// rules: [width, height, support...], where support[t] is every tile allowed
+// next to tile t.
+@group(0) @binding(0) var<storage, read_write> domains: array<atomic<u32>>;
+@group(0) @binding(1) var<storage, read> rules: array<u32>;
+
+const HEADER: u32 = 3u;
+const FIXED: u32 = 0x80000000u;
+
+// Every tile allowed next to some tile of `domain`.
+fn support(domain: u32) -> u32 {
+    var bits = domain;
+    var allowed = 0u;
+    while bits != 0u {
+        allowed |= rules[2u + countTrailingZeros(bits)];
+        bits &= bits - 1u;
+    }
+    return allowed;
+}
+
+// An emptied neighbor is a contradiction the solver resets; it doesn't narrow
+// the cells around it in the meantime.
+fn narrow(domain: u32, neighbor: u32) -> u32 {
+    let tiles = atomicLoad(&domains[HEADER + neighbor]) & ~FIXED;
+    return select(domain, domain & support(tiles), tiles != 0u);
+}
+
+@compute @workgroup_size(64)
+fn propagate(@builtin(global_invocation_id) id: vec3<u32>) {
+    let width = rules[0];
+    let height = rules[1];
+    let i = id.x;
+    if i == 0u {
+        // Marks the buffer as propagated for the round it was uploaded in.
+        atomicStore(&domains[2], atomicLoad(&domains[0]));
+    }
+    if i >= width * height {
+        return;
+    }
+    let cell = atomicLoad(&domains[HEADER + i]);
+    if (cell & FIXED) != 0u || cell == 0u {
+        return;
+    }
+    let x = i % width;
+    let y = i / width;
+    var domain = cell;
+    if x > 0u {
+        domain = narrow(domain, i - 1u);
+    }
+    if x + 1u < width {
+        domain = narrow(domain, i + 1u);
+    }
+    if y > 0u {
+        domain = narrow(domain, i - width);
+    }
+    if y + 1u < height {
+        domain = narrow(domain, i + width);
+    }
+    if domain != cell {
+        atomicAnd(&domains[HEADER + i], domain);
+        atomicStore(&domains[1], 1u);
+    }
+}
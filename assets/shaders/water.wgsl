@@ -0,0 +1,22 @@
+#import bevy_sprite::{
+    mesh2d_vertex_output::VertexOutput,
+    mesh2d_view_bindings::globals,
+}
+
+struct WaterMaterial {
+    highlight: vec4<f32>,
+    // x: wave frequency in world units, y: scroll speed
+    wave: vec4<f32>,
+};
+
+@group(#{MATERIAL_BIND_GROUP}) @binding(0) var<uniform> material: WaterMaterial;
+
+@fragment
+fn fragment(mesh: VertexOutput) -> @location(0) vec4<f32> {
+    // Sample in world space so the waves run continuously across tiles.
+    let p = mesh.world_position.xy * material.wave.x;
+    let t = globals.time * material.wave.y;
+    let wave = sin(p.x + t + sin(p.y * 1.7 + t * 0.6) * 1.5);
+    let crest = smoothstep(0.6, 1.0, wave);
+    return vec4<f32>(material.highlight.rgb, material.highlight.a * crest);
+}
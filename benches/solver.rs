@@ -0,0 +1,128 @@
+//! Benchmarks of the solver on its own, through `HeadlessSolver`: the first
+//! observation's propagation, a single observation partway through a map,
+//! and whole solves. The grid is fixed at 32x32, so smaller maps are solved
+//! as an open square in the corner with every other cell locked.
+
+use std::hint::black_box;
+
+use criterion::{BatchSize, BenchmarkId, Criterion, criterion_group, criterion_main};
+use wfc::headless::{HeadlessSolver, MAX_STEPS};
+use wfc::lock::Locks;
+use wfc::rules::{Rules, tileset_path};
+use wfc::strategy::{Heuristic, Propagation, Strategy};
+use wfc::{GRID_H, GRID_W, TileType};
+
+const SEED: u64 = 42;
+/// Sides of the open square solved in the size benchmarks.
+const SIZES: [usize; 3] = [8, 16, 32];
+
+/// Rulesets benchmarked, each with a tile that may touch every other, to
+/// lock the cells outside the open square to.
+fn rulesets() -> Vec<(Rules, TileType)> {
+    [
+        ("coast.ron", TileType::Sand),
+        ("dungeon.ron", TileType::Wall),
+    ]
+    .into_iter()
+    .map(|(file, filler)| {
+        let rules = Rules::load(&tileset_path(file)).expect("shipped ruleset loads");
+        (rules, filler)
+    })
+    .collect()
+}
+
+/// Every cell outside a `size` by `size` square in the corner, locked.
+fn open_square(size: usize, filler: TileType) -> Locks {
+    let mut locks = Locks::default();
+    for y in 0..GRID_H {
+        for x in 0..GRID_W {
+            if x >= size || y >= size {
+                locks.toggle(x, y, filler);
+            }
+        }
+    }
+    locks
+}
+
+/// The first observation on a fresh map, where propagation does the most.
+fn propagation(c: &mut Criterion) {
+    let mut group = c.benchmark_group("propagation");
+    for (rules, _) in rulesets() {
+        for propagation in [Propagation::Naive, Propagation::Ac4] {
+            let strategy = Strategy {
+                propagation,
+                ..Strategy::default()
+            };
+            let id = BenchmarkId::new(&rules.name, format!("{propagation:?}"));
+            group.bench_function(id, |b| {
+                b.iter_batched(
+                    || HeadlessSolver::new(&rules, &strategy, &Locks::default(), SEED),
+                    |mut solver| black_box(solver.step()),
+                    BatchSize::SmallInput,
+                );
+            });
+        }
+    }
+    group.finish();
+}
+
+/// One observation with half the map collapsed, for each heuristic.
+fn observation(c: &mut Criterion) {
+    let mut group = c.benchmark_group("observation");
+    for (rules, _) in rulesets() {
+        for heuristic in [
+            Heuristic::MinRemaining,
+            Heuristic::Entropy,
+            Heuristic::Scanline,
+            Heuristic::Random,
+        ] {
+            let strategy = Strategy {
+                heuristic,
+                ..Strategy::default()
+            };
+            let id = BenchmarkId::new(&rules.name, format!("{heuristic:?}"));
+            group.bench_function(id, |b| {
+                b.iter_batched(
+                    || {
+                        let mut solver =
+                            HeadlessSolver::new(&rules, &strategy, &Locks::default(), SEED);
+                        for _ in 0..GRID_W * GRID_H / 2 {
+                            solver.step();
+                        }
+                        solver
+                    },
+                    |mut solver| black_box(solver.step()),
+                    BatchSize::SmallInput,
+                );
+            });
+        }
+    }
+    group.finish();
+}
+
+/// Whole maps, from a fresh grid to the last observation.
+fn full_solve(c: &mut Criterion) {
+    let mut group = c.benchmark_group("solve");
+    group.sample_size(10);
+    for (rules, filler) in rulesets() {
+        for size in SIZES {
+            let locks = open_square(size, filler);
+            let strategy = Strategy::default();
+            let id = BenchmarkId::new(&rules.name, format!("{size}x{size}"));
+            group.bench_function(id, |b| {
+                b.iter_batched(
+                    || HeadlessSolver::new(&rules, &strategy, &locks, SEED),
+                    |mut solver| {
+                        solver.run(MAX_STEPS);
+                        black_box(solver.grid())
+                    },
+                    BatchSize::LargeInput,
+                );
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, propagation, observation, full_solve);
+criterion_main!(benches);
@@ -0,0 +1,70 @@
+use bevy::prelude::*;
+
+use crate::render::sync_tile_sprites;
+use crate::theme::Theme;
+use crate::variation::TileVariation;
+use crate::{Tile, WfcGrid};
+
+const COLLAPSE_SECONDS: f32 = 0.25;
+const COLLAPSE_START_SCALE: f32 = 0.5;
+
+/// Fades a freshly collapsed tile from white to its themed color while
+/// scaling it up to full size.
+#[derive(Component)]
+pub struct CollapseTween {
+    timer: Timer,
+}
+
+impl Default for CollapseTween {
+    fn default() -> Self {
+        Self {
+            timer: Timer::from_seconds(COLLAPSE_SECONDS, TimerMode::Once),
+        }
+    }
+}
+
+pub struct AnimationPlugin;
+
+impl Plugin for AnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, animate_collapses.after(sync_tile_sprites));
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn animate_collapses(
+    mut commands: Commands,
+    time: Res<Time>,
+    theme: Res<Theme>,
+    grid: Res<WfcGrid>,
+    mut tweens: Query<(
+        Entity,
+        &Tile,
+        &mut CollapseTween,
+        &mut Sprite,
+        &mut Transform,
+        Option<&TileVariation>,
+    )>,
+) {
+    for (entity, tile, mut tween, mut sprite, mut transform, variation) in tweens.iter_mut() {
+        let cell = grid.cell(tile.x, tile.y);
+        // Undone before the tween finished; the sprite is already reset.
+        if !cell.collapsed {
+            transform.scale = Vec3::ONE;
+            commands.entity(entity).try_remove::<CollapseTween>();
+            continue;
+        }
+
+        tween.timer.tick(time.delta());
+        let t = EaseFunction::CubicOut.sample_clamped(tween.timer.fraction());
+
+        let color = theme.color(cell.possible[0]);
+        let color = variation.map_or(color, |v| v.tint(color));
+        sprite.color = Color::WHITE.mix(&color, t);
+        transform.scale = Vec3::splat(COLLAPSE_START_SCALE.lerp(1.0, t));
+
+        if tween.timer.is_finished() {
+            commands.entity(entity).try_remove::<CollapseTween>();
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use std::path::{Path, PathBuf};
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[cfg(not(target_arch = "wasm32"))]
+use bevy::{asset::io::file::FileAssetReader, prelude::*};
+
+/// Files under `assets/` read synchronously at startup or from menus. The
+/// browser has no file system to read them from, so web builds embed them
+/// instead; dropping new files into `assets/` there needs a rebuild.
+#[cfg(target_arch = "wasm32")]
+const EMBEDDED: &[(&str, &str)] = &[
+    ("keybindings.ron", include_str!("../assets/keybindings.ron")),
+    (
+        "presets/1_small_island.ron",
+        include_str!("../assets/presets/1_small_island.ron"),
+    ),
+    (
+        "presets/2_archipelago.ron",
+        include_str!("../assets/presets/2_archipelago.ron"),
+    ),
+    (
+        "presets/3_big_continent.ron",
+        include_str!("../assets/presets/3_big_continent.ron"),
+    ),
+    (
+        "presets/4_dungeon.ron",
+        include_str!("../assets/presets/4_dungeon.ron"),
+    ),
+    (
+        "tilesets/coast.ron",
+        include_str!("../assets/tilesets/coast.ron"),
+    ),
+    (
+        "tilesets/dunes.ron",
+        include_str!("../assets/tilesets/dunes.ron"),
+    ),
+    (
+        "tilesets/dungeon.ron",
+        include_str!("../assets/tilesets/dungeon.ron"),
+    ),
+    (
+        "tilesets/marsh.ron",
+        include_str!("../assets/tilesets/marsh.ron"),
+    ),
+];
+
+/// Path of a file under `assets/`, given its path relative to it.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn asset_path(relative: &str) -> PathBuf {
+    FileAssetReader::get_base_path()
+        .join("assets")
+        .join(relative)
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn asset_path(relative: &str) -> PathBuf {
+    Path::new("assets").join(relative)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read_asset(path: &Path) -> Result<String, String> {
+    std::fs::read_to_string(path).map_err(|err| err.to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn read_asset(path: &Path) -> Result<String, String> {
+    EMBEDDED
+        .iter()
+        .find(|(relative, _)| asset_path(relative) == path)
+        .map(|(_, text)| text.to_string())
+        .ok_or_else(|| "not embedded in the web build".to_string())
+}
+
+/// `name` in the executable's directory, falling back to the working
+/// directory if that can't be determined. Saves, replays, exports and
+/// settings all live there rather than under `assets/`.
+pub fn exe_relative_dir(name: &str) -> PathBuf {
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(PathBuf::from))
+        .unwrap_or_default()
+        .join(name)
+}
+
+/// Milliseconds since the Unix epoch, for naming written files so the newest
+/// sorts last.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn unix_millis() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or_default()
+}
+
+/// Writes `text` to `path`, creating the directory it goes in first.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write_creating_dir(path: &Path, text: &str) -> Result<(), String> {
+    path.parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|()| std::fs::write(path, text))
+        .map_err(|err| err.to_string())
+}
+
+/// Files in a directory under `assets/` with the given extension, sorted by
+/// file name.
+pub fn list_assets(dir: &str, extension: &str) -> Vec<PathBuf> {
+    let mut paths = asset_files(dir);
+    paths.retain(|path| path.extension().is_some_and(|ext| ext == extension));
+    paths.sort();
+    paths
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn asset_files(dir: &str) -> Vec<PathBuf> {
+    let dir = asset_path(dir);
+    let Ok(entries) = std::fs::read_dir(&dir) else {
+        warn!("no directory at {}", dir.display());
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .collect()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn asset_files(dir: &str) -> Vec<PathBuf> {
+    let dir = asset_path(dir);
+    EMBEDDED
+        .iter()
+        .map(|(relative, _)| asset_path(relative))
+        .filter(|path| path.parent() == Some(dir.as_path()))
+        .collect()
+}
@@ -0,0 +1,267 @@
+use bevy::asset::RenderAssetUsages;
+use bevy::image::ImageSampler;
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+
+use crate::keybindings::{Action, Keybindings};
+use crate::variation::TileVariation;
+use crate::{GRID_H, GRID_W, Tile, TileType, WfcGrid, collapse_step};
+
+pub const VARIANT_PX: u32 = 16;
+const BORDER_PX: u32 = 4;
+const BORDER_SHADE: f32 = 0.6;
+const ATLAS_COLUMNS: u32 = 8;
+const ATLAS_ROWS: u32 = 8;
+
+// Neighbor bits, clockwise from north. Cardinal bits come first so the low
+// nibble alone is the 4-bit mask used by the 16-tile set.
+const N: u8 = 1 << 0;
+const E: u8 = 1 << 1;
+const S: u8 = 1 << 2;
+const W: u8 = 1 << 3;
+const NE: u8 = 1 << 4;
+const SE: u8 = 1 << 5;
+const SW: u8 = 1 << 6;
+const NW: u8 = 1 << 7;
+
+/// Which sprite variant set is used for collapsed tiles. Toggled with `T`.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AutotileSet {
+    /// 4-bit mask over the cardinal neighbors.
+    Edge16,
+    /// 8-bit mask reduced to the 47 distinct blob variants.
+    #[default]
+    Blob47,
+    /// Flat tiles with colors blended across borders by a shader instead.
+    Blended,
+}
+
+#[derive(Resource)]
+struct AutotileAtlas {
+    image: Handle<Image>,
+    layout: Handle<TextureAtlasLayout>,
+    /// Atlas index for every reduced 8-bit blob mask.
+    blob_index: [usize; 256],
+}
+
+pub struct AutotilePlugin;
+
+impl Plugin for AutotilePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AutotileSet>()
+            .add_systems(Startup, build_atlas)
+            .add_systems(
+                Update,
+                (toggle_set, update_autotiles.after(collapse_step)).chain(),
+            );
+    }
+}
+
+/// Drops corner bits whose two adjacent edges aren't both set, since those
+/// corners can't be seen. This leaves exactly 47 distinct masks.
+fn reduce_blob_mask(mask: u8) -> u8 {
+    let mut reduced = mask & (N | E | S | W);
+    for (corner, a, b) in [(NE, N, E), (SE, S, E), (SW, S, W), (NW, N, W)] {
+        if mask & corner != 0 && mask & a != 0 && mask & b != 0 {
+            reduced |= corner;
+        }
+    }
+    reduced
+}
+
+fn neighbor_mask(x: usize, y: usize, grid: &[Option<TileType>]) -> u8 {
+    let Some(tile) = grid[y * GRID_W + x] else {
+        return 0;
+    };
+    let offsets = [
+        (N, 0, 1),
+        (E, 1, 0),
+        (S, 0, -1),
+        (W, -1, 0),
+        (NE, 1, 1),
+        (SE, 1, -1),
+        (SW, -1, -1),
+        (NW, -1, 1),
+    ];
+
+    let mut mask = 0;
+    for (bit, dx, dy) in offsets {
+        let nx = x as i32 + dx;
+        let ny = y as i32 + dy;
+        // Off-grid and not-yet-collapsed neighbors count as the same type so
+        // borders only appear once both sides are known.
+        let same = if nx < 0 || ny < 0 || nx >= GRID_W as i32 || ny >= GRID_H as i32 {
+            true
+        } else {
+            grid[ny as usize * GRID_W + nx as usize].is_none_or(|n| n == tile)
+        };
+        if same {
+            mask |= bit;
+        }
+    }
+    mask
+}
+
+/// Brightness of pixel `(px, py)` of the blob variant drawn for the collapsed
+/// cell at `(x, y)`, counting variant rows from the top. Used for exports.
+pub fn blob_shade(grid: &[Option<TileType>], x: usize, y: usize, px: u32, py: u32) -> f32 {
+    variant_shade(reduce_blob_mask(neighbor_mask(x, y, grid)), px, py)
+}
+
+/// Brightness of a variant pixel: 1.0 in the interior, darkening towards
+/// edges and inner corners that face a different tile type.
+fn variant_shade(mask: u8, px: u32, py: u32) -> f32 {
+    let max = VARIANT_PX - 1;
+    let to_n = py;
+    let to_s = max - py;
+    let to_w = px;
+    let to_e = max - px;
+
+    let mut distance = u32::MAX;
+    for (bit, d) in [(N, to_n), (E, to_e), (S, to_s), (W, to_w)] {
+        if mask & bit == 0 {
+            distance = distance.min(d);
+        }
+    }
+    for (corner, a, b) in [
+        (NE, to_n, to_e),
+        (SE, to_s, to_e),
+        (SW, to_s, to_w),
+        (NW, to_n, to_w),
+    ] {
+        if mask & corner == 0 {
+            distance = distance.min(a.max(b));
+        }
+    }
+
+    if distance >= BORDER_PX {
+        1.0
+    } else {
+        BORDER_SHADE + (1.0 - BORDER_SHADE) * distance as f32 / BORDER_PX as f32
+    }
+}
+
+fn build_atlas(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    mut layouts: ResMut<Assets<TextureAtlasLayout>>,
+) {
+    // Frames 0..16 hold the 16-tile set, followed by the 47 blob variants.
+    // Corner bits are treated as "same" for the edge set so only edges shade.
+    let mut frames: Vec<u8> = (0..16u8).map(|m| m | NE | SE | SW | NW).collect();
+    let mut blob_masks: Vec<u8> = (0..=255u8).map(reduce_blob_mask).collect();
+    blob_masks.sort_unstable();
+    blob_masks.dedup();
+    debug_assert_eq!(blob_masks.len(), 47);
+
+    let mut blob_index = [0; 256];
+    for mask in 0..=255u8 {
+        let reduced = reduce_blob_mask(mask);
+        blob_index[mask as usize] = 16 + blob_masks.binary_search(&reduced).unwrap();
+    }
+    frames.extend(blob_masks);
+
+    let mut image = Image::new_fill(
+        Extent3d {
+            width: ATLAS_COLUMNS * VARIANT_PX,
+            height: ATLAS_ROWS * VARIANT_PX,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[255, 255, 255, 255],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    image.sampler = ImageSampler::nearest();
+
+    for (frame, &mask) in frames.iter().enumerate() {
+        let origin_x = frame as u32 % ATLAS_COLUMNS * VARIANT_PX;
+        let origin_y = frame as u32 / ATLAS_COLUMNS * VARIANT_PX;
+        for py in 0..VARIANT_PX {
+            for px in 0..VARIANT_PX {
+                let shade = variant_shade(mask, px, py);
+                image
+                    .set_color_at(
+                        origin_x + px,
+                        origin_y + py,
+                        Color::srgb(shade, shade, shade),
+                    )
+                    .unwrap();
+            }
+        }
+    }
+
+    let layout = TextureAtlasLayout::from_grid(
+        UVec2::splat(VARIANT_PX),
+        ATLAS_COLUMNS,
+        ATLAS_ROWS,
+        None,
+        None,
+    );
+
+    commands.insert_resource(AutotileAtlas {
+        image: images.add(image),
+        layout: layouts.add(layout),
+        blob_index,
+    });
+}
+
+fn toggle_set(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keybindings: Res<Keybindings>,
+    mut set: ResMut<AutotileSet>,
+) {
+    if keybindings.just_pressed(Action::CycleAutotile, &keyboard_input) {
+        *set = match *set {
+            AutotileSet::Edge16 => AutotileSet::Blob47,
+            AutotileSet::Blob47 => AutotileSet::Blended,
+            AutotileSet::Blended => AutotileSet::Edge16,
+        };
+    }
+}
+
+fn update_autotiles(
+    set: Res<AutotileSet>,
+    atlas: Res<AutotileAtlas>,
+    wfc_grid: Res<WfcGrid>,
+    mut tiles: Query<(&Tile, &mut Sprite, Option<&TileVariation>)>,
+) {
+    if !wfc_grid.is_changed() && !set.is_changed() {
+        return;
+    }
+
+    let grid = wfc_grid.collapsed_grid();
+
+    for (tile, mut sprite, variation) in tiles.iter_mut() {
+        if grid[tile.y * GRID_W + tile.x].is_none() {
+            // Cells can be un-collapsed again, e.g. by undo.
+            if sprite.texture_atlas.is_some() {
+                sprite.image = Handle::default();
+                sprite.texture_atlas = None;
+            }
+            continue;
+        }
+
+        let mask = neighbor_mask(tile.x, tile.y, &grid);
+        let mask = variation.map_or(mask, |v| v.local_mask(mask));
+        let index = match *set {
+            AutotileSet::Edge16 => (mask & (N | E | S | W)) as usize,
+            AutotileSet::Blob47 => atlas.blob_index[mask as usize],
+            AutotileSet::Blended => {
+                if sprite.texture_atlas.is_some() {
+                    sprite.image = Handle::default();
+                    sprite.texture_atlas = None;
+                }
+                continue;
+            }
+        };
+
+        if sprite.texture_atlas.as_ref().map(|a| a.index) != Some(index) {
+            sprite.image = atlas.image.clone();
+            sprite.texture_atlas = Some(TextureAtlas {
+                layout: atlas.layout.clone(),
+                index,
+            });
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use bevy::ecs::change_detection::Tick;
+use bevy::platform::time::Instant;
+use bevy::prelude::*;
+use bevy::tasks::futures::check_ready;
+use bevy::tasks::{AsyncComputeTaskPool, Task};
+
+use crate::cli::Cli;
+use crate::events::{CellCollapsed, Contradiction};
+use crate::headless::{HeadlessSolver, MAX_STEPS, SolverState};
+use crate::heightmap::WeightBias;
+use crate::inspector::Provenance;
+use crate::keybindings::{Action, Keybindings};
+use crate::lock::Locks;
+use crate::rules::Rules;
+use crate::stepping::{StepBudget, plan_steps};
+use crate::strategy::{Backtrack, Strategy};
+use crate::timeline::{Timeline, TimelineEvent};
+use crate::{Contradicted, GRID_W, Seed, SolverRng, Step, WfcGrid, collapse_step, regenerate};
+
+/// Whether maps are solved as a background task instead of cell by cell.
+/// Toggled with `Ctrl+B` and turned on from the start by `--background`.
+#[derive(Resource, Default)]
+struct BackgroundGeneration {
+    enabled: bool,
+}
+
+/// The running solve, with the grid's change tick when it started so edits
+/// made meanwhile can be noticed.
+#[derive(Resource, Default)]
+struct BackgroundTask(Option<(Task<Solved>, Tick)>);
+
+/// A finished background solve.
+struct Solved {
+    state: SolverState,
+    /// The solve's collapses and rewinds, for the timeline.
+    events: Vec<TimelineEvent>,
+    secs: f32,
+}
+
+/// Solves each map on the async compute task pool with the same solver,
+/// seed and settings as the live one, holding the live solver back
+/// meanwhile, and swaps the finished map in. Keeps the window responsive on
+/// grids where collapsing cell by cell would hitch every frame. Edit the map
+/// while it runs and the live solver takes over from the edit.
+pub struct BackgroundPlugin;
+
+impl Plugin for BackgroundPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BackgroundGeneration>()
+            .init_resource::<BackgroundTask>()
+            .add_systems(Startup, enable_from_cli)
+            .add_systems(
+                Update,
+                (
+                    toggle_background,
+                    start_generation.after(regenerate),
+                    finish_generation,
+                    hold_solver.after(plan_steps),
+                )
+                    .chain()
+                    .before(collapse_step),
+            );
+    }
+}
+
+fn enable_from_cli(cli: Res<Cli>, mut settings: ResMut<BackgroundGeneration>) {
+    settings.enabled = cli.background;
+}
+
+fn toggle_background(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keybindings: Res<Keybindings>,
+    mut settings: ResMut<BackgroundGeneration>,
+) {
+    if keybindings.just_pressed(Action::ToggleBackground, &keyboard_input) {
+        settings.enabled = !settings.enabled;
+        info!(
+            "background generation {}",
+            if settings.enabled { "on" } else { "off" }
+        );
+    }
+}
+
+/// Starts solving the current seed from scratch for each new map, and when
+/// turned on. Dropping the previous task cancels it.
+#[allow(clippy::too_many_arguments)]
+fn start_generation(
+    settings: Res<BackgroundGeneration>,
+    seed: Res<Seed>,
+    rules: Res<Rules>,
+    strategy: Res<Strategy>,
+    locks: Res<Locks>,
+    bias: Res<WeightBias>,
+    grid: Res<WfcGrid>,
+    mut task: ResMut<BackgroundTask>,
+) {
+    if !settings.is_changed() && !seed.is_changed() {
+        return;
+    }
+    task.0 = None;
+    if !settings.enabled {
+        return;
+    }
+    let mut solver = HeadlessSolver::new(&rules, &strategy, &locks, seed.0);
+    solver.set_bias(bias.clone());
+    let solve = AsyncComputeTaskPool::get().spawn(async move {
+        let started = Instant::now();
+        let mut events = Vec::new();
+        // Gives up after `MAX_STEPS` and shows what it has.
+        solver.run_with(MAX_STEPS, |solver, step| match step {
+            Step::Collapsed(x, y, tile) => events.push(TimelineEvent::Collapse(x, y, tile)),
+            Step::Backtracked => events.push(TimelineEvent::Restore(solver.grid_state())),
+            Step::Done => {}
+        });
+        Solved {
+            state: solver.into_state(),
+            events,
+            secs: started.elapsed().as_secs_f32(),
+        }
+    });
+    task.0 = Some((solve, grid.last_changed()));
+    info!("generating seed {} in the background", seed.0);
+}
+
+/// Swaps a finished map in, along with the solver state that produced it,
+/// so undo, the inspector and later resets carry on as if it had been
+/// solved live.
+#[allow(clippy::too_many_arguments)]
+fn finish_generation(
+    mut commands: Commands,
+    mut task: ResMut<BackgroundTask>,
+    mut grid: ResMut<WfcGrid>,
+    mut provenance: ResMut<Provenance>,
+    mut rng: ResMut<SolverRng>,
+    mut backtrack: ResMut<Backtrack>,
+    mut timeline: ResMut<Timeline>,
+    mut collapsed: MessageWriter<CellCollapsed>,
+    mut contradictions: MessageWriter<Contradiction>,
+) {
+    let Some((solve, started)) = &mut task.0 else {
+        return;
+    };
+    if grid.last_changed() != *started {
+        info!("map edited; background generation cancelled");
+        task.0 = None;
+        return;
+    }
+    let Some(solved) = check_ready(solve) else {
+        return;
+    };
+    task.0 = None;
+
+    let Solved {
+        state,
+        events,
+        secs,
+    } = solved;
+    grid.cells = state.grid.cells;
+    grid.invalidate();
+    for (i, &entity) in grid.entities.iter().enumerate() {
+        if state.contradicted.contains(&i) {
+            commands.entity(entity).try_insert(Contradicted::default());
+            contradictions.write(Contradiction {
+                x: i % GRID_W,
+                y: i / GRID_W,
+            });
+        } else {
+            commands.entity(entity).try_remove::<Contradicted>();
+        }
+    }
+    *provenance = state.provenance;
+    *rng = state.rng;
+    *backtrack = state.backtrack;
+    for event in events {
+        match event {
+            TimelineEvent::Collapse(x, y, tile) => {
+                timeline.record(x, y, tile);
+                collapsed.write(CellCollapsed { x, y, tile });
+            }
+            TimelineEvent::Restore(state) => timeline.rebase(state),
+            TimelineEvent::Seek(_) => {}
+        }
+    }
+    info!("background generation finished in {secs:.2}s");
+}
+
+fn hold_solver(task: Res<BackgroundTask>, mut budget: ResMut<StepBudget>) {
+    if task.0.is_some() {
+        budget.0 = 0;
+    }
+}
@@ -0,0 +1,182 @@
+use bevy::platform::time::Instant;
+use bevy::prelude::*;
+
+use crate::Seed;
+use crate::headless::{HeadlessSolver, MAX_STEPS};
+use crate::lock::Locks;
+use crate::rules::Rules;
+use crate::strategy::Strategy;
+
+/// Generations per benchmark, seeded consecutively from the current seed.
+const BENCH_RUNS: usize = 20;
+const PANEL_LEFT: f32 = 8.0;
+/// Below the strategy panel.
+const PANEL_TOP: f32 = 200.0;
+const PANEL_FONT_SIZE: f32 = 14.0;
+const BUTTON_BACKGROUND: Color = Color::srgba(0.05, 0.05, 0.05, 0.8);
+const BUTTON_HOVERED: Color = Color::srgba(0.3, 0.3, 0.3, 0.9);
+
+/// Outcome of one headless generation.
+struct Run {
+    secs: f32,
+    /// Times backtracking rewound the grid.
+    retries: usize,
+    contradictions: usize,
+    solved: bool,
+}
+
+/// Benchmark in progress or last finished. Runs one generation per frame so
+/// the window stays responsive.
+#[derive(Resource, Default)]
+struct Benchmark {
+    remaining: usize,
+    seed: u64,
+    runs: Vec<Run>,
+}
+
+#[derive(Component)]
+struct BenchButton;
+
+#[derive(Component)]
+struct BenchReport;
+
+pub struct BenchPlugin;
+
+impl Plugin for BenchPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Benchmark>()
+            .add_systems(Startup, spawn_panel)
+            .add_systems(
+                Update,
+                (
+                    start_benchmark,
+                    run_generation.run_if(|bench: Res<Benchmark>| bench.remaining > 0),
+                    update_report.run_if(resource_changed::<Benchmark>),
+                )
+                    .chain(),
+            );
+    }
+}
+
+fn spawn_panel(mut commands: Commands) {
+    commands
+        .spawn(Node {
+            position_type: PositionType::Absolute,
+            left: Val::Px(PANEL_LEFT),
+            top: Val::Px(PANEL_TOP),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Start,
+            row_gap: Val::Px(2.0),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn((
+                BenchButton,
+                Button,
+                Node {
+                    padding: UiRect::axes(Val::Px(6.0), Val::Px(2.0)),
+                    ..default()
+                },
+                BackgroundColor(BUTTON_BACKGROUND),
+                children![(
+                    Text::new(format!("Benchmark {BENCH_RUNS} runs")),
+                    TextFont::from_font_size(PANEL_FONT_SIZE),
+                )],
+            ));
+            parent.spawn((
+                BenchReport,
+                Text::default(),
+                TextFont::from_font_size(PANEL_FONT_SIZE),
+                Node {
+                    padding: UiRect::axes(Val::Px(6.0), Val::Px(2.0)),
+                    ..default()
+                },
+                BackgroundColor(BUTTON_BACKGROUND),
+                Visibility::Hidden,
+            ));
+        });
+}
+
+#[allow(clippy::type_complexity)]
+fn start_benchmark(
+    mut buttons: Query<
+        (&Interaction, &mut BackgroundColor),
+        (With<BenchButton>, Changed<Interaction>),
+    >,
+    seed: Res<Seed>,
+    mut bench: ResMut<Benchmark>,
+) {
+    for (interaction, mut background) in buttons.iter_mut() {
+        background.0 = match interaction {
+            Interaction::None => BUTTON_BACKGROUND,
+            Interaction::Hovered | Interaction::Pressed => BUTTON_HOVERED,
+        };
+        if *interaction == Interaction::Pressed && bench.remaining == 0 {
+            *bench = Benchmark {
+                remaining: BENCH_RUNS,
+                seed: seed.0,
+                runs: Vec::new(),
+            };
+        }
+    }
+}
+
+fn run_generation(
+    rules: Res<Rules>,
+    strategy: Res<Strategy>,
+    locks: Res<Locks>,
+    mut bench: ResMut<Benchmark>,
+) {
+    let seed = bench.seed.wrapping_add(bench.runs.len() as u64);
+    let run = generate(&rules, &strategy, &locks, seed);
+    bench.runs.push(run);
+    bench.remaining -= 1;
+}
+
+/// Solves a map headlessly, stopping at [`MAX_STEPS`].
+fn generate(rules: &Rules, strategy: &Strategy, locks: &Locks, seed: u64) -> Run {
+    let start = Instant::now();
+    let mut solver = HeadlessSolver::new(rules, strategy, locks, seed);
+    let stats = solver.run(MAX_STEPS);
+    Run {
+        secs: start.elapsed().as_secs_f32(),
+        retries: stats.retries,
+        contradictions: stats.contradictions,
+        // Finished with contradictions left in the map counts as unsolved.
+        solved: solver.solved(),
+    }
+}
+
+fn update_report(
+    bench: Res<Benchmark>,
+    mut reports: Query<(&mut Text, &mut Visibility), With<BenchReport>>,
+) {
+    let Ok((mut text, mut visibility)) = reports.single_mut() else {
+        return;
+    };
+    if bench.runs.is_empty() {
+        return;
+    }
+    *visibility = Visibility::Inherited;
+
+    let count = bench.runs.len();
+    let times = bench.runs.iter().map(|run| run.secs * 1000.0);
+    let average = times.clone().sum::<f32>() / count as f32;
+    let min = times.clone().fold(f32::INFINITY, f32::min);
+    let max = times.fold(0.0, f32::max);
+    let retries: usize = bench.runs.iter().map(|run| run.retries).sum();
+    let contradictions: usize = bench.runs.iter().map(|run| run.contradictions).sum();
+    let unsolved = bench.runs.iter().filter(|run| !run.solved).count();
+
+    let progress = if bench.remaining > 0 {
+        format!("running {count} / {BENCH_RUNS}\n")
+    } else {
+        String::new()
+    };
+    text.0 = format!(
+        "{progress}{count} runs from seed {}\ntime: avg {average:.1} ms, min {min:.1}, max {max:.1}\nretries: {retries} ({:.1} per run)\ncontradictions: {contradictions} ({:.1} per run)\nunsolved: {unsolved}",
+        bench.seed,
+        retries as f32 / count as f32,
+        contradictions as f32 / count as f32,
+    );
+}
@@ -0,0 +1,150 @@
+use bevy::asset::RenderAssetUsages;
+use bevy::image::ImageSampler;
+use bevy::prelude::*;
+use bevy::render::render_resource::{
+    AsBindGroup, Extent3d, ShaderType, TextureDimension, TextureFormat,
+};
+use bevy::shader::ShaderRef;
+use bevy::sprite_render::{AlphaMode2d, Material2d, Material2dPlugin};
+
+use crate::autotile::AutotileSet;
+use crate::{GRID_H, GRID_W, TILE_SIZE, Tile, WfcGrid, grid_center, layers};
+
+const BLEND_SHADER: &str = "shaders/blend.wgsl";
+/// Share of a cell, measured from its edge, over which colors are blended.
+const BLEND_WIDTH: f32 = 0.35;
+
+/// Tile colors blended softly across borders, drawn as one quad over the grid.
+/// Each cell's color is one texel of `cells`, transparent while uncollapsed.
+#[derive(Asset, TypePath, AsBindGroup, Clone, Debug)]
+pub struct BlendMaterial {
+    #[uniform(0)]
+    params: BlendParams,
+    #[texture(1)]
+    cells: Handle<Image>,
+}
+
+#[derive(ShaderType, Clone, Debug)]
+struct BlendParams {
+    /// x, y: grid size in cells, z: blend width as a share of a cell.
+    grid: Vec4,
+}
+
+impl Material2d for BlendMaterial {
+    fn fragment_shader() -> ShaderRef {
+        BLEND_SHADER.into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode2d {
+        AlphaMode2d::Blend
+    }
+}
+
+#[derive(Resource)]
+struct BlendOverlay {
+    entity: Entity,
+    cells: Handle<Image>,
+}
+
+pub struct BlendPlugin;
+
+impl Plugin for BlendPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(Material2dPlugin::<BlendMaterial>::default())
+            .add_systems(Startup, spawn_overlay)
+            .add_systems(
+                Update,
+                (
+                    show_overlay,
+                    sync_cells.run_if(resource_equals(AutotileSet::Blended)),
+                )
+                    .chain()
+                    .after(crate::animation::animate_collapses),
+            );
+    }
+}
+
+fn spawn_overlay(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut images: ResMut<Assets<Image>>,
+    mut materials: ResMut<Assets<BlendMaterial>>,
+) {
+    let mut cells = Image::new_fill(
+        Extent3d {
+            width: GRID_W as u32,
+            height: GRID_H as u32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 0],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    cells.sampler = ImageSampler::nearest();
+    let cells = images.add(cells);
+
+    let entity = commands
+        .spawn((
+            Mesh2d(meshes.add(Rectangle::new(
+                GRID_W as f32 * TILE_SIZE,
+                GRID_H as f32 * TILE_SIZE,
+            ))),
+            MeshMaterial2d(materials.add(BlendMaterial {
+                params: BlendParams {
+                    grid: Vec4::new(GRID_W as f32, GRID_H as f32, BLEND_WIDTH, 0.0),
+                },
+                cells: cells.clone(),
+            })),
+            Transform::from_translation(grid_center().extend(layers::BLEND)),
+            Visibility::Hidden,
+        ))
+        .id();
+    commands.insert_resource(BlendOverlay { entity, cells });
+}
+
+fn show_overlay(
+    set: Res<AutotileSet>,
+    overlay: Res<BlendOverlay>,
+    mut visibility: Query<&mut Visibility>,
+) {
+    if !set.is_changed() {
+        return;
+    }
+    if let Ok(mut visibility) = visibility.get_mut(overlay.entity) {
+        *visibility = match *set {
+            AutotileSet::Blended => Visibility::Inherited,
+            AutotileSet::Edge16 | AutotileSet::Blob47 => Visibility::Hidden,
+        };
+    }
+}
+
+/// Copies sprite colors of changed cells into the overlay texture, or of all
+/// cells right after switching to the blended set.
+fn sync_cells(
+    set: Res<AutotileSet>,
+    overlay: Res<BlendOverlay>,
+    mut images: ResMut<Assets<Image>>,
+    grid: Res<WfcGrid>,
+    tiles: Query<(&Tile, Ref<Sprite>)>,
+) {
+    if !set.is_changed() && !tiles.iter().any(|(_, sprite)| sprite.is_changed()) {
+        return;
+    }
+    let Some(cells) = images.get_mut(&overlay.cells) else {
+        return;
+    };
+
+    for (tile, sprite) in tiles.iter() {
+        if !sprite.is_changed() && !set.is_changed() {
+            continue;
+        }
+        let color = if grid.cell(tile.x, tile.y).collapsed {
+            sprite.color
+        } else {
+            Color::NONE
+        };
+        // Texture rows run top to bottom.
+        let _ = cells.set_color_at(tile.x as u32, (GRID_H - 1 - tile.y) as u32, color);
+    }
+}
@@ -0,0 +1,135 @@
+use bevy::camera::ScalingMode;
+use bevy::input::mouse::{AccumulatedMouseScroll, MouseScrollUnit};
+use bevy::prelude::*;
+
+use crate::keybindings::{Action, Keybindings};
+use crate::player::Player;
+use crate::stepping::Stepping;
+use crate::{GRID_H, GRID_W, TILE_SIZE, grid_center};
+
+/// Pan speed in world units per second at default zoom; scaled with the zoom
+/// so panning feels the same at any zoom.
+const PAN_SPEED: f32 = 600.0;
+const ZOOM_PER_LINE: f32 = 0.1;
+/// Pixel-based scroll deltas (touchpads) are roughly this many pixels per line.
+const PIXELS_PER_LINE: f32 = 20.0;
+pub const MIN_ZOOM: f32 = 0.1;
+pub const MAX_ZOOM: f32 = 10.0;
+/// Empty space kept around the grid when fitting it to the window, in tiles.
+pub const FIT_MARGIN_TILES: f32 = 1.0;
+
+/// 2D camera centered on the grid. The projection keeps the whole grid plus
+/// a margin in view whatever the window size, so resizing refits it.
+pub fn grid_camera() -> impl Bundle {
+    (
+        Camera2d,
+        fit_projection(Vec2::new(GRID_W as f32, GRID_H as f32) * TILE_SIZE),
+        Transform::from_translation(grid_center().extend(0.0)),
+    )
+}
+
+/// Projection keeping an area of `size` world units plus the margin in view.
+pub fn fit_projection(size: Vec2) -> Projection {
+    let margin = FIT_MARGIN_TILES * 2.0 * TILE_SIZE;
+    Projection::Orthographic(OrthographicProjection {
+        scaling_mode: ScalingMode::AutoMin {
+            min_width: size.x + margin,
+            min_height: size.y + margin,
+        },
+        ..OrthographicProjection::default_2d()
+    })
+}
+
+/// World position under the cursor as seen by the active 2D camera.
+pub fn cursor_world_position(
+    window: &Window,
+    cameras: &Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+) -> Option<Vec2> {
+    let cursor = window.cursor_position()?;
+    let (camera, transform) = cameras.iter().find(|(camera, _)| camera.is_active)?;
+    camera.viewport_to_world_2d(transform, cursor).ok()
+}
+
+pub struct CameraControlsPlugin;
+
+impl Plugin for CameraControlsPlugin {
+    fn build(&self, app: &mut App) {
+        // The pan keys walk the player while there is one.
+        app.add_systems(
+            Update,
+            (
+                pan_camera.run_if(not(any_with_component::<Player>)),
+                zoom_camera,
+            ),
+        );
+    }
+}
+
+fn pan_camera(
+    time: Res<Time>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keybindings: Res<Keybindings>,
+    stepping: Res<Stepping>,
+    mut cameras: Query<(&Camera, &mut Transform, &Projection), With<Camera2d>>,
+) {
+    let mut direction = Vec2::ZERO;
+    // Keys shared with single-stepping only step while generation is paused.
+    let pressed = |action| {
+        if stepping.paused {
+            keybindings.pressed_except(action, Action::Step, &keyboard_input)
+        } else {
+            keybindings.pressed(action, &keyboard_input)
+        }
+    };
+    if pressed(Action::PanUp) {
+        direction.y += 1.0;
+    }
+    if pressed(Action::PanDown) {
+        direction.y -= 1.0;
+    }
+    if pressed(Action::PanRight) {
+        direction.x += 1.0;
+    }
+    if pressed(Action::PanLeft) {
+        direction.x -= 1.0;
+    }
+    if direction == Vec2::ZERO {
+        return;
+    }
+
+    for (camera, mut transform, projection) in cameras.iter_mut() {
+        if !camera.is_active {
+            continue;
+        }
+        let scale = match projection {
+            Projection::Orthographic(ortho) => ortho.scale,
+            _ => 1.0,
+        };
+        let step = direction.normalize() * PAN_SPEED * scale * time.delta_secs();
+        transform.translation += step.extend(0.0);
+    }
+}
+
+fn zoom_camera(
+    scroll: Res<AccumulatedMouseScroll>,
+    mut cameras: Query<(&Camera, &mut Projection), With<Camera2d>>,
+) {
+    let lines = match scroll.unit {
+        MouseScrollUnit::Line => scroll.delta.y,
+        MouseScrollUnit::Pixel => scroll.delta.y / PIXELS_PER_LINE,
+    };
+    if lines == 0.0 {
+        return;
+    }
+
+    for (camera, mut projection) in cameras.iter_mut() {
+        if !camera.is_active {
+            continue;
+        }
+        if let Projection::Orthographic(ortho) = projection.as_mut() {
+            // Scrolling up zooms in; multiplicative so each notch feels the same.
+            let factor = (1.0 - ZOOM_PER_LINE).powf(lines);
+            ortho.scale = (ortho.scale * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        }
+    }
+}
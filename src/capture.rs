@@ -0,0 +1,153 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+use bevy::prelude::*;
+
+use crate::export::export_stem;
+use crate::keybindings::{Action, Keybindings};
+use crate::theme::Theme;
+use crate::{GRID_H, GRID_W, Seed, WfcGrid, collapse_step, regenerate};
+
+/// Collapses between captured frames.
+const COLLAPSES_PER_FRAME: usize = 8;
+const CELL_PX: u32 = 4;
+/// Frame delay as a fraction of a second, and how long the finished map is
+/// held before the animation loops.
+const FRAME_DELAY: (u16, u16) = (1, 20);
+const FINAL_DELAY: (u16, u16) = (2, 1);
+const UNCOLLAPSED: [u8; 4] = [24, 24, 24, 255];
+
+/// Frames of the generation being captured, as RGBA rows from the top.
+#[derive(Resource, Default)]
+struct Capture {
+    active: bool,
+    frames: Vec<Vec<u8>>,
+    /// Collapsed cells when the latest frame was taken.
+    collapsed: usize,
+}
+
+pub struct CapturePlugin;
+
+impl Plugin for CapturePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Capture>().add_systems(
+            Update,
+            (
+                toggle_capture.before(regenerate),
+                capture_frames
+                    .run_if(|capture: Res<Capture>| capture.active)
+                    .after(collapse_step),
+            ),
+        );
+    }
+}
+
+/// Starts capturing by regenerating the current map, so the animation covers
+/// the whole generation, or cancels a running capture.
+fn toggle_capture(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keybindings: Res<Keybindings>,
+    mut seed: ResMut<Seed>,
+    mut capture: ResMut<Capture>,
+) {
+    if !keybindings.just_pressed(Action::CaptureAnimation, &keyboard_input) {
+        return;
+    }
+    if capture.active {
+        *capture = Capture::default();
+        info!("animation capture cancelled");
+    } else {
+        capture.active = true;
+        seed.set_changed();
+        info!("capturing the generation");
+    }
+}
+
+/// Takes a frame every [`COLLAPSES_PER_FRAME`] collapses and writes the
+/// animation once every cell is collapsed. A new map starts the frames over.
+fn capture_frames(
+    seed: Res<Seed>,
+    theme: Res<Theme>,
+    mut capture: ResMut<Capture>,
+    grid: Res<WfcGrid>,
+) {
+    if seed.is_changed() {
+        capture.frames.clear();
+        capture.collapsed = 0;
+    }
+    if !grid.is_changed() && !capture.frames.is_empty() {
+        return;
+    }
+
+    let grid = grid.collapsed_grid();
+    let collapsed = grid.iter().flatten().count();
+    let done = collapsed == grid.len();
+    // Undo and resets can lower the count, which also gets a frame.
+    if !capture.frames.is_empty()
+        && !done
+        && collapsed.abs_diff(capture.collapsed) < COLLAPSES_PER_FRAME
+    {
+        return;
+    }
+
+    let side = CELL_PX as usize;
+    let mut frame = Vec::with_capacity(GRID_W * GRID_H * side * side * 4);
+    for y in (0..GRID_H).rev() {
+        let row: Vec<u8> = (0..GRID_W)
+            .flat_map(|x| {
+                let rgba = grid[y * GRID_W + x].map_or(UNCOLLAPSED, |tile| {
+                    theme.color(tile).to_srgba().to_u8_array()
+                });
+                rgba.repeat(side)
+            })
+            .collect();
+        for _ in 0..side {
+            frame.extend_from_slice(&row);
+        }
+    }
+    capture.frames.push(frame);
+    capture.collapsed = collapsed;
+
+    if done {
+        let path = export_stem("generation").with_extension("png");
+        match write_apng(&path, &capture.frames) {
+            Ok(()) => info!(
+                "saved {} frame animation to {}",
+                capture.frames.len(),
+                path.display()
+            ),
+            Err(err) => warn!("could not save {}: {err}", path.display()),
+        }
+        *capture = Capture::default();
+    }
+}
+
+/// Writes the frames as a looping animated PNG, which browsers and most
+/// image viewers play like a GIF.
+fn write_apng(path: &Path, frames: &[Vec<u8>]) -> Result<(), String> {
+    let file = File::create(path).map_err(|err| err.to_string())?;
+    let mut encoder = png::Encoder::new(
+        BufWriter::new(file),
+        GRID_W as u32 * CELL_PX,
+        GRID_H as u32 * CELL_PX,
+    );
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .set_animated(frames.len() as u32, 0)
+        .map_err(|err| err.to_string())?;
+    let mut writer = encoder.write_header().map_err(|err| err.to_string())?;
+    for (i, frame) in frames.iter().enumerate() {
+        let (numerator, denominator) = if i + 1 == frames.len() {
+            FINAL_DELAY
+        } else {
+            FRAME_DELAY
+        };
+        writer
+            .set_frame_delay(numerator, denominator)
+            .and_then(|()| writer.write_image_data(frame))
+            .map_err(|err| err.to_string())?;
+    }
+    writer.finish().map_err(|err| err.to_string())
+}
@@ -0,0 +1,192 @@
+use bevy::asset::RenderAssetUsages;
+use bevy::prelude::*;
+use bevy::render::render_resource::{
+    Extent3d, TextureDimension, TextureFormat, TextureViewDescriptor, TextureViewDimension,
+};
+use bevy::sprite_render::{AlphaMode2d, TileData, TilemapChunk, TilemapChunkTileData};
+
+use crate::keybindings::{Action, Keybindings};
+use crate::{GRID_H, GRID_W, TILE_SIZE, Tile, cell_center, layers};
+
+/// Cells per chunk side. Each chunk is drawn as a single mesh.
+const CHUNK_SIZE: usize = 64;
+/// Grids with more cells than this start out in chunked mode.
+const CHUNKED_THRESHOLD: usize = 128 * 128;
+
+/// How the grid is drawn. Cycled with `B`.
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RenderBackend {
+    /// One sprite entity per cell, with autotiling and swatches.
+    Sprites,
+    /// A few `TilemapChunk` meshes holding flat cell colors, for large grids.
+    Chunked,
+    /// One sprite showing an image with a texel per cell, written only where
+    /// a cell's collapsed tile changed. Flat colors like [`Self::Chunked`],
+    /// without going through the tile sprites at all.
+    Texture,
+}
+
+impl Default for RenderBackend {
+    fn default() -> Self {
+        if GRID_W * GRID_H > CHUNKED_THRESHOLD {
+            RenderBackend::Chunked
+        } else {
+            RenderBackend::Sprites
+        }
+    }
+}
+
+#[derive(Resource)]
+struct Chunks {
+    columns: usize,
+    entities: Vec<Entity>,
+}
+
+impl Chunks {
+    /// Chunk entity and index into its tile data for a cell.
+    fn locate(&self, x: usize, y: usize) -> (Entity, usize) {
+        let chunk = self.entities[y / CHUNK_SIZE * self.columns + x / CHUNK_SIZE];
+        let width = chunk_extent(x / CHUNK_SIZE, GRID_W);
+        (chunk, (y % CHUNK_SIZE) * width + x % CHUNK_SIZE)
+    }
+}
+
+/// Number of cells a chunk covers along an axis of `len` cells.
+fn chunk_extent(chunk: usize, len: usize) -> usize {
+    (len - chunk * CHUNK_SIZE).min(CHUNK_SIZE)
+}
+
+pub struct ChunkedPlugin;
+
+impl Plugin for ChunkedPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RenderBackend>()
+            .add_systems(Startup, spawn_chunks)
+            .add_systems(
+                Update,
+                (
+                    toggle_backend,
+                    apply_backend,
+                    sync_chunks.run_if(resource_equals(RenderBackend::Chunked)),
+                )
+                    .chain()
+                    .after(crate::animation::animate_collapses),
+            );
+    }
+}
+
+fn spawn_chunks(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    // A single white layer; the per-cell color does the rest.
+    let mut tileset = Image::new_fill(
+        Extent3d {
+            width: 1,
+            height: 1,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[255, 255, 255, 255],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    tileset.texture_view_descriptor = Some(TextureViewDescriptor {
+        dimension: Some(TextureViewDimension::D2Array),
+        ..default()
+    });
+    let tileset = images.add(tileset);
+
+    let columns = GRID_W.div_ceil(CHUNK_SIZE);
+    let rows = GRID_H.div_ceil(CHUNK_SIZE);
+    let mut entities = Vec::with_capacity(columns * rows);
+    for row in 0..rows {
+        for column in 0..columns {
+            let width = chunk_extent(column, GRID_W);
+            let height = chunk_extent(row, GRID_H);
+            let first = cell_center(column * CHUNK_SIZE, row * CHUNK_SIZE);
+            let last = cell_center(
+                column * CHUNK_SIZE + width - 1,
+                row * CHUNK_SIZE + height - 1,
+            );
+
+            let entity = commands
+                .spawn((
+                    TilemapChunk {
+                        chunk_size: UVec2::new(width as u32, height as u32),
+                        tile_display_size: UVec2::splat(TILE_SIZE as u32),
+                        tileset: tileset.clone(),
+                        alpha_mode: AlphaMode2d::Opaque,
+                    },
+                    TilemapChunkTileData(vec![Some(TileData::default()); width * height]),
+                    Transform::from_translation(((first + last) / 2.0).extend(layers::TILE)),
+                    Visibility::Hidden,
+                ))
+                .id();
+            entities.push(entity);
+        }
+    }
+    commands.insert_resource(Chunks { columns, entities });
+}
+
+fn toggle_backend(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keybindings: Res<Keybindings>,
+    mut backend: ResMut<RenderBackend>,
+) {
+    if keybindings.just_pressed(Action::ToggleBackend, &keyboard_input) {
+        *backend = match *backend {
+            RenderBackend::Sprites => RenderBackend::Chunked,
+            RenderBackend::Chunked => RenderBackend::Texture,
+            RenderBackend::Texture => RenderBackend::Sprites,
+        };
+    }
+}
+
+/// Shows the tile sprites or the chunks, or neither for the texture. Newly
+/// spawned tiles pick up the current mode too.
+fn apply_backend(
+    backend: Res<RenderBackend>,
+    chunks: Res<Chunks>,
+    mut tiles: Query<(Ref<Tile>, &mut Visibility), Without<TilemapChunk>>,
+    mut chunk_visibility: Query<&mut Visibility, With<TilemapChunk>>,
+) {
+    let (tile_visibility, chunk_shown) = match *backend {
+        RenderBackend::Sprites => (Visibility::Inherited, Visibility::Hidden),
+        RenderBackend::Chunked => (Visibility::Hidden, Visibility::Inherited),
+        RenderBackend::Texture => (Visibility::Hidden, Visibility::Hidden),
+    };
+
+    for (tile, mut visibility) in tiles.iter_mut() {
+        if backend.is_changed() || tile.is_added() {
+            *visibility = tile_visibility;
+        }
+    }
+    if backend.is_changed() {
+        for &chunk in &chunks.entities {
+            if let Ok(mut visibility) = chunk_visibility.get_mut(chunk) {
+                *visibility = chunk_shown;
+            }
+        }
+    }
+}
+
+/// Copies the colors the sprite path computed into the chunk tile data, for
+/// cells whose sprite changed (or all of them right after switching modes).
+fn sync_chunks(
+    backend: Res<RenderBackend>,
+    chunks: Res<Chunks>,
+    tiles: Query<(&Tile, Ref<Sprite>)>,
+    mut chunk_data: Query<&mut TilemapChunkTileData>,
+) {
+    for (tile, sprite) in tiles.iter() {
+        if !sprite.is_changed() && !backend.is_changed() {
+            continue;
+        }
+        let (chunk, index) = chunks.locate(tile.x, tile.y);
+        let Ok(mut data) = chunk_data.get_mut(chunk) else {
+            continue;
+        };
+        data.0[index] = Some(TileData {
+            color: sprite.color,
+            ..default()
+        });
+    }
+}
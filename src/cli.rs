@@ -0,0 +1,298 @@
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use clap::Parser;
+
+use crate::heightmap::{WeightBias, read_bias};
+use crate::rules::Rules;
+use crate::sample::learn_rules;
+use crate::strategy::{Heuristic, Propagation, Strategy};
+use crate::theme::Theme;
+use crate::wang::{WangLayout, import_wang};
+use crate::{GRID_H, GRID_W};
+
+/// Wave function collapse map generator. Opens the interactive app unless
+/// `--headless` or `--serve` is given.
+#[derive(Parser, Resource, Clone, Debug)]
+#[command(version, about)]
+pub struct Cli {
+    /// Config file, reloaded whenever it changes.
+    #[arg(long, value_name = "PATH", default_value = "wfc.toml")]
+    pub config: PathBuf,
+
+    /// Save file to load on startup, as written by the `Save` button.
+    #[arg(value_name = "SAVE")]
+    pub save: Option<PathBuf>,
+
+    /// Seed of the first map. Random if not given.
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Grid width in cells. Only the fixed grid size is supported for now.
+    #[arg(long, default_value_t = GRID_W)]
+    pub width: usize,
+
+    /// Grid height in cells. Only the fixed grid size is supported for now.
+    #[arg(long, default_value_t = GRID_H)]
+    pub height: usize,
+
+    /// Ruleset file to start with, in the format of `assets/tilesets`.
+    #[arg(long, value_name = "PATH")]
+    pub tileset: Option<PathBuf>,
+
+    /// Sample image to learn the ruleset from. Takes precedence over
+    /// `--tileset`.
+    #[arg(long, value_name = "PNG")]
+    pub sample: Option<PathBuf>,
+
+    /// Wang tile sheet to import as a ruleset, written to `assets/tilesets`.
+    /// Takes precedence over `--tileset`.
+    #[arg(long, value_name = "PNG")]
+    pub wang: Option<PathBuf>,
+
+    /// How `--wang` sheets encode the terrain of each tile.
+    #[arg(long, value_enum, default_value_t)]
+    pub wang_layout: WangLayout,
+
+    /// Grayscale heightmap biasing tile weights: dark cells lean towards
+    /// water and bright ones towards grass.
+    #[arg(long, value_name = "PNG")]
+    pub heightmap: Option<PathBuf>,
+
+    /// JSON or CSV map to start from. Its known cells are kept and the
+    /// solver fills in the rest.
+    #[arg(long, value_name = "PATH")]
+    pub map: Option<PathBuf>,
+
+    /// Which uncollapsed cell is observed next.
+    #[arg(long, value_enum, default_value_t)]
+    pub heuristic: Heuristic,
+
+    /// How a collapse narrows the other domains.
+    #[arg(long, value_enum, default_value_t)]
+    pub propagation: Propagation,
+
+    /// Rewind on contradictions instead of leaving them in the map.
+    #[arg(long)]
+    pub backtracking: bool,
+
+    /// Solve one map without opening a window and write it to each
+    /// `--output` path.
+    #[arg(long, requires = "output")]
+    pub headless: bool,
+
+    /// Where `--headless` writes the map. The extension picks the format:
+    /// `json`, `csv` or `png`. May be given more than once.
+    #[arg(long, short, value_name = "PATH")]
+    pub output: Vec<PathBuf>,
+
+    /// Write a statistics report when each map finishes: `report-<time>.json`
+    /// and a line in `reports.csv`.
+    #[arg(long)]
+    pub report: bool,
+
+    /// Solve each map as a background task and show it once it's finished,
+    /// instead of collapsing it cell by cell. Toggled with `Ctrl+B`.
+    #[arg(long)]
+    pub background: bool,
+
+    /// Also record each frame of automatic collapses for undo, so `Ctrl+Z`
+    /// rewinds the solver itself and not only manual edits.
+    #[arg(long)]
+    pub undo_collapses: bool,
+
+    /// Propagate on the GPU with a compute shader instead of the strategy's
+    /// propagator. Experimental; there's no backtracking.
+    #[cfg(feature = "gpu")]
+    #[arg(long)]
+    pub gpu: bool,
+
+    /// Milliseconds the solver may take per frame, running as many collapses
+    /// as fit instead of a fixed rate.
+    #[arg(long, value_name = "MS")]
+    pub frame_budget: Option<f32>,
+
+    /// Log the solver's collapse and domain reduction rates every second,
+    /// through Bevy's diagnostics.
+    #[arg(long)]
+    pub log_diagnostics: bool,
+
+    /// Serve generated maps over HTTP on this port instead of opening a
+    /// window.
+    #[arg(long, value_name = "PORT", conflicts_with = "headless")]
+    pub serve: Option<u16>,
+
+    /// Accept `--serve` requests on every network interface instead of only
+    /// from this machine. The server has no authentication.
+    #[arg(long, requires = "serve")]
+    pub listen_public: bool,
+
+    /// Solve this many maps in parallel without opening a window, with seeds
+    /// counting up from `--seed` or 0, and print aggregate statistics. Each
+    /// `--output` path gets them as `json`, or one row per map as `csv`.
+    #[arg(
+        long,
+        value_name = "COUNT",
+        value_parser = clap::value_parser!(u64).range(1..),
+        conflicts_with_all = ["headless", "serve"]
+    )]
+    pub sweep: Option<u64>,
+}
+
+impl Cli {
+    /// Parses the process arguments, exiting with a usage message if they're
+    /// invalid, including grid sizes other than the fixed one.
+    pub fn parse_args() -> Self {
+        use clap::CommandFactory;
+
+        let cli = Self::parse();
+        if (cli.width, cli.height) != (GRID_W, GRID_H) {
+            Self::command()
+                .error(
+                    clap::error::ErrorKind::ValueValidation,
+                    format!("the grid is fixed at {GRID_W}x{GRID_H}"),
+                )
+                .exit();
+        }
+        cli
+    }
+
+    pub fn strategy(&self) -> Strategy {
+        Strategy {
+            propagation: self.propagation,
+            backtracking: self.backtracking,
+            heuristic: self.heuristic,
+        }
+    }
+
+    /// Rules learned from `--sample`, else imported from `--wang`, else read
+    /// from `--tileset`, else the built-in ones. Files that can't be read
+    /// fall back to the next option. Called before the app's logging is set
+    /// up, so errors go to stderr.
+    pub fn rules(&self) -> Rules {
+        if let Some(path) = &self.sample {
+            // Learned tiles are drawn in the colors their pixels matched, so
+            // the default theme's palette keeps the output looking like the
+            // sample.
+            match learn_rules(path, Theme::default().palette()) {
+                Ok(rules) => return rules,
+                Err(err) => eprintln!("could not read sample {}: {err}", path.display()),
+            }
+        }
+        if let Some(path) = &self.wang {
+            match import_wang(path, self.wang_layout, Theme::default().palette()) {
+                Ok((rules, file)) => {
+                    println!("wrote tileset {}", file.display());
+                    return rules;
+                }
+                Err(err) => eprintln!("could not import {}: {err}", path.display()),
+            }
+        }
+        if let Some(path) = &self.tileset {
+            match Rules::load(path) {
+                Ok(rules) => return rules,
+                Err(err) => eprintln!("could not load tileset {}: {err}", path.display()),
+            }
+        }
+        Rules::default()
+    }
+
+    /// Tile weight bias from `--heightmap`, or none.
+    pub fn bias(&self) -> WeightBias {
+        let Some(path) = &self.heightmap else {
+            return WeightBias::default();
+        };
+        match read_bias(path) {
+            Ok(bias) => bias,
+            Err(err) => {
+                eprintln!("could not read heightmap {}: {err}", path.display());
+                WeightBias::default()
+            }
+        }
+    }
+}
+
+/// Locks keeping the known cells of the `--map` import, or none without one.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn map_locks(cli: &Cli) -> Result<crate::lock::Locks, String> {
+    use std::collections::HashMap;
+
+    use crate::TileType;
+    use crate::lock::Locks;
+    use crate::map_import::read_map;
+
+    let mut locks = Locks::default();
+    if let Some(path) = &cli.map {
+        let grid =
+            read_map(path).map_err(|err| format!("could not import {}: {err}", path.display()))?;
+        let cells: HashMap<(usize, usize), TileType> = grid
+            .iter()
+            .enumerate()
+            .filter_map(|(i, tile)| tile.map(|tile| ((i % GRID_W, i / GRID_W), tile)))
+            .collect();
+        locks.replace(cells);
+    }
+    Ok(locks)
+}
+
+/// Solves one map from the command line's configuration and writes it to
+/// every `--output` path. Returns false if any of them couldn't be written.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn run_headless(cli: &Cli) -> bool {
+    use crate::export::{map_csv, map_json, map_png};
+    use crate::headless::{HeadlessSolver, MAX_STEPS};
+    use crate::report::Report;
+
+    let seed = cli.seed.unwrap_or_else(rand::random);
+    let rules = cli.rules();
+    // Known cells of an imported map are kept by locking them.
+    let locks = match map_locks(cli) {
+        Ok(locks) => locks,
+        Err(err) => {
+            eprintln!("{err}");
+            return false;
+        }
+    };
+
+    let mut solver = HeadlessSolver::new(&rules, &cli.strategy(), &locks, seed);
+    solver.set_bias(cli.bias());
+    let started = std::time::Instant::now();
+    let stats = solver.run(MAX_STEPS);
+    let grid = solver.grid();
+    if !stats.done {
+        eprintln!("gave up after {MAX_STEPS} steps; open cells are left empty");
+    }
+    if cli.report {
+        let report = Report::new(
+            seed,
+            &rules.name,
+            &grid,
+            stats.contradictions,
+            0,
+            started.elapsed().as_secs_f32(),
+        );
+        match report.write() {
+            Ok(path) => println!("wrote report to {}", path.display()),
+            Err(err) => eprintln!("could not write report: {err}"),
+        }
+    }
+
+    let theme = Theme::default();
+    let mut ok = true;
+    for path in &cli.output {
+        let bytes = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => map_json(seed, &rules.name, &theme, &grid).map(String::into_bytes),
+            Some("csv") => Ok(map_csv(&grid).into_bytes()),
+            Some("png") => map_png(&theme, &grid),
+            _ => Err("unknown format; use a .json, .csv or .png path".to_string()),
+        };
+        match bytes.and_then(|bytes| std::fs::write(path, bytes).map_err(|err| err.to_string())) {
+            Ok(()) => println!("wrote seed {seed} to {}", path.display()),
+            Err(err) => {
+                eprintln!("could not write {}: {err}", path.display());
+                ok = false;
+            }
+        }
+    }
+    ok
+}
@@ -0,0 +1,290 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::keybindings::{Action, Keybindings};
+use crate::{GRID_H, GRID_W, TILE_SIZE, TileType, WfcGrid, cell_center, collapse_step};
+
+pub const COASTLINE_COLOR: Color = Color::srgb(0.05, 0.1, 0.3);
+const SMOOTHING_PASSES: usize = 2;
+
+/// Point on the doubled grid: cell centers sit on even coordinates and the
+/// marching-squares edge midpoints between them on odd ones.
+type HalfPoint = (i32, i32);
+
+/// Smoothed water/land boundaries, in cell units. Toggled with `L`.
+#[derive(Resource, Default)]
+pub struct Coastline {
+    pub visible: bool,
+    pub polylines: Vec<Vec<Vec2>>,
+}
+
+pub struct CoastlinePlugin;
+
+impl Plugin for CoastlinePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Coastline>().add_systems(
+            Update,
+            (
+                toggle_coastline,
+                rebuild_coastline.after(collapse_step),
+                draw_coastline.run_if(|coastline: Res<Coastline>| coastline.visible),
+            )
+                .chain(),
+        );
+    }
+}
+
+/// Segments for each marching-squares case. Corner bits are bottom-left = 1,
+/// bottom-right = 2, top-right = 4 and top-left = 8; edges are named by side.
+/// Saddles (5 and 10) keep the water corners separate.
+fn case_segments(case: u8) -> &'static [(Side, Side)] {
+    use Side::*;
+    match case {
+        1 | 14 => &[(Left, Bottom)],
+        2 | 13 => &[(Bottom, Right)],
+        3 | 12 => &[(Left, Right)],
+        4 | 11 => &[(Right, Top)],
+        5 => &[(Left, Bottom), (Right, Top)],
+        6 | 9 => &[(Bottom, Top)],
+        7 | 8 => &[(Top, Left)],
+        10 => &[(Bottom, Right), (Top, Left)],
+        _ => &[],
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Side {
+    Bottom,
+    Right,
+    Top,
+    Left,
+}
+
+impl Side {
+    fn midpoint(self, x: usize, y: usize) -> HalfPoint {
+        let (x, y) = (x as i32 * 2, y as i32 * 2);
+        match self {
+            Side::Bottom => (x + 1, y),
+            Side::Right => (x + 2, y + 1),
+            Side::Top => (x + 1, y + 2),
+            Side::Left => (x, y + 1),
+        }
+    }
+}
+
+/// Traces water/land boundaries through the collapsed cells with marching
+/// squares, sampling at cell centers. Squares touching an uncollapsed cell are
+/// skipped. Returned polylines are in cell units and already smoothed.
+pub fn trace_coastline(grid: &[Option<TileType>]) -> Vec<Vec<Vec2>> {
+    let mut segments = Vec::new();
+    for y in 0..GRID_H.saturating_sub(1) {
+        for x in 0..GRID_W.saturating_sub(1) {
+            let corners = [(x, y), (x + 1, y), (x + 1, y + 1), (x, y + 1)];
+            let mut case = 0;
+            let mut known = true;
+            for (bit, (cx, cy)) in corners.into_iter().enumerate() {
+                match grid[cy * GRID_W + cx] {
+                    Some(TileType::Water) => case |= 1 << bit,
+                    Some(_) => {}
+                    None => known = false,
+                }
+            }
+            if !known {
+                continue;
+            }
+            for &(a, b) in case_segments(case) {
+                segments.push((a.midpoint(x, y), b.midpoint(x, y)));
+            }
+        }
+    }
+
+    chain_segments(&segments)
+        .into_iter()
+        .map(|points| {
+            let closed = points.len() > 2 && points.first() == points.last();
+            let points: Vec<Vec2> = points
+                .into_iter()
+                .map(|(x, y)| Vec2::new(x as f32, y as f32) / 2.0)
+                .collect();
+            (0..SMOOTHING_PASSES).fold(points, |p, _| chaikin(&p, closed))
+        })
+        .collect()
+}
+
+/// Joins segments sharing endpoints into polylines. Closed loops repeat their
+/// first point at the end.
+fn chain_segments(segments: &[(HalfPoint, HalfPoint)]) -> Vec<Vec<HalfPoint>> {
+    let mut adjacency: HashMap<HalfPoint, Vec<usize>> = HashMap::new();
+    for (i, &(a, b)) in segments.iter().enumerate() {
+        adjacency.entry(a).or_default().push(i);
+        adjacency.entry(b).or_default().push(i);
+    }
+
+    let mut used = vec![false; segments.len()];
+    let mut polylines = Vec::new();
+
+    // Start open chains from their dangling ends first, then pick up loops.
+    let mut starts: Vec<usize> = (0..segments.len()).collect();
+    starts.sort_by_key(|&i| {
+        let (a, b) = segments[i];
+        adjacency[&a].len().min(adjacency[&b].len())
+    });
+
+    for start in starts {
+        if used[start] {
+            continue;
+        }
+        used[start] = true;
+        let (a, b) = segments[start];
+        let (mut line, mut end) = if adjacency[&a].len() == 1 {
+            (vec![a, b], b)
+        } else {
+            (vec![b, a], a)
+        };
+
+        while let Some(&next) = adjacency[&end].iter().find(|&&i| !used[i]) {
+            used[next] = true;
+            let (na, nb) = segments[next];
+            end = if na == end { nb } else { na };
+            line.push(end);
+        }
+        polylines.push(line);
+    }
+    polylines
+}
+
+/// One pass of Chaikin corner cutting.
+fn chaikin(points: &[Vec2], closed: bool) -> Vec<Vec2> {
+    if points.len() < 3 {
+        return points.to_vec();
+    }
+
+    let mut smoothed = Vec::with_capacity(points.len() * 2);
+    if !closed {
+        smoothed.push(points[0]);
+    }
+    for pair in points.windows(2) {
+        smoothed.push(pair[0].lerp(pair[1], 0.25));
+        smoothed.push(pair[0].lerp(pair[1], 0.75));
+    }
+    if closed {
+        smoothed.push(smoothed[0]);
+    } else {
+        smoothed.push(points[points.len() - 1]);
+    }
+    smoothed
+}
+
+fn toggle_coastline(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keybindings: Res<Keybindings>,
+    mut coastline: ResMut<Coastline>,
+) {
+    if keybindings.just_pressed(Action::ToggleCoastline, &keyboard_input) {
+        coastline.visible = !coastline.visible;
+    }
+}
+
+fn rebuild_coastline(mut coastline: ResMut<Coastline>, grid: Res<WfcGrid>) {
+    if !grid.is_changed() {
+        return;
+    }
+    coastline.polylines = trace_coastline(&grid.collapsed_grid());
+}
+
+fn draw_coastline(mut gizmos: Gizmos, coastline: Res<Coastline>) {
+    let origin = cell_center(0, 0);
+    for polyline in &coastline.polylines {
+        gizmos.linestrip_2d(
+            polyline.iter().map(|&p| origin + p * TILE_SIZE),
+            COASTLINE_COLOR,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// All grass, with water at the given cells.
+    fn grid_with_water(water: &[(usize, usize)]) -> Vec<Option<TileType>> {
+        let mut tiles = vec![Some(TileType::Grass); GRID_W * GRID_H];
+        for &(x, y) in water {
+            tiles[y * GRID_W + x] = Some(TileType::Water);
+        }
+        tiles
+    }
+
+    #[test]
+    fn land_only_maps_have_no_coastline() {
+        assert!(trace_coastline(&grid_with_water(&[])).is_empty());
+    }
+
+    #[test]
+    fn a_pond_is_one_closed_loop_around_it() {
+        let polylines = trace_coastline(&grid_with_water(&[(5, 7)]));
+        assert_eq!(polylines.len(), 1);
+        let pond = &polylines[0];
+        assert_eq!(pond.first(), pond.last());
+        // Smoothing only cuts corners of the diamond through the edge
+        // midpoints, so nothing ends up further out than they are.
+        for point in pond {
+            let offset = *point - Vec2::new(5.0, 7.0);
+            assert!(offset.x.abs() + offset.y.abs() <= 0.5 + 1e-5, "{point}");
+        }
+    }
+
+    #[test]
+    fn uncollapsed_cells_break_the_coastline() {
+        let mut tiles = grid_with_water(&[(5, 7)]);
+        tiles[7 * GRID_W + 6] = None;
+        let polylines = trace_coastline(&tiles);
+        assert_eq!(polylines.len(), 1);
+        assert_ne!(polylines[0].first(), polylines[0].last());
+    }
+
+    #[test]
+    fn segments_chain_from_a_dangling_end() {
+        let segments = [((2, 0), (2, 2)), ((0, 0), (1, 0)), ((1, 0), (2, 0))];
+        // Either end could start it; the first segment with one is taken.
+        assert_eq!(
+            chain_segments(&segments),
+            vec![vec![(2, 2), (2, 0), (1, 0), (0, 0)]]
+        );
+    }
+
+    #[test]
+    fn segments_chain_into_closed_loops() {
+        let segments = [((0, 0), (1, 0)), ((1, 1), (0, 0)), ((1, 0), (1, 1))];
+        let polylines = chain_segments(&segments);
+        assert_eq!(polylines.len(), 1);
+        assert_eq!(polylines[0].len(), 4);
+        assert_eq!(polylines[0].first(), polylines[0].last());
+    }
+
+    #[test]
+    fn chaikin_keeps_open_ends_and_closes_loops() {
+        let open = [Vec2::ZERO, Vec2::new(4.0, 0.0), Vec2::new(4.0, 4.0)];
+        let smoothed = chaikin(&open, false);
+        assert_eq!(
+            smoothed,
+            vec![
+                Vec2::ZERO,
+                Vec2::new(1.0, 0.0),
+                Vec2::new(3.0, 0.0),
+                Vec2::new(4.0, 1.0),
+                Vec2::new(4.0, 3.0),
+                Vec2::new(4.0, 4.0),
+            ]
+        );
+
+        let closed = [Vec2::ZERO, Vec2::X, Vec2::Y, Vec2::ZERO];
+        let smoothed = chaikin(&closed, true);
+        assert_eq!(smoothed.len(), 7);
+        assert_eq!(smoothed.first(), smoothed.last());
+
+        let short = [Vec2::ZERO, Vec2::X];
+        assert_eq!(chaikin(&short, false), short.to_vec());
+    }
+}
@@ -0,0 +1,228 @@
+use bevy::prelude::*;
+
+use crate::{GRID_H, GRID_W, TILE_SIZE, TileType, WfcGrid, cell_center, collapse_step};
+
+/// Longest distance a mover goes in one go, in cells. Longer moves are split
+/// up so they can't skip over a blocking cell.
+const MAX_SUBSTEP: f32 = 0.5;
+/// Boxes touching a blocking cell don't count as overlapping it, so one
+/// pushed flush against it in a frame can still slide along it the next.
+const TOUCH_EPSILON: f32 = 1e-3;
+
+/// Which cells block movement, kept in step with the map: tiles characters
+/// can't walk on and cells not collapsed yet. Everything off the grid blocks
+/// too, so movers stay on the map.
+#[derive(Resource)]
+pub struct CollisionGrid {
+    blocked: Vec<bool>,
+}
+
+impl Default for CollisionGrid {
+    fn default() -> Self {
+        Self {
+            blocked: vec![true; GRID_W * GRID_H],
+        }
+    }
+}
+
+/// Something walking over the map, stopped by the [`CollisionGrid`] rather
+/// than by physics colliders. Its transform moves by `velocity` (world units
+/// per second) each frame.
+#[derive(Component, Default)]
+pub struct Mover {
+    /// Width and height of its box in world units, centered on the transform.
+    pub size: Vec2,
+    pub velocity: Vec2,
+}
+
+pub struct CollisionPlugin;
+
+impl Plugin for CollisionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CollisionGrid>().add_systems(
+            Update,
+            (update_collision_grid.after(collapse_step), move_movers).chain(),
+        );
+    }
+}
+
+impl CollisionGrid {
+    /// From the collapsed tile of every cell, indexed by `y * GRID_W + x`.
+    pub fn from_tiles(tiles: &[Option<TileType>]) -> Self {
+        Self {
+            blocked: tiles
+                .iter()
+                .map(|tile| !tile.is_some_and(TileType::walkable))
+                .collect(),
+        }
+    }
+
+    pub fn blocked(&self, x: isize, y: isize) -> bool {
+        if x < 0 || y < 0 || x >= GRID_W as isize || y >= GRID_H as isize {
+            return true;
+        }
+        self.blocked[y as usize * GRID_W + x as usize]
+    }
+
+    /// Moves a box of `size` centered on `center` by `delta`, in world units,
+    /// stopping it flush against the cells it runs into. The axes move one
+    /// after the other, so a box pushed diagonally into a wall slides along
+    /// it. Returns the new center.
+    pub fn move_box(&self, center: Vec2, size: Vec2, delta: Vec2) -> Vec2 {
+        let half = size / TILE_SIZE / 2.0;
+        let delta = delta / TILE_SIZE;
+        let mut center = to_cells(center);
+        let steps = (delta.abs().max_element() / MAX_SUBSTEP).ceil().max(1.0);
+        for _ in 0..steps as usize {
+            for axis in 0..2 {
+                let step = delta[axis] / steps;
+                if step == 0.0 {
+                    continue;
+                }
+                let mut moved = center;
+                moved[axis] += step;
+                if self.overlaps(moved - half, moved + half) {
+                    // Against the first cell boundary past the side leading.
+                    moved[axis] = if step > 0.0 {
+                        (center[axis] + half[axis] - 0.5).ceil() + 0.5 - half[axis]
+                    } else {
+                        (center[axis] - half[axis] + 0.5).floor() - 0.5 + half[axis]
+                    };
+                }
+                center = moved;
+            }
+        }
+        cell_center(0, 0) + center * TILE_SIZE
+    }
+
+    /// Whether the box between `min` and `max`, in cells, overlaps a blocking
+    /// cell. Cell `x` spans `x - 0.5` to `x + 0.5`.
+    fn overlaps(&self, min: Vec2, max: Vec2) -> bool {
+        let first = (min + TOUCH_EPSILON + 0.5).floor();
+        let last = (max - TOUCH_EPSILON + 0.5).ceil() - 1.0;
+        (first.y as isize..=last.y as isize)
+            .any(|y| (first.x as isize..=last.x as isize).any(|x| self.blocked(x, y)))
+    }
+}
+
+/// World position in cells, where cell `(x, y)` is centered on `(x, y)`.
+fn to_cells(world: Vec2) -> Vec2 {
+    (world - cell_center(0, 0)) / TILE_SIZE
+}
+
+fn update_collision_grid(grid: Res<WfcGrid>, mut collision: ResMut<CollisionGrid>) {
+    if !grid.is_changed() {
+        return;
+    }
+    *collision = CollisionGrid::from_tiles(&grid.collapsed_grid());
+}
+
+pub fn move_movers(
+    time: Res<Time>,
+    collision: Res<CollisionGrid>,
+    mut movers: Query<(&Mover, &mut Transform)>,
+) {
+    for (mover, mut transform) in movers.iter_mut() {
+        if mover.velocity == Vec2::ZERO {
+            continue;
+        }
+        let center = collision.move_box(
+            transform.translation.truncate(),
+            mover.size,
+            mover.velocity * time.delta_secs(),
+        );
+        transform.translation = center.extend(transform.translation.z);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOX: Vec2 = Vec2::splat(TILE_SIZE / 2.0);
+
+    /// All grass, with water at the given cells.
+    fn grid_with_water(water: &[(usize, usize)]) -> CollisionGrid {
+        let mut tiles = vec![Some(TileType::Grass); GRID_W * GRID_H];
+        for &(x, y) in water {
+            tiles[y * GRID_W + x] = Some(TileType::Water);
+        }
+        CollisionGrid::from_tiles(&tiles)
+    }
+
+    fn assert_near(actual: Vec2, expected: Vec2) {
+        assert!(
+            actual.abs_diff_eq(expected, 1e-2),
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn off_grid_cells_block() {
+        let grid = grid_with_water(&[]);
+        assert!(!grid.blocked(0, 0));
+        assert!(!grid.blocked(GRID_W as isize - 1, GRID_H as isize - 1));
+        assert!(grid.blocked(-1, 0));
+        assert!(grid.blocked(0, -1));
+        assert!(grid.blocked(GRID_W as isize, 0));
+        assert!(grid.blocked(0, GRID_H as isize));
+    }
+
+    #[test]
+    fn unwalkable_and_uncollapsed_cells_block() {
+        let mut tiles = vec![Some(TileType::Sand); GRID_W * GRID_H];
+        tiles[0] = Some(TileType::Water);
+        tiles[1] = None;
+        let grid = CollisionGrid::from_tiles(&tiles);
+        assert!(grid.blocked(0, 0));
+        assert!(grid.blocked(1, 0));
+        assert!(!grid.blocked(2, 0));
+    }
+
+    #[test]
+    fn edge_cells_stop_movers_at_the_grid_edge() {
+        let grid = grid_with_water(&[]);
+        let left = cell_center(0, 5);
+        let moved = grid.move_box(left, BOX, Vec2::new(-3.0 * TILE_SIZE, 0.0));
+        assert_near(moved, left - Vec2::new(TILE_SIZE / 4.0, 0.0));
+
+        let corner = cell_center(GRID_W - 1, GRID_H - 1);
+        let moved = grid.move_box(corner, BOX, Vec2::splat(TILE_SIZE));
+        assert_near(moved, corner + Vec2::splat(TILE_SIZE / 4.0));
+    }
+
+    #[test]
+    fn movers_slide_along_the_grid_edge() {
+        let grid = grid_with_water(&[]);
+        let bottom = cell_center(4, 0) - Vec2::new(0.0, TILE_SIZE / 4.0);
+        let moved = grid.move_box(bottom, BOX, Vec2::new(2.0 * TILE_SIZE, -TILE_SIZE));
+        assert_near(moved, bottom + Vec2::new(2.0 * TILE_SIZE, 0.0));
+    }
+
+    #[test]
+    fn water_on_an_edge_cell_blocks() {
+        let grid = grid_with_water(&[(GRID_W - 1, 3)]);
+        let start = cell_center(GRID_W - 3, 3);
+        let moved = grid.move_box(start, BOX, Vec2::new(3.0 * TILE_SIZE, 0.0));
+        assert_near(
+            moved,
+            cell_center(GRID_W - 2, 3) + Vec2::new(TILE_SIZE / 4.0, 0.0),
+        );
+
+        // A row over, the edge itself is what stops it.
+        let start = cell_center(GRID_W - 3, 4);
+        let moved = grid.move_box(start, BOX, Vec2::new(3.0 * TILE_SIZE, 0.0));
+        assert_near(
+            moved,
+            cell_center(GRID_W - 1, 4) + Vec2::new(TILE_SIZE / 4.0, 0.0),
+        );
+    }
+
+    #[test]
+    fn fast_movers_dont_skip_over_a_cell() {
+        let grid = grid_with_water(&[(5, 0)]);
+        let start = cell_center(1, 0);
+        let moved = grid.move_box(start, BOX, Vec2::new(10.0 * TILE_SIZE, 0.0));
+        assert_near(moved, cell_center(4, 0) + Vec2::new(TILE_SIZE / 4.0, 0.0));
+    }
+}
@@ -0,0 +1,276 @@
+use bevy::asset::RenderAssetUsages;
+use bevy::image::ImageSampler;
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+
+use crate::camera::fit_projection;
+use crate::headless::HeadlessSolver;
+use crate::keybindings::{Action, Keybindings};
+use crate::lock::Locks;
+use crate::rules::{Rules, list_tilesets};
+use crate::stepping::{StepBudget, plan_steps};
+use crate::strategy::Strategy;
+use crate::theme::Theme;
+use crate::{GRID_H, GRID_W, Seed, Step, TILE_SIZE, grid_center, layers};
+
+/// Space between the two grids, in tiles.
+const GAP_TILES: f32 = 2.0;
+const UNCOLLAPSED_COLOR: Color = Color::srgb(0.15, 0.15, 0.15);
+const LABEL_FONT_SIZE: f32 = 16.0;
+
+/// What the second grid differs in. Cycled with `X`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum CompareMode {
+    #[default]
+    Off,
+    /// Same rules, the next seed.
+    OtherSeed,
+    /// Same seed, the next ruleset in `assets/tilesets`.
+    OtherRuleset,
+}
+
+/// Second map generated next to the main one with the same strategy, locks
+/// and step budget, so both fill in side by side.
+#[derive(Resource, Default)]
+struct Comparison {
+    mode: CompareMode,
+    solver: Option<HeadlessSolver>,
+    /// Ruleset of the second grid in [`CompareMode::OtherRuleset`].
+    other_rules: Option<Rules>,
+    /// Ruleset name the comparison was started for.
+    rules_name: String,
+    done: bool,
+}
+
+#[derive(Resource)]
+struct ComparisonImage(Handle<Image>);
+
+#[derive(Component)]
+struct ComparisonView;
+
+#[derive(Component)]
+struct ComparisonLabel;
+
+pub struct ComparisonPlugin;
+
+impl Plugin for ComparisonPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Comparison>()
+            .add_systems(Startup, spawn_view)
+            .add_systems(
+                Update,
+                (
+                    cycle_mode,
+                    restart_comparison,
+                    advance.after(plan_steps),
+                    fit_camera,
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Center of the second grid, to the right of the main one.
+fn comparison_center() -> Vec2 {
+    grid_center() + Vec2::X * (GRID_W as f32 + GAP_TILES) * TILE_SIZE
+}
+
+fn spawn_view(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    let mut image = Image::new_fill(
+        Extent3d {
+            width: GRID_W as u32,
+            height: GRID_H as u32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &UNCOLLAPSED_COLOR.to_srgba().to_u8_array(),
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    image.sampler = ImageSampler::nearest();
+    let handle = images.add(image);
+
+    let size = Vec2::new(GRID_W as f32, GRID_H as f32) * TILE_SIZE;
+    commands
+        .spawn((
+            ComparisonView,
+            Sprite {
+                image: handle.clone(),
+                custom_size: Some(size),
+                ..default()
+            },
+            Transform::from_translation(comparison_center().extend(layers::TILE)),
+            Visibility::Hidden,
+        ))
+        .with_child((
+            ComparisonLabel,
+            Text2d::default(),
+            TextFont::from_font_size(LABEL_FONT_SIZE),
+            Transform::from_xyz(0.0, size.y / 2.0 + TILE_SIZE, layers::LABEL),
+        ));
+    commands.insert_resource(ComparisonImage(handle));
+}
+
+fn cycle_mode(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keybindings: Res<Keybindings>,
+    mut comparison: ResMut<Comparison>,
+    mut views: Query<&mut Visibility, With<ComparisonView>>,
+) {
+    if !keybindings.just_pressed(Action::CycleComparison, &keyboard_input) {
+        return;
+    }
+    comparison.mode = match comparison.mode {
+        CompareMode::Off => CompareMode::OtherSeed,
+        CompareMode::OtherSeed => CompareMode::OtherRuleset,
+        CompareMode::OtherRuleset => CompareMode::Off,
+    };
+    for mut visibility in views.iter_mut() {
+        *visibility = if comparison.mode == CompareMode::Off {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+    }
+}
+
+/// The ruleset after the current one in file order that has a different
+/// name, wrapping around.
+fn next_ruleset(current: &str) -> Option<Rules> {
+    let rulesets: Vec<Rules> = list_tilesets()
+        .iter()
+        .filter_map(|path| Rules::load(path).ok())
+        .collect();
+    let position = rulesets.iter().position(|rules| rules.name == current);
+    let start = position.map_or(0, |i| i + 1);
+    (0..rulesets.len())
+        .map(|offset| &rulesets[(start + offset) % rulesets.len()])
+        .find(|rules| rules.name != current)
+        .cloned()
+}
+
+/// Starts the second map over whenever the main one would differ from it in
+/// anything but what is compared. Weight changes reach an other-seed
+/// comparison live, as they do the main map.
+fn restart_comparison(
+    mut comparison: ResMut<Comparison>,
+    seed: Res<Seed>,
+    rules: Res<Rules>,
+    strategy: Res<Strategy>,
+    locks: Res<Locks>,
+    mut labels: Query<&mut Text2d, With<ComparisonLabel>>,
+) {
+    if comparison.mode == CompareMode::Off {
+        if comparison.solver.is_some() {
+            comparison.solver = None;
+        }
+        return;
+    }
+    let ruleset_switched = comparison.rules_name != rules.name;
+    let restart = comparison.is_changed()
+        || ruleset_switched
+        || seed.is_changed()
+        || strategy.is_changed()
+        || locks.is_changed();
+    if !restart {
+        if rules.is_changed()
+            && comparison.mode == CompareMode::OtherSeed
+            && let Some(solver) = comparison.solver.as_mut()
+        {
+            solver.set_rules(rules.clone());
+        }
+        return;
+    }
+
+    let comparison = comparison.into_inner();
+    comparison.rules_name = rules.name.clone();
+    comparison.done = false;
+    let (solver, label) = match comparison.mode {
+        CompareMode::Off => return,
+        CompareMode::OtherSeed => {
+            let other_seed = seed.0.wrapping_add(1);
+            (
+                Some(HeadlessSolver::new(&rules, &strategy, &locks, other_seed)),
+                format!("seed {other_seed}"),
+            )
+        }
+        CompareMode::OtherRuleset => {
+            if ruleset_switched || comparison.other_rules.is_none() {
+                comparison.other_rules = next_ruleset(&rules.name);
+            }
+            match &comparison.other_rules {
+                Some(other) => (
+                    Some(HeadlessSolver::new(other, &strategy, &locks, seed.0)),
+                    format!("ruleset {}", other.name),
+                ),
+                None => (None, "no other ruleset in assets/tilesets".to_string()),
+            }
+        }
+    };
+    comparison.solver = solver;
+    for mut text in labels.iter_mut() {
+        text.0 = label.clone();
+    }
+}
+
+/// Runs as many steps as the main solver gets this frame and redraws the
+/// second grid.
+fn advance(
+    budget: Res<StepBudget>,
+    theme: Res<Theme>,
+    image: Res<ComparisonImage>,
+    mut images: ResMut<Assets<Image>>,
+    mut comparison: ResMut<Comparison>,
+) {
+    let comparison = comparison.bypass_change_detection();
+    let Some(solver) = comparison.solver.as_mut() else {
+        return;
+    };
+    let mut stepped = false;
+    if !comparison.done {
+        for _ in 0..budget.0 {
+            stepped = true;
+            if let (Step::Done, _) = solver.step() {
+                comparison.done = true;
+                break;
+            }
+        }
+    }
+    if !stepped && !theme.is_changed() {
+        return;
+    }
+    let Some(image) = images.get_mut(&image.0) else {
+        return;
+    };
+    // Image rows run top to bottom, grid rows bottom to top.
+    for (i, tile) in solver.grid().into_iter().enumerate() {
+        let color = tile.map_or(UNCOLLAPSED_COLOR, |tile| theme.color(tile));
+        let (x, y) = (i % GRID_W, i / GRID_W);
+        let _ = image.set_color_at(x as u32, (GRID_H - 1 - y) as u32, color);
+    }
+}
+
+/// Widens the view to both grids while comparing, and fits the camera when
+/// it's spawned.
+fn fit_camera(
+    comparison: Res<Comparison>,
+    added: Query<(), Added<Camera2d>>,
+    mut cameras: Query<(&mut Projection, &mut Transform), With<Camera2d>>,
+) {
+    if !comparison.is_changed() && added.is_empty() {
+        return;
+    }
+    let grid = Vec2::new(GRID_W as f32, GRID_H as f32) * TILE_SIZE;
+    let (size, center) = if comparison.mode == CompareMode::Off {
+        (grid, grid_center())
+    } else {
+        (
+            grid + Vec2::X * (grid.x + GAP_TILES * TILE_SIZE),
+            (grid_center() + comparison_center()) / 2.0,
+        )
+    };
+    for (mut projection, mut transform) in cameras.iter_mut() {
+        *projection = fit_projection(size);
+        transform.translation = center.extend(transform.translation.z);
+    }
+}
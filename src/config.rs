@@ -0,0 +1,247 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use serde::Deserialize;
+
+use crate::cli::Cli;
+use crate::day_night::DayNightCycle;
+use crate::display::DisplaySettings;
+use crate::keybindings::{Action, Binding, Keybindings};
+use crate::rules::Rules;
+use crate::speed::GenerationSpeed;
+use crate::{GRID_H, GRID_W, Seed};
+
+/// Seconds between checks of the config file for changes.
+const POLL_SECS: f32 = 1.0;
+
+/// Contents of the config file, `wfc.toml` in the working directory unless
+/// `--config` says otherwise. Every section is optional; command-line options
+/// take precedence at startup.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Replaces the saved display settings while set.
+    pub window: Option<DisplaySettings>,
+    pub grid: GridConfig,
+    /// Ruleset file, in the format of `assets/tilesets`.
+    pub tileset: Option<PathBuf>,
+    /// Collapses per second.
+    pub speed: Option<f32>,
+    /// Milliseconds the solver may take per frame, replacing `speed`.
+    pub frame_budget: Option<f32>,
+    pub day_night: DayNightConfig,
+    /// Per-action overrides of `assets/keybindings.ron`, e.g.
+    /// `Restart = [{ Key = "KeyR" }]`.
+    pub keybindings: HashMap<Action, Vec<Binding>>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GridConfig {
+    /// Only the fixed grid size is supported for now.
+    pub width: Option<usize>,
+    pub height: Option<usize>,
+    pub seed: Option<u64>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DayNightConfig {
+    /// Seconds for one pass through the palette.
+    pub period_secs: Option<f32>,
+    /// Tints in sRGB with alpha, e.g. `[[1.0, 0.6, 0.4, 0.25], ...]`.
+    pub palette: Option<Vec<[f32; 4]>>,
+}
+
+impl DayNightConfig {
+    /// Sets the parts of the cycle this config gives; the rest keep theirs.
+    fn apply(&self, cycle: &mut DayNightCycle) {
+        if let Some(period_secs) = self.period_secs {
+            cycle.period_secs = period_secs;
+        }
+        if let Some(palette) = &self.palette {
+            cycle.palette = palette
+                .iter()
+                .map(|&[r, g, b, a]| Color::srgba(r, g, b, a))
+                .collect();
+        }
+    }
+}
+
+impl Config {
+    /// The config at `path`, or the defaults if it is missing. Called before
+    /// the app's logging is set up, so errors go to stderr.
+    pub fn load(path: &Path) -> Self {
+        match Self::read(path) {
+            Ok(config) => config,
+            Err(err) => {
+                eprintln!("ignoring config {}: {err}", path.display());
+                Self::default()
+            }
+        }
+    }
+
+    fn read(path: &Path) -> Result<Self, String> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Self::default());
+            }
+            Err(err) => return Err(err.to_string()),
+        };
+        let config: Self = toml::from_str(&text).map_err(|err| err.to_string())?;
+        let grid = &config.grid;
+        if grid.width.is_some_and(|width| width != GRID_W)
+            || grid.height.is_some_and(|height| height != GRID_H)
+        {
+            return Err(format!("the grid is fixed at {GRID_W}x{GRID_H}"));
+        }
+        Ok(config)
+    }
+
+    /// Uses the config for the options not given on the command line.
+    pub fn fill_in(&self, cli: &mut Cli) {
+        cli.seed = cli.seed.or(self.grid.seed);
+        cli.frame_budget = cli.frame_budget.or(self.frame_budget);
+        if cli.tileset.is_none() {
+            cli.tileset.clone_from(&self.tileset);
+        }
+    }
+
+    /// Key bindings from the bindings file with this config's overrides.
+    fn keybindings(&self) -> Keybindings {
+        let mut keybindings = Keybindings::load();
+        keybindings.extend(self.keybindings.clone());
+        keybindings
+    }
+}
+
+/// The config file and what was last read from it, so a change only
+/// reapplies the sections that differ.
+#[derive(Resource)]
+pub struct LoadedConfig {
+    path: PathBuf,
+    config: Config,
+    modified: Option<SystemTime>,
+    poll: Timer,
+}
+
+impl LoadedConfig {
+    pub fn new(path: PathBuf, config: Config) -> Self {
+        Self {
+            modified: modified(&path),
+            path,
+            config,
+            poll: Timer::from_seconds(POLL_SECS, TimerMode::Repeating),
+        }
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+}
+
+pub struct ConfigPlugin;
+
+impl Plugin for ConfigPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, apply_startup_config)
+            .add_systems(Update, reload_config);
+    }
+}
+
+/// Applies the sections that aren't already part of the app's setup.
+fn apply_startup_config(
+    loaded: Res<LoadedConfig>,
+    mut speed: ResMut<GenerationSpeed>,
+    mut day_night: ResMut<DayNightCycle>,
+    mut keybindings: ResMut<Keybindings>,
+) {
+    if let Some(collapses_per_second) = loaded.config.speed {
+        speed.set(collapses_per_second);
+    }
+    loaded.config.day_night.apply(&mut day_night);
+    if !loaded.config.keybindings.is_empty() {
+        *keybindings = loaded.config.keybindings();
+    }
+}
+
+/// Rereads the config file when it changes and applies the sections that
+/// differ from before. A new seed or tileset regenerates the map; a file that
+/// doesn't parse is ignored until it's fixed.
+#[allow(clippy::too_many_arguments)]
+fn reload_config(
+    time: Res<Time>,
+    mut loaded: ResMut<LoadedConfig>,
+    mut settings: ResMut<DisplaySettings>,
+    mut seed: ResMut<Seed>,
+    mut rules: ResMut<Rules>,
+    mut speed: ResMut<GenerationSpeed>,
+    mut day_night: ResMut<DayNightCycle>,
+    mut keybindings: ResMut<Keybindings>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if !loaded.poll.tick(time.delta()).just_finished() {
+        return;
+    }
+    let modified = modified(&loaded.path);
+    if modified == loaded.modified {
+        return;
+    }
+    loaded.modified = modified;
+    let config = match Config::read(&loaded.path) {
+        Ok(config) => config,
+        Err(err) => {
+            warn!("ignoring changes to {}: {err}", loaded.path.display());
+            return;
+        }
+    };
+    info!("reloading {}", loaded.path.display());
+    let old = &loaded.config;
+
+    if config.window != old.window
+        && let Some(window_settings) = &config.window
+    {
+        *settings = window_settings.clone();
+        for mut window in windows.iter_mut() {
+            settings.apply(&mut window);
+        }
+    }
+    if config.speed != old.speed
+        && let Some(collapses_per_second) = config.speed
+    {
+        speed.set(collapses_per_second);
+    }
+    if config.frame_budget != old.frame_budget {
+        speed.set_frame_budget(config.frame_budget);
+    }
+    if config.day_night != old.day_night {
+        config.day_night.apply(&mut day_night);
+    }
+    if config.keybindings != old.keybindings {
+        *keybindings = config.keybindings();
+    }
+    if config.tileset != old.tileset
+        && let Some(path) = &config.tileset
+    {
+        match Rules::load(path) {
+            Ok(loaded_rules) => {
+                info!("rules: {}", loaded_rules.name);
+                *rules = loaded_rules;
+                seed.set_changed();
+            }
+            Err(err) => warn!("could not load {}: {err}", path.display()),
+        }
+    }
+    if config.grid.seed != old.grid.seed
+        && let Some(new_seed) = config.grid.seed
+    {
+        seed.0 = new_seed;
+    }
+    loaded.config = config;
+}
@@ -0,0 +1,75 @@
+use bevy::prelude::*;
+
+use crate::{TILE_SIZE, collapse_step, layers};
+
+const FLASH_SECONDS: f32 = 1.0;
+const CONTRADICTION_COLOR: Color = Color::srgb(0.9, 0.1, 0.1);
+
+/// Marks a cell whose domain emptied. The cell flashes red, then keeps a red
+/// outline until it is collapsed to a tile consistent with its neighbors.
+#[derive(Component)]
+pub struct Contradicted {
+    flash: Timer,
+}
+
+impl Default for Contradicted {
+    fn default() -> Self {
+        Self {
+            flash: Timer::from_seconds(FLASH_SECONDS, TimerMode::Once),
+        }
+    }
+}
+
+#[derive(Component)]
+struct ContradictionFlash;
+
+pub struct ContradictionPlugin;
+
+impl Plugin for ContradictionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (spawn_flashes, fade_flashes, draw_outlines)
+                .chain()
+                .after(collapse_step),
+        );
+    }
+}
+
+fn spawn_flashes(mut commands: Commands, marked: Query<Entity, Added<Contradicted>>) {
+    for entity in marked.iter() {
+        commands.entity(entity).with_child((
+            ContradictionFlash,
+            Sprite::from_color(CONTRADICTION_COLOR, Vec2::splat(TILE_SIZE)),
+            Transform::from_xyz(0.0, 0.0, layers::FLASH),
+        ));
+    }
+}
+
+fn fade_flashes(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut marked: Query<&mut Contradicted>,
+    mut flashes: Query<(Entity, &ChildOf, &mut Sprite), With<ContradictionFlash>>,
+) {
+    for (flash_entity, child_of, mut sprite) in flashes.iter_mut() {
+        // The marker is removed once the cell is repaired.
+        let Ok(mut contradicted) = marked.get_mut(child_of.parent()) else {
+            commands.entity(flash_entity).despawn();
+            continue;
+        };
+
+        contradicted.flash.tick(time.delta());
+        sprite.color = CONTRADICTION_COLOR.with_alpha(contradicted.flash.fraction_remaining());
+    }
+}
+
+fn draw_outlines(mut gizmos: Gizmos, marked: Query<&GlobalTransform, With<Contradicted>>) {
+    for transform in marked.iter() {
+        gizmos.rect_2d(
+            Isometry2d::from_translation(transform.translation().truncate()),
+            Vec2::splat(TILE_SIZE - 1.0),
+            CONTRADICTION_COLOR,
+        );
+    }
+}
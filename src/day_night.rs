@@ -0,0 +1,103 @@
+use bevy::prelude::*;
+
+use crate::keybindings::{Action, Keybindings};
+use crate::{GRID_H, GRID_W, TILE_SIZE, grid_center, layers};
+
+/// Ambient tint blended over the map, cycling through `palette` once every
+/// `period_secs`. Each palette entry's alpha is how strongly it tints.
+/// Toggled with `K`; `[day_night]` in the config file can replace both.
+#[derive(Resource)]
+pub struct DayNightCycle {
+    pub enabled: bool,
+    pub period_secs: f32,
+    pub palette: Vec<Color>,
+    elapsed: f32,
+}
+
+impl Default for DayNightCycle {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            period_secs: 60.0,
+            palette: vec![
+                // Dawn, day, dusk, night.
+                Color::srgba(1.0, 0.6, 0.4, 0.25),
+                Color::srgba(1.0, 1.0, 0.9, 0.0),
+                Color::srgba(0.9, 0.4, 0.2, 0.3),
+                Color::srgba(0.05, 0.05, 0.25, 0.55),
+            ],
+            elapsed: 0.0,
+        }
+    }
+}
+
+impl DayNightCycle {
+    fn current_tint(&self) -> Color {
+        let Some(&first) = self.palette.first() else {
+            return Color::NONE;
+        };
+        if self.palette.len() == 1 || self.period_secs <= 0.0 {
+            return first;
+        }
+
+        let phase = (self.elapsed / self.period_secs).fract() * self.palette.len() as f32;
+        let from = phase as usize % self.palette.len();
+        let to = (from + 1) % self.palette.len();
+        self.palette[from].mix(&self.palette[to], phase.fract())
+    }
+}
+
+#[derive(Component)]
+struct TintOverlay;
+
+pub struct DayNightPlugin;
+
+impl Plugin for DayNightPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DayNightCycle>()
+            .add_systems(Startup, spawn_overlay)
+            .add_systems(Update, (toggle_cycle, advance_cycle).chain());
+    }
+}
+
+fn spawn_overlay(mut commands: Commands) {
+    commands.spawn((
+        TintOverlay,
+        Sprite::from_color(
+            Color::NONE,
+            Vec2::new(GRID_W as f32, GRID_H as f32) * TILE_SIZE,
+        ),
+        Transform::from_translation(grid_center().extend(layers::TINT)),
+        Visibility::Hidden,
+    ));
+}
+
+fn toggle_cycle(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keybindings: Res<Keybindings>,
+    mut cycle: ResMut<DayNightCycle>,
+) {
+    if keybindings.just_pressed(Action::ToggleDayNight, &keyboard_input) {
+        cycle.enabled = !cycle.enabled;
+    }
+}
+
+fn advance_cycle(
+    time: Res<Time>,
+    mut cycle: ResMut<DayNightCycle>,
+    mut overlays: Query<(&mut Sprite, &mut Visibility), With<TintOverlay>>,
+) {
+    if cycle.enabled {
+        cycle.elapsed += time.delta_secs();
+    }
+    let tint = cycle.current_tint();
+
+    for (mut sprite, mut visibility) in overlays.iter_mut() {
+        *visibility = if cycle.enabled {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        sprite.color = tint;
+    }
+}
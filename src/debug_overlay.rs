@@ -0,0 +1,87 @@
+use bevy::prelude::*;
+
+use crate::keybindings::{Action, Keybindings};
+use crate::{GRID_H, GRID_W, TILE_SIZE, cell_center, grid_center, layers};
+
+const GRID_COLOR: Color = Color::srgba(0.0, 0.0, 0.0, 0.5);
+const LABEL_FONT_SIZE: f32 = 6.0;
+
+/// Debug overlay state. `G` toggles cell borders, `C` toggles coordinates.
+#[derive(Resource, Default)]
+pub struct DebugOverlay {
+    pub grid: bool,
+    pub coordinates: bool,
+}
+
+#[derive(Component)]
+struct CoordinateLabel;
+
+pub struct DebugOverlayPlugin;
+
+impl Plugin for DebugOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DebugOverlay>().add_systems(
+            Update,
+            (
+                toggle_overlay,
+                draw_grid.run_if(|overlay: Res<DebugOverlay>| overlay.grid),
+                sync_coordinate_labels.run_if(resource_changed::<DebugOverlay>),
+            )
+                .chain(),
+        );
+    }
+}
+
+fn toggle_overlay(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keybindings: Res<Keybindings>,
+    mut overlay: ResMut<DebugOverlay>,
+) {
+    if keybindings.just_pressed(Action::ToggleGrid, &keyboard_input) {
+        overlay.grid = !overlay.grid;
+    }
+    if keybindings.just_pressed(Action::ToggleCoordinates, &keyboard_input) {
+        overlay.coordinates = !overlay.coordinates;
+    }
+}
+
+fn draw_grid(mut gizmos: Gizmos) {
+    gizmos
+        .grid_2d(
+            Isometry2d::from_translation(grid_center()),
+            UVec2::new(GRID_W as u32, GRID_H as u32),
+            Vec2::splat(TILE_SIZE),
+            GRID_COLOR,
+        )
+        .outer_edges();
+}
+
+fn sync_coordinate_labels(
+    mut commands: Commands,
+    overlay: Res<DebugOverlay>,
+    labels: Query<Entity, With<CoordinateLabel>>,
+) {
+    let shown = !labels.is_empty();
+    if overlay.coordinates == shown {
+        return;
+    }
+
+    if !overlay.coordinates {
+        for label in labels.iter() {
+            commands.entity(label).despawn();
+        }
+        return;
+    }
+
+    for y in 0..GRID_H {
+        for x in 0..GRID_W {
+            commands.spawn((
+                CoordinateLabel,
+                Text2d::new(format!("{x},{y}")),
+                TextFont::from_font_size(LABEL_FONT_SIZE),
+                TextColor(Color::BLACK),
+                Transform::from_translation(cell_center(x, y).extend(layers::LABEL)),
+            ));
+        }
+    }
+}
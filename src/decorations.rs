@@ -0,0 +1,99 @@
+use bevy::prelude::*;
+
+use crate::generation::GenerationState;
+use crate::variation::{cell_hash, unit};
+use crate::{
+    GRID_H, GRID_W, Seed, TILE_SIZE, TileType, WfcGrid, cell_center, layers, neighbor_coords,
+};
+
+/// Keeps decoration rolls independent of the tile variation of the same cell.
+const DECORATION_SALT: u64 = 0x6465_636f_7261_7465;
+/// Furthest a decoration sits from its cell's center, as a fraction of a cell.
+const MAX_JITTER: f32 = 0.25;
+
+/// Something placed on a finished map for looks. It doesn't block movement.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Decoration {
+    Tree,
+    Shell,
+    Reeds,
+}
+
+impl Decoration {
+    /// Chance of a cell with the tile getting the decoration. Land next to
+    /// water grows reeds instead of anything else.
+    fn roll(tile: TileType, by_water: bool) -> Option<(Decoration, f32)> {
+        match tile {
+            TileType::Grass | TileType::Sand if by_water => Some((Decoration::Reeds, 0.35)),
+            TileType::Grass => Some((Decoration::Tree, 0.15)),
+            TileType::Sand => Some((Decoration::Shell, 0.06)),
+            _ => None,
+        }
+    }
+
+    fn color(self) -> Color {
+        match self {
+            Decoration::Tree => Color::srgb(0.1, 0.35, 0.12),
+            Decoration::Shell => Color::srgb(0.98, 0.86, 0.8),
+            Decoration::Reeds => Color::srgb(0.45, 0.5, 0.2),
+        }
+    }
+
+    /// As a fraction of a cell.
+    fn size(self) -> Vec2 {
+        match self {
+            Decoration::Tree => Vec2::splat(0.5),
+            Decoration::Shell => Vec2::splat(0.2),
+            Decoration::Reeds => Vec2::new(0.12, 0.5),
+        }
+    }
+}
+
+pub struct DecorationPlugin;
+
+impl Plugin for DecorationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GenerationState::Complete), spawn_decorations)
+            .add_systems(OnExit(GenerationState::Complete), despawn_decorations);
+    }
+}
+
+/// Rolls each cell's decoration from the seed and its coordinate, so the
+/// same map is always decorated the same way, and jitters it within the
+/// cell.
+fn spawn_decorations(mut commands: Commands, seed: Res<Seed>, grid: Res<WfcGrid>) {
+    let tiles = grid.collapsed_grid();
+    let tile = |x: usize, y: usize| tiles[y * GRID_W + x];
+    for (x, y, _) in grid.iter() {
+        let Some(cell_tile) = tile(x, y) else {
+            continue;
+        };
+        let by_water = neighbor_coords(x, y).any(|(nx, ny)| tile(nx, ny) == Some(TileType::Water));
+        let Some((decoration, density)) = Decoration::roll(cell_tile, by_water) else {
+            continue;
+        };
+        let hash = cell_hash(seed.0, DECORATION_SALT, x, y);
+        if unit(hash) >= density {
+            continue;
+        }
+        let jitter = Vec2::new(unit(hash << 24), unit(hash << 48)) * 2.0 - 1.0;
+        let position = cell_center(x, y) + jitter * MAX_JITTER * TILE_SIZE;
+        let height = GRID_H as f32 * TILE_SIZE;
+        let depth = (0.5 - (position.y - cell_center(0, 0).y) / height) * layers::DECORATION_SPREAD;
+        commands.spawn((
+            decoration,
+            Sprite {
+                color: decoration.color(),
+                custom_size: Some(decoration.size() * TILE_SIZE),
+                ..default()
+            },
+            Transform::from_translation(position.extend(layers::DECORATION + depth)),
+        ));
+    }
+}
+
+fn despawn_decorations(mut commands: Commands, decorations: Query<Entity, With<Decoration>>) {
+    for decoration in decorations.iter() {
+        commands.entity(decoration).despawn();
+    }
+}
@@ -0,0 +1,58 @@
+use bevy::diagnostic::{
+    Diagnostic, DiagnosticPath, Diagnostics, LogDiagnosticsPlugin, RegisterDiagnostic,
+};
+use bevy::prelude::*;
+
+use crate::collapse_step;
+use crate::inspector::Provenance;
+
+pub const COLLAPSES: DiagnosticPath = DiagnosticPath::const_new("wfc/collapses_per_second");
+/// Tiles eliminated from domains by propagation.
+pub const DOMAIN_REDUCTIONS: DiagnosticPath =
+    DiagnosticPath::const_new("wfc/domain_reductions_per_second");
+/// Time the solver took this frame, measured by `collapse_step`.
+pub const SOLVER_TIME: DiagnosticPath = DiagnosticPath::const_new("wfc/solver_time");
+
+/// Solver rates as Bevy diagnostics, next to the frame time and entity
+/// counts, for the diagnostics store, log and any inspector reading them.
+/// With `log`, as set by `--log-diagnostics`, they're logged every second.
+pub struct SolverDiagnosticsPlugin {
+    pub log: bool,
+}
+
+impl Plugin for SolverDiagnosticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_diagnostic(Diagnostic::new(COLLAPSES).with_suffix("/s"))
+            .register_diagnostic(Diagnostic::new(DOMAIN_REDUCTIONS).with_suffix("/s"))
+            .register_diagnostic(Diagnostic::new(SOLVER_TIME).with_suffix("ms"))
+            .add_systems(Update, measure_solver.after(collapse_step));
+        if self.log {
+            app.add_plugins(LogDiagnosticsPlugin::filtered(
+                [COLLAPSES, DOMAIN_REDUCTIONS].into_iter().collect(),
+            ));
+        }
+    }
+}
+
+/// Measures from the totals the elimination log keeps for each map.
+fn measure_solver(
+    time: Res<Time>,
+    provenance: Res<Provenance>,
+    mut last: Local<(usize, usize)>,
+    mut diagnostics: Diagnostics,
+) {
+    let totals = (provenance.collapses(), provenance.eliminated());
+    // A new map starts its log, and so its totals, over.
+    let since = if provenance.is_added() { (0, 0) } else { *last };
+    *last = totals;
+    let secs = time.delta_secs_f64();
+    if secs <= 0.0 {
+        return;
+    }
+    diagnostics.add_measurement(&COLLAPSES, || {
+        totals.0.saturating_sub(since.0) as f64 / secs
+    });
+    diagnostics.add_measurement(&DOMAIN_REDUCTIONS, || {
+        totals.1.saturating_sub(since.1) as f64 / secs
+    });
+}
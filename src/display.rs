@@ -0,0 +1,158 @@
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use bevy::window::{MonitorSelection, PresentMode, PrimaryWindow, WindowMode, WindowResolution};
+use serde::{Deserialize, Serialize};
+
+use crate::asset_files::exe_relative_dir;
+use crate::keybindings::{Action, Keybindings};
+
+/// Settings file next to the executable, rewritten whenever a setting changes.
+const SETTINGS_FILE: &str = "settings.ron";
+/// Window sizes cycled through, in logical pixels.
+const RESOLUTIONS: [(u32, u32); 4] = [(1280, 720), (1600, 900), (1920, 1080), (2560, 1440)];
+
+/// Window settings applied at startup and changed with `F11` (fullscreen),
+/// `F10` (vsync) and `F9` (window size).
+#[derive(Resource, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DisplaySettings {
+    pub fullscreen: bool,
+    pub vsync: bool,
+    /// Windowed size in logical pixels.
+    pub resolution: (u32, u32),
+}
+
+impl Default for DisplaySettings {
+    fn default() -> Self {
+        Self {
+            fullscreen: false,
+            vsync: true,
+            resolution: RESOLUTIONS[0],
+        }
+    }
+}
+
+impl DisplaySettings {
+    /// Settings from the settings file, or the defaults if it is missing or
+    /// doesn't parse. The binary calls it before the app's logging is set
+    /// up, so errors go to stderr.
+    pub fn load() -> Self {
+        let path = settings_path();
+        let Ok(text) = std::fs::read_to_string(&path) else {
+            return Self::default();
+        };
+        ron::from_str(&text).unwrap_or_else(|err| {
+            eprintln!(
+                "using default display settings, could not parse {}: {err}",
+                path.display()
+            );
+            Self::default()
+        })
+    }
+
+    fn save(&self) {
+        let path = settings_path();
+        let result = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|err| err.to_string())
+            .and_then(|text| std::fs::write(&path, text).map_err(|err| err.to_string()));
+        if let Err(err) = result {
+            warn!("could not save {}: {err}", path.display());
+        }
+    }
+
+    /// Primary window configured with these settings.
+    pub fn window(&self) -> Window {
+        let mut window = Window {
+            resolution: WindowResolution::new(self.resolution.0, self.resolution.1),
+            ..default()
+        };
+        self.apply(&mut window);
+        window
+    }
+
+    pub fn apply(&self, window: &mut Window) {
+        window.mode = if self.fullscreen {
+            WindowMode::BorderlessFullscreen(MonitorSelection::Current)
+        } else {
+            WindowMode::Windowed
+        };
+        window.present_mode = if self.vsync {
+            PresentMode::AutoVsync
+        } else {
+            PresentMode::AutoNoVsync
+        };
+        let (width, height) = self.resolution;
+        if !self.fullscreen
+            && (window.resolution.width(), window.resolution.height())
+                != (width as f32, height as f32)
+        {
+            window.resolution.set(width as f32, height as f32);
+        }
+    }
+}
+
+/// The settings file next to the executable.
+fn settings_path() -> PathBuf {
+    exe_relative_dir(SETTINGS_FILE)
+}
+
+pub struct DisplaySettingsPlugin;
+
+impl Plugin for DisplaySettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Startup,
+            load_settings.run_if(not(resource_exists::<DisplaySettings>)),
+        )
+        .add_systems(Update, change_settings);
+    }
+}
+
+/// Reads the settings file for apps that didn't start with settings of their
+/// own, and applies it to the window.
+fn load_settings(mut commands: Commands, mut windows: Query<&mut Window, With<PrimaryWindow>>) {
+    let settings = DisplaySettings::load();
+    for mut window in windows.iter_mut() {
+        settings.apply(&mut window);
+    }
+    commands.insert_resource(settings);
+}
+
+fn change_settings(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keybindings: Res<Keybindings>,
+    mut settings: ResMut<DisplaySettings>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    let mut changed = false;
+    if keybindings.just_pressed(Action::ToggleFullscreen, &keyboard_input) {
+        settings.fullscreen = !settings.fullscreen;
+        changed = true;
+    }
+    if keybindings.just_pressed(Action::ToggleVsync, &keyboard_input) {
+        settings.vsync = !settings.vsync;
+        info!("vsync {}", if settings.vsync { "on" } else { "off" });
+        changed = true;
+    }
+    if keybindings.just_pressed(Action::CycleResolution, &keyboard_input) {
+        let next = RESOLUTIONS
+            .iter()
+            .position(|&resolution| resolution == settings.resolution)
+            .map_or(0, |i| (i + 1) % RESOLUTIONS.len());
+        settings.resolution = RESOLUTIONS[next];
+        info!(
+            "window size {}x{}",
+            settings.resolution.0, settings.resolution.1
+        );
+        changed = true;
+    }
+    if !changed {
+        return;
+    }
+
+    for mut window in windows.iter_mut() {
+        settings.apply(&mut window);
+    }
+    settings.save();
+}
@@ -0,0 +1,215 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use crate::rules::Rules;
+use crate::strategy::Heuristic;
+use crate::{Cell, GRID_W, TileType};
+
+/// Uncollapsed cells in the order the heuristic observes them, so picking the
+/// next cell is a pop rather than a pass over the grid. A cell is pushed
+/// again whenever its domain narrows; entries it has outgrown are dropped
+/// when they reach the top. [`Heuristic::Random`] has no order and doesn't
+/// use the queue.
+#[derive(Clone, Default)]
+pub struct CandidateQueue {
+    heap: BinaryHeap<Entry>,
+    /// Heuristic and tile weights the keys were computed with, or `None`
+    /// until the queue is built.
+    keyed: Option<(Heuristic, Weights)>,
+}
+
+type Weights = [f32; TileType::ALL.len()];
+
+/// A cell and its key when pushed. Ordered so the heap's top is the lowest
+/// key, ties going to the lowest index like the row-by-row scans before it.
+#[derive(Clone, Copy)]
+struct Entry {
+    key: f32,
+    index: usize,
+}
+
+impl Ord for Entry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .key
+            .total_cmp(&self.key)
+            .then(other.index.cmp(&self.index))
+    }
+}
+
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Entry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Entry {}
+
+impl CandidateQueue {
+    /// Drops every entry so the next [`pop`](Self::pop) rebuilds the queue.
+    /// Needed whenever cells change without [`update`](Self::update).
+    pub fn invalidate(&mut self) {
+        self.heap.clear();
+        self.keyed = None;
+    }
+
+    /// Entries on the queue, counting the ones their cells have outgrown.
+    pub fn entries(&self) -> usize {
+        self.heap.len()
+    }
+
+    /// Queues the cell at `index` again after its domain changed.
+    pub fn update(&mut self, index: usize, cell: &Cell) {
+        if let Some((heuristic, weights)) = &self.keyed
+            && let Some(key) = key(*heuristic, weights, cell)
+        {
+            self.heap.push(Entry { key, index });
+        }
+    }
+
+    /// The cell the heuristic observes next, taken off the queue. Rebuilt
+    /// first if the heuristic or the rules' weights changed.
+    pub fn pop(
+        &mut self,
+        cells: &[Cell],
+        rules: &Rules,
+        heuristic: Heuristic,
+    ) -> Option<(usize, usize)> {
+        let weights = TileType::ALL.map(|tile| rules.weight(tile));
+        if self.keyed != Some((heuristic, weights)) {
+            // Refilled in place, so the pushes propagation makes later reuse
+            // the capacity of earlier maps.
+            self.heap.clear();
+            self.heap
+                .extend(cells.iter().enumerate().filter_map(|(index, cell)| {
+                    key(heuristic, &weights, cell).map(|key| Entry { key, index })
+                }));
+            self.keyed = Some((heuristic, weights));
+        }
+        while let Some(entry) = self.heap.pop() {
+            let current = key(heuristic, &weights, &cells[entry.index]);
+            if current.is_some_and(|key| key.total_cmp(&entry.key).is_eq()) {
+                return Some((entry.index % GRID_W, entry.index / GRID_W));
+            }
+        }
+        None
+    }
+}
+
+/// Where `cell` goes in the queue, or `None` if it can't be observed.
+fn key(heuristic: Heuristic, weights: &Weights, cell: &Cell) -> Option<f32> {
+    if cell.collapsed || cell.possible.is_empty() {
+        return None;
+    }
+    Some(match heuristic {
+        Heuristic::MinRemaining => cell.possible.len() as f32,
+        Heuristic::Entropy => shannon_entropy(weights, &cell.possible),
+        // Every cell ties, so the index alone orders them row by row.
+        Heuristic::Scanline | Heuristic::Random => 0.0,
+    })
+}
+
+/// Shannon entropy of a domain under the tile weights.
+fn shannon_entropy(weights: &Weights, possible: &[TileType]) -> f32 {
+    let total: f32 = possible.iter().map(|tile| weights[tile.index()]).sum();
+    if total <= 0.0 {
+        return 0.0;
+    }
+    let weighted_log: f32 = possible
+        .iter()
+        .map(|tile| weights[tile.index()])
+        .filter(|&weight| weight > 0.0)
+        .map(|weight| weight * weight.ln())
+        .sum();
+    total.ln() - weighted_log / total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn open_cells() -> Vec<Cell> {
+        let cell = Cell {
+            possible: vec![TileType::Sand, TileType::Water, TileType::Grass],
+            collapsed: false,
+        };
+        vec![cell; GRID_W * 4]
+    }
+
+    #[test]
+    fn ties_go_to_the_first_cell_in_row_order() {
+        let cells = open_cells();
+        let rules = Rules::default();
+        let mut queue = CandidateQueue::default();
+        for expected in [(0, 0), (1, 0), (2, 0)] {
+            assert_eq!(
+                queue.pop(&cells, &rules, Heuristic::MinRemaining),
+                Some(expected)
+            );
+        }
+    }
+
+    #[test]
+    fn narrowed_cells_jump_the_queue() {
+        let mut cells = open_cells();
+        let rules = Rules::default();
+        let mut queue = CandidateQueue::default();
+        assert_eq!(
+            queue.pop(&cells, &rules, Heuristic::MinRemaining),
+            Some((0, 0))
+        );
+        let narrowed = GRID_W + 5;
+        cells[narrowed].possible.truncate(1);
+        queue.update(narrowed, &cells[narrowed]);
+        assert_eq!(
+            queue.pop(&cells, &rules, Heuristic::MinRemaining),
+            Some((5, 1))
+        );
+    }
+
+    #[test]
+    fn stale_and_collapsed_entries_are_skipped() {
+        let mut cells = open_cells();
+        let rules = Rules::default();
+        let mut queue = CandidateQueue::default();
+        assert_eq!(
+            queue.pop(&cells, &rules, Heuristic::MinRemaining),
+            Some((0, 0))
+        );
+        // Cell 1 keeps its old entry after narrowing again without an
+        // update, and cell 2 collapses.
+        cells[1].possible.truncate(2);
+        queue.update(1, &cells[1]);
+        cells[1].possible.truncate(1);
+        cells[2].collapse(TileType::Sand);
+        let before = queue.entries();
+        assert_eq!(
+            queue.pop(&cells, &rules, Heuristic::MinRemaining),
+            Some((3, 0))
+        );
+        // Both of cell 1's entries and cell 2's were dropped on the way.
+        assert_eq!(queue.entries(), before - 4);
+    }
+
+    #[test]
+    fn unobservable_cells_are_never_popped() {
+        let mut cells = open_cells();
+        for cell in &mut cells {
+            cell.collapse(TileType::Grass);
+        }
+        let last = cells.len() - 1;
+        cells[last].possible.clear();
+        cells[last].collapsed = false;
+        let mut queue = CandidateQueue::default();
+        assert_eq!(
+            queue.pop(&cells, &Rules::default(), Heuristic::Entropy),
+            None
+        );
+    }
+}
@@ -0,0 +1,58 @@
+use bevy::prelude::*;
+
+use crate::{TileSet, TileType};
+
+/// A cell the solver observed and collapsed to `tile`.
+#[derive(Message, Clone, Copy, Debug)]
+pub struct CellCollapsed {
+    pub x: usize,
+    pub y: usize,
+    pub tile: TileType,
+}
+
+/// Tiles propagation ruled out of a cell's domain.
+#[derive(Message, Clone, Copy, Debug)]
+pub struct DomainReduced {
+    pub x: usize,
+    pub y: usize,
+    pub removed: TileSet,
+}
+
+/// A cell left without a consistent tile, marked as contradicted. Rewinds
+/// by backtracking don't count; they leave nothing in the map.
+#[derive(Message, Clone, Copy, Debug)]
+pub struct Contradiction {
+    pub x: usize,
+    pub y: usize,
+}
+
+/// The map for `seed` has nothing left to observe. `failed` if it has
+/// contradictions left in it.
+#[derive(Message, Clone, Copy, Debug)]
+pub struct GenerationComplete {
+    pub seed: u64,
+    pub failed: bool,
+}
+
+/// A spawn point picked on the completed map for `seed`, one message per
+/// point. Its marker entity carries a [`SpawnPoint`](crate::spawn_points::SpawnPoint).
+#[derive(Message, Clone, Copy, Debug)]
+pub struct SpawnPointChosen {
+    pub seed: u64,
+    pub x: usize,
+    pub y: usize,
+}
+
+/// What the solver does, as messages for rendering, audio, gameplay and
+/// logging to read without looking at the grid.
+pub struct SolverEventsPlugin;
+
+impl Plugin for SolverEventsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<CellCollapsed>()
+            .add_message::<DomainReduced>()
+            .add_message::<Contradiction>()
+            .add_message::<GenerationComplete>()
+            .add_message::<SpawnPointChosen>();
+    }
+}
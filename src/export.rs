@@ -0,0 +1,536 @@
+use std::path::{Path, PathBuf};
+
+use bevy::asset::RenderAssetUsages;
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use serde::Serialize;
+
+use crate::asset_files::{exe_relative_dir, unix_millis};
+use crate::autotile::{VARIANT_PX, blob_shade};
+use crate::coastline::{COASTLINE_COLOR, Coastline};
+use crate::keybindings::{Action, Keybindings};
+use crate::rules::Rules;
+use crate::theme::Theme;
+use crate::{GRID_H, GRID_W, Seed, TileType, WfcGrid};
+
+/// Side of a cell in SVG exports, in SVG user units.
+const SVG_CELL: f32 = 16.0;
+const SVG_COASTLINE_WIDTH: f32 = 2.0;
+
+/// Side of a cell in the flat PNGs of the map server and headless runs.
+const MAP_PNG_CELL_PX: u32 = 8;
+
+/// How each cell is drawn in an exported PNG.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ExportStyle {
+    /// A flat square of this many pixels.
+    Pixels(u32),
+    /// The cell's blob autotile variant, as drawn in the app.
+    Tiles,
+}
+
+/// Cycled with `Ctrl+E`, starting with one pixel per cell.
+const STYLES: [ExportStyle; 4] = [
+    ExportStyle::Pixels(1),
+    ExportStyle::Pixels(4),
+    ExportStyle::Pixels(16),
+    ExportStyle::Tiles,
+];
+
+impl ExportStyle {
+    fn cell_px(self) -> u32 {
+        match self {
+            ExportStyle::Pixels(px) => px,
+            ExportStyle::Tiles => VARIANT_PX,
+        }
+    }
+}
+
+/// JSON map export, described by `docs/map.schema.json`. Bump the version
+/// whenever the format changes incompatibly.
+#[derive(Serialize)]
+struct JsonMap {
+    format: &'static str,
+    version: u32,
+    width: usize,
+    height: usize,
+    seed: u64,
+    rules: String,
+    tiles: Vec<JsonTile>,
+    /// Tile ids by row from the top, `None` for cells not collapsed yet.
+    cells: Vec<Vec<Option<usize>>>,
+}
+
+#[derive(Serialize)]
+struct JsonTile {
+    id: usize,
+    name: String,
+    walkable: bool,
+    tags: &'static [&'static str],
+    /// Theme color as `#RRGGBBAA`.
+    color: String,
+}
+
+const JSON_FORMAT: &str = "wfc-map";
+const JSON_VERSION: u32 = 1;
+
+/// Index into [`STYLES`] used by the next export.
+#[derive(Resource, Default)]
+struct ExportSettings(usize);
+
+pub struct ExportPlugin;
+
+impl Plugin for ExportPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ExportSettings>().add_systems(
+            Update,
+            (
+                cycle_style,
+                export_png,
+                export_tiled,
+                export_json,
+                export_csv,
+                export_svg,
+                export_graph,
+            )
+                .chain(),
+        );
+    }
+}
+
+/// `<kind>-<unix millis>` in the executable's directory, falling back to the
+/// working directory if that can't be determined. Exports add an extension.
+pub fn export_stem(kind: &str) -> PathBuf {
+    exe_relative_dir(&format!("{kind}-{}", unix_millis()))
+}
+
+fn cycle_style(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keybindings: Res<Keybindings>,
+    mut settings: ResMut<ExportSettings>,
+) {
+    if keybindings.just_pressed(Action::CycleExportStyle, &keyboard_input) {
+        settings.0 = (settings.0 + 1) % STYLES.len();
+        info!("exporting maps as {:?}", STYLES[settings.0]);
+    }
+}
+
+/// Writes the collapsed cells as a PNG, themed like the app. Cells not
+/// collapsed yet stay transparent.
+fn export_png(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keybindings: Res<Keybindings>,
+    settings: Res<ExportSettings>,
+    theme: Res<Theme>,
+    grid: Res<WfcGrid>,
+) {
+    if !keybindings.just_pressed(Action::ExportPng, &keyboard_input) {
+        return;
+    }
+    let style = STYLES[settings.0];
+    let cell_px = style.cell_px();
+    let mut image = Image::new_fill(
+        Extent3d {
+            width: GRID_W as u32 * cell_px,
+            height: GRID_H as u32 * cell_px,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 0],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+
+    let grid = grid.collapsed_grid();
+    for y in 0..GRID_H {
+        for x in 0..GRID_W {
+            let Some(tile) = grid[y * GRID_W + x] else {
+                continue;
+            };
+            let color = theme.color(tile);
+            // Image rows run top to bottom, grid rows bottom to top.
+            let origin_x = x as u32 * cell_px;
+            let origin_y = (GRID_H - 1 - y) as u32 * cell_px;
+            for py in 0..cell_px {
+                for px in 0..cell_px {
+                    let shade = match style {
+                        ExportStyle::Pixels(_) => 1.0,
+                        ExportStyle::Tiles => blob_shade(&grid, x, y, px, py),
+                    };
+                    let shaded = color.to_srgba() * shade;
+                    let _ = image.set_color_at(
+                        origin_x + px,
+                        origin_y + py,
+                        shaded.with_alpha(1.0).into(),
+                    );
+                }
+            }
+        }
+    }
+
+    let path = export_stem("map").with_extension("png");
+    match save_png(image, &path) {
+        Ok(()) => info!("exported map to {}", path.display()),
+        Err(err) => warn!("could not export {}: {err}", path.display()),
+    }
+}
+
+fn save_png(image: Image, path: &Path) -> Result<(), String> {
+    image
+        .try_into_dynamic()
+        .map_err(|err| err.to_string())
+        .and_then(|dynamic| dynamic.to_rgba8().save(path).map_err(|err| err.to_string()))
+}
+
+/// Writes the collapsed cells as a Tiled map (`.tmx`) with a CSV tile layer,
+/// along with a tileset image holding one themed tile per tile type. Each
+/// tileset tile carries its tile type as a `type` property, and cells not
+/// collapsed yet are left empty.
+fn export_tiled(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keybindings: Res<Keybindings>,
+    rules: Res<Rules>,
+    theme: Res<Theme>,
+    grid: Res<WfcGrid>,
+) {
+    if !keybindings.just_pressed(Action::ExportTiled, &keyboard_input) {
+        return;
+    }
+    let stem = export_stem("map");
+    let map_path = stem.with_extension("tmx");
+    let tileset_path = stem.with_extension("tileset.png");
+    let tileset_file = tileset_path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let count = TileType::ALL.len() as u32;
+    let mut tileset = Image::new_fill(
+        Extent3d {
+            width: count * VARIANT_PX,
+            height: VARIANT_PX,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 0],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    for tile in TileType::ALL {
+        for py in 0..VARIANT_PX {
+            for px in 0..VARIANT_PX {
+                let x = tile.index() as u32 * VARIANT_PX + px;
+                let _ = tileset.set_color_at(x, py, theme.color(tile));
+            }
+        }
+    }
+
+    // Global tile IDs start at 1; 0 is an empty cell. Tiled lists rows from
+    // the top, grid rows run bottom to top.
+    let grid = grid.collapsed_grid();
+    let rows: Vec<String> = (0..GRID_H)
+        .rev()
+        .map(|y| {
+            (0..GRID_W)
+                .map(|x| {
+                    grid[y * GRID_W + x]
+                        .map_or(0, |tile| tile.index() + 1)
+                        .to_string()
+                })
+                .collect::<Vec<_>>()
+                .join(",")
+        })
+        .collect();
+    let tile_types: String = TileType::ALL
+        .iter()
+        .map(|tile| {
+            format!(
+                "  <tile id=\"{}\">\n   <properties>\n    <property name=\"type\" value=\"{tile:?}\"/>\n   </properties>\n  </tile>\n",
+                tile.index()
+            )
+        })
+        .collect();
+    let map = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" renderorder="right-down" width="{GRID_W}" height="{GRID_H}" tilewidth="{VARIANT_PX}" tileheight="{VARIANT_PX}" infinite="0" nextlayerid="2" nextobjectid="1">
+ <tileset firstgid="1" name="{name}" tilewidth="{VARIANT_PX}" tileheight="{VARIANT_PX}" tilecount="{count}" columns="{count}">
+  <image source="{tileset_file}" width="{width}" height="{VARIANT_PX}"/>
+{tile_types} </tileset>
+ <layer id="1" name="terrain" width="{GRID_W}" height="{GRID_H}">
+  <data encoding="csv">
+{data}
+  </data>
+ </layer>
+</map>
+"#,
+        name = xml_escape(&rules.name),
+        width = count * VARIANT_PX,
+        data = rows.join(",\n"),
+    );
+
+    let result = save_png(tileset, &tileset_path)
+        .and_then(|()| std::fs::write(&map_path, map).map_err(|err| err.to_string()));
+    match result {
+        Ok(()) => info!("exported Tiled map to {}", map_path.display()),
+        Err(err) => warn!("could not export {}: {err}", map_path.display()),
+    }
+}
+
+/// The JSON export of `grid`, indexed by `y * GRID_W + x`.
+pub fn map_json(
+    seed: u64,
+    rules_name: &str,
+    theme: &Theme,
+    grid: &[Option<TileType>],
+) -> Result<String, String> {
+    let map = JsonMap {
+        format: JSON_FORMAT,
+        version: JSON_VERSION,
+        width: GRID_W,
+        height: GRID_H,
+        seed,
+        rules: rules_name.to_string(),
+        tiles: TileType::ALL
+            .iter()
+            .map(|tile| JsonTile {
+                id: tile.index(),
+                name: format!("{tile:?}"),
+                walkable: tile.walkable(),
+                tags: tile.tags(),
+                color: theme.color(*tile).to_srgba().to_hex(),
+            })
+            .collect(),
+        // Rows from the top like the other exports; grid rows run bottom to
+        // top.
+        cells: (0..GRID_H)
+            .rev()
+            .map(|y| {
+                (0..GRID_W)
+                    .map(|x| grid[y * GRID_W + x].map(TileType::index))
+                    .collect()
+            })
+            .collect(),
+    };
+
+    serde_json::to_string_pretty(&map).map_err(|err| err.to_string())
+}
+
+/// Writes the grid and tile metadata as `map-<unix millis>.json`, for game
+/// engines and scripts.
+fn export_json(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keybindings: Res<Keybindings>,
+    seed: Res<Seed>,
+    rules: Res<Rules>,
+    theme: Res<Theme>,
+    grid: Res<WfcGrid>,
+) {
+    if !keybindings.just_pressed(Action::ExportJson, &keyboard_input) {
+        return;
+    }
+    let grid = grid.collapsed_grid();
+    let path = export_stem("map").with_extension("json");
+    let result = map_json(seed.0, &rules.name, &theme, &grid)
+        .and_then(|text| std::fs::write(&path, text).map_err(|err| err.to_string()));
+    match result {
+        Ok(()) => info!("exported map to {}", path.display()),
+        Err(err) => warn!("could not export {}: {err}", path.display()),
+    }
+}
+
+/// Writes the grid as `map-<unix millis>.csv`: one line per row from the top,
+/// holding the tile ids used by the JSON export. Cells not collapsed yet are
+/// left empty.
+fn export_csv(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keybindings: Res<Keybindings>,
+    grid: Res<WfcGrid>,
+) {
+    if !keybindings.just_pressed(Action::ExportCsv, &keyboard_input) {
+        return;
+    }
+    let csv = map_csv(&grid.collapsed_grid());
+    let path = export_stem("map").with_extension("csv");
+    match std::fs::write(&path, csv) {
+        Ok(()) => info!("exported map to {}", path.display()),
+        Err(err) => warn!("could not export {}: {err}", path.display()),
+    }
+}
+
+/// Tile ids of the cells, one line per row from the top, with uncollapsed
+/// cells left empty.
+pub fn map_csv(grid: &[Option<TileType>]) -> String {
+    (0..GRID_H)
+        .rev()
+        .map(|y| {
+            let row: Vec<String> = (0..GRID_W)
+                .map(|x| {
+                    grid[y * GRID_W + x]
+                        .map(|tile| tile.index().to_string())
+                        .unwrap_or_default()
+                })
+                .collect();
+            row.join(",") + "\n"
+        })
+        .collect()
+}
+
+/// Flat PNG of the collapsed cells in the default theme, rows from the top.
+/// Cells still open stay transparent. Used by the map server and headless
+/// runs.
+pub fn map_png(theme: &Theme, grid: &[Option<TileType>]) -> Result<Vec<u8>, String> {
+    let side = MAP_PNG_CELL_PX as usize;
+    let mut pixels = Vec::with_capacity(GRID_W * GRID_H * side * side * 4);
+    for y in (0..GRID_H).rev() {
+        let row: Vec<u8> = (0..GRID_W)
+            .flat_map(|x| {
+                let rgba = grid[y * GRID_W + x]
+                    .map_or([0; 4], |tile| theme.color(tile).to_srgba().to_u8_array());
+                rgba.repeat(side)
+            })
+            .collect();
+        for _ in 0..side {
+            pixels.extend_from_slice(&row);
+        }
+    }
+
+    let mut bytes = Vec::new();
+    let mut encoder = png::Encoder::new(
+        &mut bytes,
+        GRID_W as u32 * MAP_PNG_CELL_PX,
+        GRID_H as u32 * MAP_PNG_CELL_PX,
+    );
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&pixels))
+        .map_err(|err| err.to_string())?;
+    Ok(bytes)
+}
+
+/// Vector map with a square per collapsed cell in its theme color, plus the
+/// smoothed coastlines while they're shown, so it scales cleanly for print.
+fn map_svg(theme: &Theme, grid: &[Option<TileType>], coastlines: &[Vec<Vec2>]) -> String {
+    let (width, height) = (GRID_W as f32 * SVG_CELL, GRID_H as f32 * SVG_CELL);
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" viewBox=\"0 0 {width} {height}\">\n"
+    );
+    // Rows from the top; grid rows run bottom to top.
+    svg += "<g shape-rendering=\"crispEdges\">\n";
+    for y in 0..GRID_H {
+        for x in 0..GRID_W {
+            let Some(tile) = grid[y * GRID_W + x] else {
+                continue;
+            };
+            svg += &format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{SVG_CELL}\" height=\"{SVG_CELL}\" fill=\"{}\"/>\n",
+                x as f32 * SVG_CELL,
+                (GRID_H - 1 - y) as f32 * SVG_CELL,
+                theme.color(tile).to_srgba().to_hex(),
+            );
+        }
+    }
+    svg += "</g>\n";
+
+    // Coastline points are in cells, from the bottom-left cell's center.
+    let to_svg = |point: Vec2| {
+        format!(
+            "{:.2} {:.2}",
+            (point.x + 0.5) * SVG_CELL,
+            (GRID_H as f32 - 0.5 - point.y) * SVG_CELL
+        )
+    };
+    for polyline in coastlines {
+        let Some((first, rest)) = polyline.split_first() else {
+            continue;
+        };
+        let mut data = format!("M {}", to_svg(*first));
+        for &point in rest {
+            data += &format!(" L {}", to_svg(point));
+        }
+        svg += &format!(
+            "<path d=\"{data}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{SVG_COASTLINE_WIDTH}\" stroke-linejoin=\"round\" stroke-linecap=\"round\"/>\n",
+            COASTLINE_COLOR.to_srgba().to_hex(),
+        );
+    }
+    svg + "</svg>\n"
+}
+
+/// Writes the grid as `map-<unix millis>.svg`, with the coastlines if the
+/// overlay is on.
+fn export_svg(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keybindings: Res<Keybindings>,
+    theme: Res<Theme>,
+    coastline: Res<Coastline>,
+    grid: Res<WfcGrid>,
+) {
+    if !keybindings.just_pressed(Action::ExportSvg, &keyboard_input) {
+        return;
+    }
+    let coastlines = if coastline.visible {
+        coastline.polylines.as_slice()
+    } else {
+        &[]
+    };
+    let svg = map_svg(&theme, &grid.collapsed_grid(), coastlines);
+    let path = export_stem("map").with_extension("svg");
+    match std::fs::write(&path, svg) {
+        Ok(()) => info!("exported map to {}", path.display()),
+        Err(err) => warn!("could not export {}: {err}", path.display()),
+    }
+}
+
+/// Writes the active ruleset as a Graphviz graph (`rules-<unix millis>.dot`)
+/// with a node per tile type, filled with its theme color and labeled with
+/// its weight. Rules don't depend on direction, so one edge stands for all
+/// four; pairs allowed both ways get a single two-headed edge.
+fn export_graph(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keybindings: Res<Keybindings>,
+    rules: Res<Rules>,
+    theme: Res<Theme>,
+) {
+    if !keybindings.just_pressed(Action::ExportGraph, &keyboard_input) {
+        return;
+    }
+    let mut dot = format!(
+        "digraph \"{}\" {{\n  node [style=filled, shape=box];\n",
+        rules.name.replace('\\', "\\\\").replace('"', "\\\"")
+    );
+    let tiles: Vec<TileType> = rules.tiles().iter().collect();
+    for &tile in &tiles {
+        dot += &format!(
+            "  {tile:?} [label=\"{tile:?}\\nweight {}\", fillcolor=\"{}\"];\n",
+            rules.weight(tile),
+            theme.color(tile).to_srgba().to_hex()
+        );
+    }
+    for (i, &tile) in tiles.iter().enumerate() {
+        for &neighbor in &tiles[i..] {
+            let edge = match (rules.allows(tile, neighbor), rules.allows(neighbor, tile)) {
+                (true, true) => format!("  {tile:?} -> {neighbor:?} [dir=both];\n"),
+                (true, false) => format!("  {tile:?} -> {neighbor:?};\n"),
+                (false, true) => format!("  {neighbor:?} -> {tile:?};\n"),
+                (false, false) => continue,
+            };
+            dot += &edge;
+        }
+    }
+    dot += "}\n";
+
+    let path = export_stem("rules").with_extension("dot");
+    match std::fs::write(&path, dot) {
+        Ok(()) => info!("exported rules graph to {}", path.display()),
+        Err(err) => warn!("could not export {}: {err}", path.display()),
+    }
+}
+
+/// Escapes text for an XML attribute value.
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
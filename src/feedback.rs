@@ -0,0 +1,196 @@
+use std::time::Duration;
+
+use bevy::audio::{AddAudioSource, Decodable, Source, Volume};
+use bevy::prelude::*;
+use rand::prelude::*;
+
+use crate::animation::CollapseTween;
+use crate::keybindings::{Action, Keybindings};
+use crate::render::sync_tile_sprites;
+use crate::{TILE_SIZE, layers};
+
+/// Particles spawned across all collapses in one frame. Bursts shrink as more
+/// cells collapse per frame, down to one particle each for the first cells.
+const PARTICLE_BUDGET: usize = 48;
+const PARTICLES_PER_BURST: usize = 12;
+const PARTICLE_SECONDS: f32 = 0.4;
+const PARTICLE_SIZE: f32 = 3.0;
+const PARTICLE_SPEED: f32 = 60.0;
+const PARTICLE_Z: f32 = layers::FLASH + 0.1;
+
+const CLICK_SAMPLE_RATE: u32 = 44_100;
+const CLICK_SECONDS: f32 = 0.03;
+const CLICK_HZ: f32 = 1_800.0;
+const CLICK_VOLUME: f32 = 0.4;
+
+/// Feedback played when a cell collapses. The click sound is toggled with `U`.
+#[derive(Resource)]
+pub struct CollapseFeedback {
+    pub particles: bool,
+    pub sound: bool,
+}
+
+impl Default for CollapseFeedback {
+    fn default() -> Self {
+        Self {
+            particles: true,
+            sound: false,
+        }
+    }
+}
+
+#[derive(Component)]
+struct Particle {
+    velocity: Vec2,
+    timer: Timer,
+}
+
+/// Short decaying tone, synthesized rather than loaded from a file.
+#[derive(Asset, TypePath)]
+struct ClickSound;
+
+#[derive(Resource)]
+struct ClickHandle(Handle<ClickSound>);
+
+struct ClickDecoder {
+    sample: u32,
+    len: u32,
+}
+
+impl Iterator for ClickDecoder {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.sample >= self.len {
+            return None;
+        }
+        let t = self.sample as f32 / CLICK_SAMPLE_RATE as f32;
+        let envelope = 1.0 - self.sample as f32 / self.len as f32;
+        self.sample += 1;
+        Some((t * CLICK_HZ * std::f32::consts::TAU).sin() * envelope * envelope)
+    }
+}
+
+impl Source for ClickDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        Some((self.len - self.sample) as usize)
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        CLICK_SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        Some(Duration::from_secs_f32(CLICK_SECONDS))
+    }
+}
+
+impl Decodable for ClickSound {
+    type DecoderItem = f32;
+    type Decoder = ClickDecoder;
+
+    fn decoder(&self) -> Self::Decoder {
+        ClickDecoder {
+            sample: 0,
+            len: (CLICK_SECONDS * CLICK_SAMPLE_RATE as f32) as u32,
+        }
+    }
+}
+
+pub struct FeedbackPlugin;
+
+impl Plugin for FeedbackPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CollapseFeedback>()
+            .add_audio_source::<ClickSound>()
+            .add_systems(Startup, setup_click)
+            .add_systems(
+                Update,
+                (
+                    toggle_sound,
+                    emit_feedback.after(sync_tile_sprites),
+                    update_particles,
+                )
+                    .chain(),
+            );
+    }
+}
+
+fn setup_click(mut commands: Commands, mut sounds: ResMut<Assets<ClickSound>>) {
+    commands.insert_resource(ClickHandle(sounds.add(ClickSound)));
+}
+
+fn toggle_sound(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keybindings: Res<Keybindings>,
+    mut feedback: ResMut<CollapseFeedback>,
+) {
+    if keybindings.just_pressed(Action::ToggleSound, &keyboard_input) {
+        feedback.sound = !feedback.sound;
+    }
+}
+
+fn emit_feedback(
+    mut commands: Commands,
+    feedback: Res<CollapseFeedback>,
+    click: Res<ClickHandle>,
+    collapsed: Query<&Transform, Added<CollapseTween>>,
+) {
+    let count = collapsed.iter().count();
+    if count == 0 {
+        return;
+    }
+
+    if feedback.particles {
+        let per_burst = (PARTICLE_BUDGET / count).min(PARTICLES_PER_BURST);
+        let mut rng = rand::rng();
+        for transform in collapsed.iter().take(PARTICLE_BUDGET) {
+            let origin = transform.translation.truncate();
+            for _ in 0..per_burst.max(1) {
+                let angle = rng.random_range(0.0..std::f32::consts::TAU);
+                let speed = rng.random_range(0.5..1.0) * PARTICLE_SPEED;
+                let offset = Vec2::new(rng.random_range(-0.5..0.5), rng.random_range(-0.5..0.5))
+                    * TILE_SIZE
+                    * 0.5;
+                commands.spawn((
+                    Particle {
+                        velocity: Vec2::from_angle(angle) * speed,
+                        timer: Timer::from_seconds(PARTICLE_SECONDS, TimerMode::Once),
+                    },
+                    Sprite::from_color(Color::WHITE, Vec2::splat(PARTICLE_SIZE)),
+                    Transform::from_translation((origin + offset).extend(PARTICLE_Z)),
+                ));
+            }
+        }
+    }
+
+    // One click per frame, quieter when many cells collapse at once.
+    if feedback.sound {
+        let volume = CLICK_VOLUME / (count as f32).sqrt();
+        commands.spawn((
+            AudioPlayer::<ClickSound>(click.0.clone()),
+            PlaybackSettings::DESPAWN.with_volume(Volume::Linear(volume)),
+        ));
+    }
+}
+
+fn update_particles(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut particles: Query<(Entity, &mut Particle, &mut Transform, &mut Sprite)>,
+) {
+    for (entity, mut particle, mut transform, mut sprite) in particles.iter_mut() {
+        particle.timer.tick(time.delta());
+        if particle.timer.is_finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        transform.translation += (particle.velocity * time.delta_secs()).extend(0.0);
+        sprite.color = Color::WHITE.with_alpha(particle.timer.fraction_remaining());
+    }
+}
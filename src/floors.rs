@@ -0,0 +1,300 @@
+use bevy::ecs::change_detection::Tick;
+use bevy::prelude::*;
+use bevy::tasks::futures::check_ready;
+use bevy::tasks::{AsyncComputeTaskPool, Task};
+
+use crate::generation::GenerationState;
+use crate::headless::{HeadlessSolver, MAX_STEPS};
+use crate::history::GridState;
+use crate::inspector::Provenance;
+use crate::keybindings::{Action, Keybindings};
+use crate::lock::Locks;
+use crate::player::Player;
+use crate::rules::Rules;
+use crate::strategy::Strategy;
+use crate::timeline::Timeline;
+use crate::variation::cell_hash;
+use crate::{GRID_H, GRID_W, Seed, TileType, WfcGrid, cell_center, collapse_step, neighbor_coords};
+
+/// Floors of a dungeon, counting the map generated live as the top one.
+const FLOOR_COUNT: usize = 3;
+/// Keeps where stairs go independent of other rolls.
+const STAIRS_SALT: u64 = 0x7374_6169_7273_0000;
+/// Seeds tried for a floor before the dungeon stops above it.
+const FLOOR_ATTEMPTS: u64 = 4;
+
+/// The floors of a dungeon, built under a completed map whenever its ruleset
+/// has stairs, and which of them is shown. Stairs down on one floor sit at
+/// the same cell as the stairs up on the floor below.
+#[derive(Resource, Default)]
+pub struct Floors {
+    /// Collapsed tile of every cell of each floor, top floor first.
+    floors: Vec<Vec<Option<TileType>>>,
+    /// Where each floor's stairs down, and the next floor's stairs up, are.
+    stairs: Vec<(usize, usize)>,
+    shown: usize,
+}
+
+/// Floors being planned off the main thread, with the grid's change tick
+/// when planning started so edits made meanwhile can be noticed.
+#[derive(Resource, Default)]
+struct FloorTask(Option<(Task<Floors>, Tick)>);
+
+pub struct FloorsPlugin;
+
+impl Plugin for FloorsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Floors>()
+            .init_resource::<FloorTask>()
+            .add_systems(OnEnter(GenerationState::Complete), start_floors)
+            .add_systems(OnExit(GenerationState::Complete), clear_floors)
+            .add_systems(
+                Update,
+                (finish_floors, switch_floor)
+                    .chain()
+                    .run_if(in_state(GenerationState::Complete))
+                    .before(collapse_step),
+            );
+    }
+}
+
+/// A cell of `tiles` to put stairs on: a floor tile, not locked and with
+/// neighbors that allow stairs, picked by a roll of the floor's seed.
+fn stairs_cell(
+    rules: &Rules,
+    locks: &Locks,
+    tiles: &[Option<TileType>],
+    seed: u64,
+) -> Option<(usize, usize)> {
+    let tile = |x: usize, y: usize| tiles[y * GRID_W + x];
+    (0..GRID_H)
+        .flat_map(|y| (0..GRID_W).map(move |x| (x, y)))
+        .filter(|&(x, y)| {
+            tile(x, y) == Some(TileType::Floor)
+                && !locks.contains(x, y)
+                && neighbor_coords(x, y).all(|(nx, ny)| {
+                    tile(nx, ny).is_some_and(|neighbor| {
+                        [TileType::StairsUp, TileType::StairsDown]
+                            .into_iter()
+                            .all(|stairs| rules.allows(neighbor, stairs))
+                    })
+                })
+        })
+        .min_by_key(|&(x, y)| cell_hash(seed, STAIRS_SALT, x, y))
+}
+
+/// Solves a floor, trying further seeds while a solve contradicts itself or
+/// runs out of steps. Only a finished floor is kept: one with cells left
+/// open would send the shown map back to generating, and so end the dungeon.
+fn solve_floor(
+    rules: &Rules,
+    strategy: &Strategy,
+    locks: &Locks,
+    floor: usize,
+    seed: u64,
+) -> Option<Vec<Option<TileType>>> {
+    (0..FLOOR_ATTEMPTS).find_map(|attempt| {
+        let offset = floor as u64 + attempt * FLOOR_COUNT as u64;
+        let mut solver = HeadlessSolver::new(rules, strategy, locks, seed.wrapping_add(offset));
+        solver.run(MAX_STEPS);
+        solver.solved().then(|| solver.grid())
+    })
+}
+
+/// Solves the floors below `top` one after another. Each is solved with its
+/// stairs up locked under the stairs down of the floor above, so the solver
+/// fits the floor around them, and gets its own stairs down once solved.
+/// Stops early on a floor with nowhere to put stairs or that can't be solved.
+fn plan_floors(
+    rules: &Rules,
+    strategy: &Strategy,
+    locks: &Locks,
+    top: Vec<Option<TileType>>,
+    seed: u64,
+) -> Floors {
+    let mut floors = Floors {
+        floors: vec![top],
+        ..default()
+    };
+    for floor in 1..FLOOR_COUNT {
+        let above = floors.floors.last_mut().unwrap();
+        let Some((x, y)) = stairs_cell(rules, locks, above, seed.wrapping_add(floor as u64 - 1))
+        else {
+            warn!("no room for stairs on floor {floor}; the dungeon stops there");
+            break;
+        };
+
+        let mut floor_locks = locks.clone();
+        floor_locks.toggle(x, y, TileType::StairsUp);
+        let Some(tiles) = solve_floor(rules, strategy, &floor_locks, floor, seed) else {
+            warn!(
+                "floor {} has no solution; the dungeon stops above it",
+                floor + 1
+            );
+            break;
+        };
+        above[y * GRID_W + x] = Some(TileType::StairsDown);
+        floors.stairs.push((x, y));
+        floors.floors.push(tiles);
+    }
+    floors
+}
+
+/// Starts planning the dungeon under a completed map of a ruleset with
+/// stairs on the async compute task pool, as each floor is a full solve.
+fn start_floors(
+    seed: Res<Seed>,
+    rules: Res<Rules>,
+    strategy: Res<Strategy>,
+    locks: Res<Locks>,
+    grid: Res<WfcGrid>,
+    mut task: ResMut<FloorTask>,
+) {
+    let tiles = rules.tiles();
+    if !tiles.contains(TileType::StairsUp) || !tiles.contains(TileType::StairsDown) {
+        return;
+    }
+    let (rules, strategy, locks) = (rules.clone(), *strategy, locks.clone());
+    let (top, seed) = (grid.collapsed_grid(), seed.0);
+    let plan = AsyncComputeTaskPool::get()
+        .spawn(async move { plan_floors(&rules, &strategy, &locks, top, seed) });
+    task.0 = Some((plan, grid.last_changed()));
+    info!("planning the floors below");
+}
+
+/// Takes the planned dungeon once it's ready. The live map becomes its top
+/// floor, with stairs down written into it. Edit the map meanwhile and the
+/// plan, made for the old map, is dropped.
+fn finish_floors(
+    mut commands: Commands,
+    rules: Res<Rules>,
+    mut provenance: ResMut<Provenance>,
+    mut timeline: ResMut<Timeline>,
+    mut grid: ResMut<WfcGrid>,
+    mut floors: ResMut<Floors>,
+    mut task: ResMut<FloorTask>,
+) {
+    let Some((plan, started)) = &mut task.0 else {
+        return;
+    };
+    if grid.last_changed() != *started {
+        info!("map edited; floor planning cancelled");
+        task.0 = None;
+        return;
+    }
+    let Some(planned) = check_ready(plan) else {
+        return;
+    };
+    task.0 = None;
+    *floors = planned;
+    info!(
+        "dungeon of {} floors, stairs at {:?}",
+        floors.floors.len(),
+        floors.stairs
+    );
+    show_floor(
+        &mut commands,
+        &rules,
+        &mut provenance,
+        &mut timeline,
+        &mut grid,
+        &floors,
+    );
+}
+
+/// Dropping a task still planning cancels it.
+fn clear_floors(mut floors: ResMut<Floors>, mut task: ResMut<FloorTask>) {
+    *floors = Floors::default();
+    task.0 = None;
+}
+
+/// Writes the shown floor into the grid. Like an imported map, the timeline
+/// starts from it.
+fn show_floor(
+    commands: &mut Commands,
+    rules: &Rules,
+    provenance: &mut Provenance,
+    timeline: &mut Timeline,
+    grid: &mut WfcGrid,
+    floors: &Floors,
+) {
+    let state = GridState::from_grid(rules, &floors.floors[floors.shown]);
+    state.restore(commands, rules, provenance, grid);
+    timeline.rebase(state);
+}
+
+/// Shows the next floor down, and the top one again after the bottom. The
+/// player, if there is one, takes the stairs: it arrives on the stairs up
+/// going down and on the top floor's stairs down going back up.
+#[allow(clippy::too_many_arguments)]
+fn switch_floor(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keybindings: Res<Keybindings>,
+    rules: Res<Rules>,
+    mut provenance: ResMut<Provenance>,
+    mut timeline: ResMut<Timeline>,
+    mut grid: ResMut<WfcGrid>,
+    mut floors: ResMut<Floors>,
+    task: Res<FloorTask>,
+    mut players: Query<&mut Transform, With<Player>>,
+) {
+    if !keybindings.just_pressed(Action::CycleFloor, &keyboard_input) {
+        return;
+    }
+    if task.0.is_some() {
+        info!("the floors below are still being planned");
+        return;
+    }
+    if floors.floors.len() < 2 {
+        info!("no other floors to show");
+        return;
+    }
+    let from = floors.shown;
+    floors.shown = (from + 1) % floors.floors.len();
+    let to = floors.shown;
+    show_floor(
+        &mut commands,
+        &rules,
+        &mut provenance,
+        &mut timeline,
+        &mut grid,
+        &floors,
+    );
+    info!("showing floor {} of {}", to + 1, floors.floors.len());
+
+    let (x, y) = floors.stairs[if to > from { to - 1 } else { to }];
+    for mut transform in players.iter_mut() {
+        transform.translation = cell_center(x, y).extend(transform.translation.z);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::tileset_path;
+
+    /// Every floor is a finished map, and each pair of neighboring floors
+    /// meets at one cell: stairs down above, stairs up below.
+    #[test]
+    fn floors_are_solved_and_joined_by_stairs() {
+        let rules = Rules::load(&tileset_path("dungeon.ron")).unwrap();
+        let strategy = Strategy::default();
+        let locks = Locks::default();
+        let mut solver = HeadlessSolver::new(&rules, &strategy, &locks, 7);
+        solver.run(MAX_STEPS);
+        assert!(solver.solved());
+
+        let floors = plan_floors(&rules, &strategy, &locks, solver.grid(), 7);
+        assert_eq!(floors.floors.len(), FLOOR_COUNT);
+        assert_eq!(floors.stairs.len(), FLOOR_COUNT - 1);
+        for tiles in &floors.floors {
+            assert!(tiles.iter().all(Option::is_some));
+        }
+        for (floor, &(x, y)) in floors.stairs.iter().enumerate() {
+            let at = |floor: usize| floors.floors[floor][y * GRID_W + x];
+            assert_eq!(at(floor), Some(TileType::StairsDown));
+            assert_eq!(at(floor + 1), Some(TileType::StairsUp));
+        }
+    }
+}
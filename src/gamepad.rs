@@ -0,0 +1,225 @@
+use bevy::prelude::*;
+use rand::prelude::*;
+
+use crate::inspector::Inspector;
+use crate::lock::Locks;
+use crate::manual::ManualCollapse;
+use crate::reset::ResetRegion;
+use crate::rules::Rules;
+use crate::speed::GenerationSpeed;
+use crate::stepping::Stepping;
+use crate::tile_palette::SelectedTile;
+use crate::{GRID_H, GRID_W, TILE_SIZE, TileType, WfcGrid, cell_center, collapse_step};
+
+/// Stick deflection needed to move the cursor.
+const STICK_DEADZONE: f32 = 0.5;
+/// Delay between cursor moves while the stick is held.
+const STICK_REPEAT_SECS: f32 = 0.12;
+/// Speed factor per second with a trigger fully pressed.
+const TRIGGER_SPEED_FACTOR: f32 = 4.0;
+const CURSOR_COLOR: Color = Color::srgb(0.3, 0.9, 1.0);
+
+/// Cell selected with a gamepad, shown once a gamepad moves it first. The
+/// D-pad or left stick move it; `South` collapses it to the palette tile or
+/// a weighted pick, `West` locks or unlocks it, `East` resets it and `North`
+/// inspects it. The bumpers cycle the palette tile, the triggers change the
+/// generation speed and `Start` pauses.
+#[derive(Resource, Default)]
+struct GamepadCursor {
+    cell: Option<(usize, usize)>,
+    repeat_secs: f32,
+}
+
+pub struct GamepadPlugin;
+
+impl Plugin for GamepadPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GamepadCursor>()
+            .add_systems(
+                Update,
+                (
+                    move_cursor,
+                    collapse_cell,
+                    reset_cell,
+                    toggle_lock,
+                    adjust_generation,
+                )
+                    .chain()
+                    .before(collapse_step),
+            )
+            .add_systems(Update, draw_cursor.after(collapse_step));
+    }
+}
+
+fn move_cursor(time: Res<Time>, gamepads: Query<&Gamepad>, mut cursor: ResMut<GamepadCursor>) {
+    for gamepad in gamepads.iter() {
+        let mut step = IVec2::ZERO;
+        for (button, direction) in [
+            (GamepadButton::DPadUp, IVec2::Y),
+            (GamepadButton::DPadDown, IVec2::NEG_Y),
+            (GamepadButton::DPadLeft, IVec2::NEG_X),
+            (GamepadButton::DPadRight, IVec2::X),
+        ] {
+            if gamepad.just_pressed(button) {
+                step += direction;
+            }
+        }
+
+        let stick = gamepad.left_stick();
+        if stick.abs().max_element() < STICK_DEADZONE {
+            cursor.repeat_secs = 0.0;
+        } else {
+            cursor.repeat_secs -= time.delta_secs();
+            if cursor.repeat_secs <= 0.0 {
+                cursor.repeat_secs = STICK_REPEAT_SECS;
+                step += IVec2::new(
+                    if stick.x.abs() >= STICK_DEADZONE {
+                        stick.x.signum() as i32
+                    } else {
+                        0
+                    },
+                    if stick.y.abs() >= STICK_DEADZONE {
+                        stick.y.signum() as i32
+                    } else {
+                        0
+                    },
+                );
+            }
+        }
+        if step == IVec2::ZERO {
+            continue;
+        }
+
+        let (x, y) = cursor.cell.unwrap_or((GRID_W / 2, GRID_H / 2));
+        let moved = (IVec2::new(x as i32, y as i32) + step).clamp(
+            IVec2::ZERO,
+            IVec2::new(GRID_W as i32 - 1, GRID_H as i32 - 1),
+        );
+        cursor.cell = Some((moved.x as usize, moved.y as usize));
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn collapse_cell(
+    mut commands: Commands,
+    gamepads: Query<&Gamepad>,
+    cursor: Res<GamepadCursor>,
+    rules: Res<Rules>,
+    selected: Res<SelectedTile>,
+    mut inspector: ResMut<Inspector>,
+    mut manual: ManualCollapse,
+    mut grid: ResMut<WfcGrid>,
+) {
+    let Some((x, y)) = cursor.cell else {
+        return;
+    };
+    for gamepad in gamepads.iter() {
+        if gamepad.just_pressed(GamepadButton::North) {
+            inspector.inspect((x, y));
+        }
+        if !gamepad.just_pressed(GamepadButton::South) {
+            continue;
+        }
+        let tile = grid.cell(x, y);
+        if tile.collapsed {
+            continue;
+        }
+        // No chooser to point at with a gamepad, so without a palette tile
+        // the pick is left to chance like the solver's.
+        let choice = selected
+            .0
+            .filter(|choice| tile.possible.contains(choice))
+            .or_else(|| {
+                tile.possible
+                    .choose_weighted(&mut rand::rng(), |&choice| rules.weight(choice))
+                    .ok()
+                    .copied()
+            });
+        if let Some(choice) = choice {
+            manual.collapse(&mut commands, &mut grid, (x, y), choice);
+        }
+    }
+}
+
+fn reset_cell(
+    mut commands: Commands,
+    gamepads: Query<&Gamepad>,
+    cursor: Res<GamepadCursor>,
+    mut region: ResetRegion,
+    mut grid: ResMut<WfcGrid>,
+) {
+    let Some(cell) = cursor.cell else {
+        return;
+    };
+    if gamepads
+        .iter()
+        .any(|gamepad| gamepad.just_pressed(GamepadButton::East))
+    {
+        region.reset(&mut commands, &mut grid, cell, 0);
+    }
+}
+
+fn toggle_lock(
+    gamepads: Query<&Gamepad>,
+    cursor: Res<GamepadCursor>,
+    mut locks: ResMut<Locks>,
+    grid: Res<WfcGrid>,
+) {
+    let Some((x, y)) = cursor.cell else {
+        return;
+    };
+    if !gamepads
+        .iter()
+        .any(|gamepad| gamepad.just_pressed(GamepadButton::West))
+    {
+        return;
+    }
+    let tile = grid.cell(x, y);
+    if tile.collapsed {
+        locks.toggle(x, y, tile.possible[0]);
+    }
+}
+
+fn adjust_generation(
+    time: Res<Time>,
+    gamepads: Query<&Gamepad>,
+    mut speed: ResMut<GenerationSpeed>,
+    mut stepping: ResMut<Stepping>,
+    rules: Res<Rules>,
+    mut selected: ResMut<SelectedTile>,
+) {
+    for gamepad in gamepads.iter() {
+        let faster = gamepad.get(GamepadButton::RightTrigger2).unwrap_or(0.0);
+        let slower = gamepad.get(GamepadButton::LeftTrigger2).unwrap_or(0.0);
+        if faster != slower {
+            speed.scale(TRIGGER_SPEED_FACTOR.powf((faster - slower) * time.delta_secs()));
+        }
+        if gamepad.just_pressed(GamepadButton::Start) {
+            stepping.paused = !stepping.paused;
+        }
+
+        // The bumpers step through the ruleset's tiles, with no tile between
+        // the last and the first.
+        let tiles: Vec<TileType> = rules.tiles().iter().collect();
+        let offset = isize::from(gamepad.just_pressed(GamepadButton::RightTrigger))
+            - isize::from(gamepad.just_pressed(GamepadButton::LeftTrigger));
+        if offset != 0 {
+            let current = selected
+                .0
+                .and_then(|tile| tiles.iter().position(|&t| t == tile));
+            let current = current.map_or(0, |i| i as isize + 1);
+            let next = (current + offset).rem_euclid(tiles.len() as isize + 1);
+            selected.0 = (next > 0).then(|| tiles[next as usize - 1]);
+        }
+    }
+}
+
+fn draw_cursor(mut gizmos: Gizmos, cursor: Res<GamepadCursor>) {
+    if let Some((x, y)) = cursor.cell {
+        gizmos.rect_2d(
+            Isometry2d::from_translation(cell_center(x, y)),
+            Vec2::splat(TILE_SIZE),
+            CURSOR_COLOR,
+        );
+    }
+}
@@ -0,0 +1,89 @@
+use bevy::prelude::*;
+
+use crate::events::GenerationComplete;
+use crate::stepping::Stepping;
+use crate::{Contradicted, Seed, WfcGrid, WfcSet};
+
+/// Where the current map is in its generation. Derived from the grid and
+/// [`Stepping`] each frame, so editing a finished map sends it back to
+/// generating; gate systems and UI on it with `in_state`.
+#[derive(States, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum GenerationState {
+    /// Until the first map is set up.
+    #[default]
+    Configuring,
+    Generating,
+    /// Stopped with `Space`; single steps still run.
+    Paused,
+    /// Every cell collapsed without a contradiction.
+    Complete,
+    /// Nothing left to observe, with contradictions left in the map.
+    Failed,
+}
+
+impl GenerationState {
+    pub fn label(self) -> &'static str {
+        match self {
+            GenerationState::Configuring => "configuring",
+            GenerationState::Generating => "generating",
+            GenerationState::Paused => "paused",
+            GenerationState::Complete => "complete",
+            GenerationState::Failed => "failed",
+        }
+    }
+
+    pub fn finished(self) -> bool {
+        matches!(self, GenerationState::Complete | GenerationState::Failed)
+    }
+}
+
+pub struct GenerationStatePlugin;
+
+impl Plugin for GenerationStatePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_state::<GenerationState>().add_systems(
+            Update,
+            track_generation
+                .run_if(resource_changed::<WfcGrid>.or(resource_changed::<Stepping>))
+                .in_set(WfcSet::React),
+        );
+    }
+}
+
+/// A map is finished once every cell is collapsed or, after a contradiction
+/// without backtracking, left with no tile at all.
+fn track_generation(
+    stepping: Res<Stepping>,
+    grid: Res<WfcGrid>,
+    contradicted: Query<(), With<Contradicted>>,
+    seed: Res<Seed>,
+    state: Res<State<GenerationState>>,
+    mut next: ResMut<NextState<GenerationState>>,
+    mut complete: MessageWriter<GenerationComplete>,
+) {
+    let observable = grid
+        .cells
+        .iter()
+        .any(|cell| !cell.collapsed && !cell.possible.is_empty());
+    let target = if observable {
+        if stepping.paused {
+            GenerationState::Paused
+        } else {
+            GenerationState::Generating
+        }
+    } else if contradicted.is_empty() {
+        GenerationState::Complete
+    } else {
+        GenerationState::Failed
+    };
+    if *state.get() == target {
+        return;
+    }
+    next.set(target);
+    if target.finished() {
+        complete.write(GenerationComplete {
+            seed: seed.0,
+            failed: target == GenerationState::Failed,
+        });
+    }
+}
@@ -0,0 +1,468 @@
+use bevy::ecs::change_detection::Tick;
+use bevy::prelude::*;
+use bevy::render::extract_resource::{ExtractResource, ExtractResourcePlugin};
+use bevy::render::gpu_readback::{Readback, ReadbackComplete};
+use bevy::render::render_asset::RenderAssets;
+use bevy::render::render_graph::{self, RenderGraph, RenderLabel};
+use bevy::render::render_resource::binding_types::{storage_buffer, storage_buffer_read_only};
+use bevy::render::render_resource::*;
+use bevy::render::renderer::{RenderContext, RenderDevice};
+use bevy::render::storage::{GpuShaderStorageBuffer, ShaderStorageBuffer};
+use bevy::render::{Render, RenderApp, RenderStartup, RenderSystems};
+use rand::seq::IndexedRandom;
+
+use crate::cli::Cli;
+use crate::events::{CellCollapsed, Contradiction, DomainReduced};
+use crate::heightmap::WeightBias;
+use crate::inspector::Provenance;
+use crate::rules::Rules;
+use crate::stepping::{StepBudget, plan_steps};
+use crate::strategy::Strategy;
+use crate::timeline::Timeline;
+use crate::{
+    Cell, Contradicted, GRID_H, GRID_W, SolverRng, TileSet, TileType, WfcGrid, collapse_step,
+    neighbor_coords, observe,
+};
+
+const PROPAGATE_SHADER: &str = "shaders/propagate.wgsl";
+
+/// Words before the cells in the domain buffer: the round it was uploaded
+/// in, whether the last dispatch narrowed anything, and the round the shader
+/// last propagated.
+const HEADER: usize = 3;
+/// Set on collapsed cells' masks.
+const FIXED: u32 = 1 << 31;
+/// Shader passes per dispatch. Changes travel one cell per pass, so wider
+/// fronts take another round trip.
+const PASSES: usize = 32;
+const WORKGROUP_SIZE: usize = 64;
+
+/// Whether propagation runs on the GPU, as set by `--gpu`, and the round
+/// uploaded last.
+#[derive(Resource, Default)]
+struct GpuPropagation {
+    enabled: bool,
+    /// Cells to observe per round, taken from the step budget.
+    observations: usize,
+    round: u32,
+    pending: Option<Pending>,
+}
+
+/// A round waiting for its readback, with the grid's change tick when it
+/// was uploaded so edits made meanwhile can be noticed.
+struct Pending {
+    round: u32,
+    readback: Entity,
+    uploaded: Tick,
+}
+
+/// The buffers the compute shader works on, shared with the render world.
+#[derive(Resource, ExtractResource, Clone)]
+struct GpuBuffers {
+    domains: Handle<ShaderStorageBuffer>,
+    rules: Handle<ShaderStorageBuffer>,
+    round: u32,
+}
+
+/// The last domains read back from the GPU.
+#[derive(Resource, Default)]
+struct ReadbackData(Option<Vec<u32>>);
+
+/// Experimental backend that keeps the domains in a storage buffer and runs
+/// arc consistency in a compute shader, meant for grids far larger than
+/// the CPU propagators keep up with. Each round observes a batch of cells
+/// no two of which are neighbors, uploads the domains, and reads them back
+/// for the next round's observations once the shader has settled them.
+/// Needs a GPU with compute shaders, so not WebGL2.
+///
+/// Turned on by `--gpu`, it replaces the strategy's propagation; there's no
+/// backtracking, and the inspector doesn't explain what the shader
+/// eliminated. The grid is still the fixed one, so this mostly exercises
+/// the round trip until larger grids land.
+pub struct GpuPropagationPlugin;
+
+impl Plugin for GpuPropagationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GpuPropagation>()
+            .init_resource::<ReadbackData>()
+            .add_plugins(ExtractResourcePlugin::<GpuBuffers>::default())
+            .add_systems(Startup, (enable_from_cli, create_buffers))
+            .add_systems(
+                Update,
+                (
+                    hold_solver.after(plan_steps).before(collapse_step),
+                    (upload_rules, apply_round, observe_round)
+                        .chain()
+                        .after(collapse_step),
+                ),
+            );
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app
+            .init_resource::<PropagateDispatch>()
+            .add_systems(RenderStartup, (init_pipeline, add_propagate_node))
+            .add_systems(
+                Render,
+                prepare_dispatch.in_set(RenderSystems::PrepareBindGroups),
+            );
+    }
+}
+
+fn enable_from_cli(cli: Res<Cli>, mut gpu: ResMut<GpuPropagation>) {
+    gpu.enabled = cli.gpu;
+}
+
+fn create_buffers(mut commands: Commands, mut buffers: ResMut<Assets<ShaderStorageBuffer>>) {
+    let mut domains = ShaderStorageBuffer::from(vec![0u32; HEADER + GRID_W * GRID_H]);
+    // Read back by the main world after every round.
+    domains.buffer_description.usage |= BufferUsages::COPY_SRC;
+    commands.insert_resource(GpuBuffers {
+        domains: buffers.add(domains),
+        rules: buffers.add(ShaderStorageBuffer::from(vec![
+            0u32;
+            2 + TileType::ALL.len()
+        ])),
+        round: 0,
+    });
+}
+
+/// Takes the step budget as this round's observations, leaving none for the
+/// CPU solver.
+fn hold_solver(mut gpu: ResMut<GpuPropagation>, mut budget: ResMut<StepBudget>) {
+    if gpu.enabled {
+        gpu.observations = budget.0;
+        budget.0 = 0;
+    }
+}
+
+fn upload_rules(
+    rules: Res<Rules>,
+    gpu_buffers: Res<GpuBuffers>,
+    mut buffers: ResMut<Assets<ShaderStorageBuffer>>,
+) {
+    if !rules.is_changed() {
+        return;
+    }
+    let mut words = vec![GRID_W as u32, GRID_H as u32];
+    words.extend(TileType::ALL.map(|tile| {
+        mask(
+            TileType::ALL
+                .iter()
+                .copied()
+                .filter(|&neighbor| rules.allows(tile, neighbor)),
+        )
+    }));
+    if let Some(buffer) = buffers.get_mut(&gpu_buffers.rules) {
+        buffer.set_data(words);
+    }
+}
+
+/// Takes in a round the shader has finished with. Cells it emptied are
+/// contradictions, reset to every tile of the ruleset like the CPU
+/// propagators do. The shader's changed flag is set by any pass of the round
+/// that narrowed a cell and never cleared in between, so a round that
+/// narrowed anything goes back for another, which may find nothing left to
+/// do.
+#[allow(clippy::too_many_arguments)]
+fn apply_round(
+    mut commands: Commands,
+    mut gpu: ResMut<GpuPropagation>,
+    mut data: ResMut<ReadbackData>,
+    mut gpu_buffers: ResMut<GpuBuffers>,
+    mut buffers: ResMut<Assets<ShaderStorageBuffer>>,
+    mut provenance: ResMut<Provenance>,
+    rules: Res<Rules>,
+    mut grid: ResMut<WfcGrid>,
+    mut reduced: MessageWriter<DomainReduced>,
+    mut contradictions: MessageWriter<Contradiction>,
+) {
+    let Some(pending) = &gpu.pending else {
+        return;
+    };
+    if grid.last_changed() != pending.uploaded {
+        info!("map edited; GPU round {} dropped", pending.round);
+        commands.entity(pending.readback).despawn();
+        gpu.pending = None;
+        data.0 = None;
+        return;
+    }
+    let Some(words) = data.0.take_if(|words| words[2] == pending.round) else {
+        return;
+    };
+    commands.entity(pending.readback).despawn();
+    gpu.pending = None;
+
+    for (i, &word) in words[HEADER..].iter().enumerate() {
+        if grid.cells[i].collapsed {
+            continue;
+        }
+        let (x, y) = (i % GRID_W, i / GRID_W);
+        let possible: Vec<TileType> = TileType::ALL
+            .into_iter()
+            .filter(|tile| word & (1 << tile.index()) != 0)
+            .collect();
+        if possible.is_empty() {
+            warn!("contradiction: ({x}, {y}) has no possible tiles left");
+            grid.cells[i].possible = rules.tiles().iter().collect();
+            commands
+                .entity(grid.entities[i])
+                .try_insert(Contradicted::default());
+            contradictions.write(Contradiction { x, y });
+            provenance.clear(x, y);
+        } else {
+            let removed: TileSet = grid.cells[i]
+                .possible
+                .iter()
+                .copied()
+                .filter(|tile| !possible.contains(tile))
+                .collect();
+            if !removed.is_empty() {
+                reduced.write(DomainReduced { x, y, removed });
+            }
+            grid.cells[i].possible = possible;
+        }
+    }
+    grid.invalidate();
+    if words[1] != 0 {
+        let uploaded = grid.last_changed();
+        upload(
+            &mut commands,
+            &mut gpu,
+            &mut gpu_buffers,
+            &mut buffers,
+            &grid,
+            uploaded,
+        );
+    }
+}
+
+/// Collapses the cells the heuristic picks until one is next to a cell it
+/// already picked this round, then uploads the domains.
+#[allow(clippy::too_many_arguments)]
+fn observe_round(
+    mut commands: Commands,
+    mut gpu: ResMut<GpuPropagation>,
+    mut gpu_buffers: ResMut<GpuBuffers>,
+    mut buffers: ResMut<Assets<ShaderStorageBuffer>>,
+    rules: Res<Rules>,
+    bias: Res<WeightBias>,
+    strategy: Res<Strategy>,
+    mut rng: ResMut<SolverRng>,
+    mut provenance: ResMut<Provenance>,
+    mut timeline: ResMut<Timeline>,
+    mut grid: ResMut<WfcGrid>,
+    mut collapsed: MessageWriter<CellCollapsed>,
+) {
+    if !gpu.enabled || gpu.pending.is_some() {
+        return;
+    }
+    let mut claimed = vec![false; GRID_W * GRID_H];
+    let mut observed = false;
+    for _ in 0..gpu.observations {
+        let Some((x, y)) = observe(
+            strategy.heuristic,
+            &rules,
+            &mut rng.0,
+            grid.bypass_change_detection(),
+        ) else {
+            break;
+        };
+        // Skipped cells are back in the queue once the round is applied.
+        if claimed[y * GRID_W + x] {
+            break;
+        }
+        claimed[y * GRID_W + x] = true;
+        for (nx, ny) in neighbor_coords(x, y) {
+            claimed[ny * GRID_W + nx] = true;
+        }
+
+        let possible = &grid.cell(x, y).possible;
+        let choice = possible
+            .choose_weighted(&mut rng.0, |&tile| bias.weight(&rules, tile, x, y))
+            .copied()
+            .unwrap_or_else(|_| *possible.choose(&mut rng.0).unwrap());
+        *grid.bypass_change_detection().cell_mut(x, y) = Cell {
+            possible: vec![choice],
+            collapsed: true,
+        };
+        commands
+            .entity(grid.entity(x, y))
+            .try_remove::<Contradicted>();
+        provenance.begin_step();
+        timeline.record(x, y, choice);
+        collapsed.write(CellCollapsed { x, y, tile: choice });
+        observed = true;
+    }
+    if observed {
+        grid.set_changed();
+        let uploaded = grid.last_changed();
+        upload(
+            &mut commands,
+            &mut gpu,
+            &mut gpu_buffers,
+            &mut buffers,
+            &grid,
+            uploaded,
+        );
+    }
+}
+
+/// Writes the grid's domains for a new round and starts reading them back.
+fn upload(
+    commands: &mut Commands,
+    gpu: &mut GpuPropagation,
+    gpu_buffers: &mut GpuBuffers,
+    buffers: &mut Assets<ShaderStorageBuffer>,
+    grid: &WfcGrid,
+    uploaded: Tick,
+) {
+    gpu.round += 1;
+    let mut words = vec![gpu.round, 0, 0];
+    words.extend(grid.cells.iter().map(|cell| {
+        let fixed = if cell.collapsed { FIXED } else { 0 };
+        mask(cell.possible.iter().copied()) | fixed
+    }));
+    if let Some(buffer) = buffers.get_mut(&gpu_buffers.domains) {
+        buffer.set_data(words);
+    }
+    gpu_buffers.round = gpu.round;
+    let readback = commands
+        .spawn(Readback::buffer(gpu_buffers.domains.clone()))
+        .observe(
+            |event: On<ReadbackComplete>, mut data: ResMut<ReadbackData>| {
+                data.0 = Some(event.to_shader_type());
+            },
+        )
+        .id();
+    gpu.pending = Some(Pending {
+        round: gpu.round,
+        readback,
+        uploaded,
+    });
+}
+
+fn mask(tiles: impl Iterator<Item = TileType>) -> u32 {
+    tiles.fold(0, |mask, tile| mask | 1 << tile.index())
+}
+
+#[derive(Resource)]
+struct PropagatePipeline {
+    layout: BindGroupLayoutDescriptor,
+    pipeline: CachedComputePipelineId,
+}
+
+/// The bind group to dispatch with this frame, set only when a new round
+/// was uploaded.
+#[derive(Resource, Default)]
+struct PropagateDispatch {
+    bind_group: Option<BindGroup>,
+    dispatched: u32,
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+struct PropagateLabel;
+
+fn init_pipeline(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    pipeline_cache: Res<PipelineCache>,
+) {
+    let layout = BindGroupLayoutDescriptor::new(
+        "wfc_propagate",
+        &BindGroupLayoutEntries::sequential(
+            ShaderStages::COMPUTE,
+            (
+                storage_buffer::<Vec<u32>>(false),
+                storage_buffer_read_only::<Vec<u32>>(false),
+            ),
+        ),
+    );
+    let pipeline = pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
+        label: Some("wfc_propagate".into()),
+        layout: vec![layout.clone()],
+        shader: asset_server.load(PROPAGATE_SHADER),
+        entry_point: Some("propagate".into()),
+        ..default()
+    });
+    commands.insert_resource(PropagatePipeline { layout, pipeline });
+}
+
+/// Runs once per frame at the top of the render graph.
+fn add_propagate_node(mut render_graph: ResMut<RenderGraph>) {
+    render_graph.add_node(PropagateLabel, PropagateNode);
+}
+
+/// Binds the buffers for a round that hasn't been dispatched yet, once the
+/// shader has compiled.
+fn prepare_dispatch(
+    pipeline: Res<PropagatePipeline>,
+    pipeline_cache: Res<PipelineCache>,
+    render_device: Res<RenderDevice>,
+    gpu_buffers: Option<Res<GpuBuffers>>,
+    buffers: Res<RenderAssets<GpuShaderStorageBuffer>>,
+    mut dispatch: ResMut<PropagateDispatch>,
+) {
+    dispatch.bind_group = None;
+    let Some(gpu_buffers) = gpu_buffers else {
+        return;
+    };
+    if gpu_buffers.round == dispatch.dispatched
+        || pipeline_cache
+            .get_compute_pipeline(pipeline.pipeline)
+            .is_none()
+    {
+        return;
+    }
+    let (Some(domains), Some(rules)) = (
+        buffers.get(&gpu_buffers.domains),
+        buffers.get(&gpu_buffers.rules),
+    ) else {
+        return;
+    };
+    dispatch.bind_group = Some(render_device.create_bind_group(
+        "wfc_propagate",
+        &pipeline_cache.get_bind_group_layout(&pipeline.layout),
+        &BindGroupEntries::sequential((
+            domains.buffer.as_entire_buffer_binding(),
+            rules.buffer.as_entire_buffer_binding(),
+        )),
+    ));
+    dispatch.dispatched = gpu_buffers.round;
+}
+
+struct PropagateNode;
+
+impl render_graph::Node for PropagateNode {
+    fn run(
+        &self,
+        _graph: &mut render_graph::RenderGraphContext,
+        render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), render_graph::NodeRunError> {
+        let Some(bind_group) = &world.resource::<PropagateDispatch>().bind_group else {
+            return Ok(());
+        };
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let pipeline = world.resource::<PropagatePipeline>();
+        let Some(pipeline) = pipeline_cache.get_compute_pipeline(pipeline.pipeline) else {
+            return Ok(());
+        };
+        let mut pass =
+            render_context
+                .command_encoder()
+                .begin_compute_pass(&ComputePassDescriptor {
+                    label: Some("wfc_propagate"),
+                    ..default()
+                });
+        pass.set_pipeline(pipeline);
+        pass.set_bind_group(0, bind_group, &[]);
+        let workgroups = (GRID_W * GRID_H).div_ceil(WORKGROUP_SIZE) as u32;
+        // Every dispatch sees the writes of the ones before it.
+        for _ in 0..PASSES {
+            pass.dispatch_workgroups(workgroups, 1, 1);
+        }
+        Ok(())
+    }
+}
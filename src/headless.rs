@@ -0,0 +1,260 @@
+use bevy::prelude::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use crate::events::{Contradiction, DomainReduced};
+use crate::heightmap::WeightBias;
+use crate::history::GridState;
+use crate::inspector::Provenance;
+use crate::lock::Locks;
+use crate::rules::Rules;
+use crate::strategy::{Backtrack, Strategy};
+use crate::{
+    Contradicted, GRID_H, GRID_W, SolverRng, Step, Tile, TileType, WfcGrid, collapse_once,
+    locked_grid,
+};
+
+/// Observations after which a solve gives up, so a backtracking solver stuck
+/// on an unsolvable ruleset still ends.
+pub const MAX_STEPS: usize = GRID_W * GRID_H * 64;
+
+/// The real solver in a world of its own, without sprites or any of the app's
+/// other systems. Used for benchmarks and comparison maps.
+pub struct HeadlessSolver {
+    world: World,
+    schedule: Schedule,
+}
+
+/// Where a headless solver left off, for handing its map to the app.
+pub(crate) struct SolverState {
+    /// Domains of every cell; the entities are the headless world's.
+    pub grid: WfcGrid,
+    /// Cells marked as contradicted, indexed by `y * GRID_W + x`.
+    pub contradicted: Vec<usize>,
+    pub provenance: Provenance,
+    pub rng: SolverRng,
+    pub backtrack: Backtrack,
+}
+
+/// Tally of a [`HeadlessSolver::run`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RunStats {
+    /// Whether the solver finished before running out of steps. A finished
+    /// map can still hold contradictions; see [`HeadlessSolver::solved`].
+    pub done: bool,
+    /// Cells marked as contradicted along the way.
+    pub contradictions: usize,
+    /// Rewinds by backtracking.
+    pub retries: usize,
+}
+
+/// What the latest headless step did.
+#[derive(Resource, Default)]
+struct StepOutcome {
+    step: Option<Step>,
+    /// Cells marked by the previous step.
+    contradictions: usize,
+}
+
+impl HeadlessSolver {
+    /// Fresh map for `seed`, with the locked cells already collapsed. Tile
+    /// weights aren't biased by any heightmap until [`Self::set_bias`].
+    pub fn new(rules: &Rules, strategy: &Strategy, locks: &Locks, seed: u64) -> Self {
+        let mut world = World::new();
+        world.insert_resource(rules.clone());
+        world.insert_resource(*strategy);
+        world.insert_resource(SolverRng(ChaCha8Rng::seed_from_u64(seed)));
+        world.insert_resource(Backtrack::default());
+        world.init_resource::<StepOutcome>();
+        world.init_resource::<WeightBias>();
+        // Written by propagation; nothing reads them here.
+        world.init_resource::<Messages<DomainReduced>>();
+        world.init_resource::<Messages<Contradiction>>();
+
+        let (mut wfc_grid, provenance) = locked_grid(rules, locks);
+        for y in 0..GRID_H {
+            for x in 0..GRID_W {
+                // Entities only to carry contradiction markers.
+                wfc_grid.entities[y * GRID_W + x] = world.spawn(Tile { x, y }).id();
+            }
+        }
+        world.insert_resource(wfc_grid);
+        world.insert_resource(provenance);
+
+        // A schedule rather than one-off system runs, so `Added<Contradicted>`
+        // sees each step's markers once.
+        let mut schedule = Schedule::default();
+        schedule.add_systems(step);
+        Self { world, schedule }
+    }
+
+    /// Runs one observation. Also returns how many cells the previous one
+    /// marked as contradicted.
+    pub fn step(&mut self) -> (Step, usize) {
+        self.schedule.run(&mut self.world);
+        self.world.resource_mut::<Messages<DomainReduced>>().clear();
+        self.world.resource_mut::<Messages<Contradiction>>().clear();
+        let mut outcome = self.world.resource_mut::<StepOutcome>();
+        (
+            outcome.step.take().unwrap_or(Step::Done),
+            outcome.contradictions,
+        )
+    }
+
+    /// Steps until the solver finishes or `max_steps` observations have run.
+    pub fn run(&mut self, max_steps: usize) -> RunStats {
+        self.run_with(max_steps, |_, _| {})
+    }
+
+    /// Like [`Self::run`], handing each step to `on_step` as it's taken.
+    pub fn run_with(&mut self, max_steps: usize, mut on_step: impl FnMut(&Self, Step)) -> RunStats {
+        let mut stats = RunStats::default();
+        for _ in 0..max_steps {
+            let (step, marked) = self.step();
+            stats.contradictions += marked;
+            match step {
+                Step::Backtracked => stats.retries += 1,
+                Step::Done => stats.done = true,
+                Step::Collapsed(..) => {}
+            }
+            on_step(self, step);
+            if stats.done {
+                break;
+            }
+        }
+        stats
+    }
+
+    /// Swaps the rules, e.g. for changed weights, keeping the map.
+    pub fn set_rules(&mut self, rules: Rules) {
+        self.world.insert_resource(rules);
+    }
+
+    /// Biases tile weights, e.g. by a heightmap, for the coming steps.
+    pub fn set_bias(&mut self, bias: WeightBias) {
+        self.world.insert_resource(bias);
+    }
+
+    /// Collapsed tile of every cell, indexed by `y * GRID_W + x`.
+    pub fn grid(&mut self) -> Vec<Option<TileType>> {
+        self.world.resource::<WfcGrid>().collapsed_grid()
+    }
+
+    /// Whether the map is finished: every cell collapsed and none marked as
+    /// contradicted.
+    pub fn solved(&mut self) -> bool {
+        let contradicted = self
+            .world
+            .query_filtered::<(), With<Contradicted>>()
+            .iter(&self.world)
+            .next()
+            .is_some();
+        !contradicted
+            && self
+                .world
+                .resource::<WfcGrid>()
+                .cells
+                .iter()
+                .all(|cell| cell.collapsed)
+    }
+
+    /// Domains of every cell, as undo and the timeline keep them.
+    pub(crate) fn grid_state(&self) -> GridState {
+        GridState::capture(self.world.resource::<WfcGrid>())
+    }
+
+    /// Ends the run, handing over the map and the solver state behind it.
+    pub(crate) fn into_state(mut self) -> SolverState {
+        let contradicted = self
+            .world
+            .query_filtered::<&Tile, With<Contradicted>>()
+            .iter(&self.world)
+            .map(|tile| tile.y * GRID_W + tile.x)
+            .collect();
+        // All inserted by `new`.
+        let world = &mut self.world;
+        SolverState {
+            grid: world.remove_resource().unwrap(),
+            contradicted,
+            provenance: world.remove_resource().unwrap(),
+            rng: world.remove_resource().unwrap(),
+            backtrack: world.remove_resource().unwrap(),
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn step(
+    mut commands: Commands,
+    rules: Res<Rules>,
+    bias: Res<WeightBias>,
+    strategy: Res<Strategy>,
+    mut rng: ResMut<SolverRng>,
+    mut backtrack: ResMut<Backtrack>,
+    mut provenance: ResMut<Provenance>,
+    mut outcome: ResMut<StepOutcome>,
+    mut grid: ResMut<WfcGrid>,
+    contradicted: Query<(), Added<Contradicted>>,
+) {
+    outcome.contradictions = contradicted.iter().count();
+    outcome.step = Some(collapse_once(
+        &mut commands,
+        &mut rng.0,
+        &rules,
+        &bias,
+        &strategy,
+        &mut backtrack,
+        &mut provenance,
+        &mut grid,
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::seed_field::map_hash;
+
+    /// Seeds are shared between machines, so a known seed has to keep
+    /// producing the same map everywhere. Changes to the solver's choices
+    /// change this hash too; update it only for those.
+    #[test]
+    fn known_seed_produces_known_map() {
+        let mut solver = HeadlessSolver::new(
+            &Rules::default(),
+            &Strategy::default(),
+            &Locks::default(),
+            42,
+        );
+        for _ in 0..GRID_W * GRID_H * 4 {
+            if let (Step::Done, _) = solver.step() {
+                break;
+            }
+        }
+        assert!(solver.solved());
+        let grid = solver.grid();
+        assert_eq!(format!("{:08x}", map_hash(&grid)), "b2e5b83a");
+    }
+
+    /// Water and wall locked either side of a cell that would have to be
+    /// sand for the one and grass for the other, so the map can't finish.
+    #[test]
+    fn an_impossible_map_is_not_solved() {
+        let chain = [
+            TileType::Water,
+            TileType::Sand,
+            TileType::Grass,
+            TileType::Wall,
+        ];
+        let rules = Rules::from_sample("Chain".to_string(), &chain, chain.len());
+        let mut locks = Locks::default();
+        locks.toggle(0, 0, TileType::Water);
+        locks.toggle(2, 0, TileType::Wall);
+        let mut solver = HeadlessSolver::new(&rules, &Strategy::default(), &locks, 42);
+        for _ in 0..GRID_W * GRID_H * 4 {
+            if let (Step::Done, _) = solver.step() {
+                break;
+            }
+        }
+        assert!(!solver.solved());
+    }
+}
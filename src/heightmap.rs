@@ -0,0 +1,66 @@
+use std::path::Path;
+
+use bevy::prelude::*;
+
+use crate::rules::Rules;
+use crate::sample::read_png;
+use crate::{GRID_H, GRID_W, TileType};
+
+/// Weight multiplier left for a tile at the opposite end of the heightmap
+/// from where it belongs, so the solver can still use it to satisfy the rules.
+const BIAS_FLOOR: f32 = 0.05;
+
+/// Per-cell multipliers on the rules' tile weights, indexed by
+/// `y * GRID_W + x`. Empty unless a heightmap was given. They shape which
+/// tile an observed cell picks, not which cell is observed next.
+#[derive(Resource, Clone, Default)]
+pub struct WeightBias(Vec<[f32; TileType::ALL.len()]>);
+
+impl WeightBias {
+    pub fn weight(&self, rules: &Rules, tile: TileType, x: usize, y: usize) -> f32 {
+        let bias = self
+            .0
+            .get(y * GRID_W + x)
+            .map_or(1.0, |cell| cell[tile.index()]);
+        rules.weight(tile) * bias
+    }
+}
+
+/// Height each tile is most likely at, from 0 (black) to 1 (white).
+fn tile_height(tile: TileType) -> f32 {
+    match tile {
+        TileType::Water => 0.0,
+        TileType::Sand => 0.5,
+        TileType::Grass => 1.0,
+        TileType::Floor | TileType::StairsUp | TileType::StairsDown => 0.0,
+        TileType::Corridor => 0.25,
+        TileType::Door => 0.5,
+        TileType::Wall => 1.0,
+    }
+}
+
+/// Reads a grayscale image, stretched over the whole grid, into weight
+/// multipliers that fall off linearly with the distance between a cell's
+/// height and each tile's.
+pub fn read_bias(path: &Path) -> Result<WeightBias, String> {
+    let image = read_png(path)?;
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    let mut cells = Vec::with_capacity(GRID_W * GRID_H);
+    for y in 0..GRID_H {
+        for x in 0..GRID_W {
+            // Image rows run top to bottom, grid rows bottom to top.
+            let px = x * width / GRID_W;
+            let py = (GRID_H - 1 - y) * height / GRID_H;
+            let color = image
+                .get_color_at(px as u32, py as u32)
+                .map_err(|err| err.to_string())?
+                .to_srgba();
+            let level = 0.299 * color.red + 0.587 * color.green + 0.114 * color.blue;
+            cells.push(TileType::ALL.map(|tile| {
+                let closeness = (1.0 - (level - tile_height(tile)).abs() * 2.0).max(0.0);
+                BIAS_FLOOR + (1.0 - BIAS_FLOOR) * closeness
+            }));
+        }
+    }
+    Ok(WeightBias(cells))
+}
@@ -0,0 +1,175 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::cli::Cli;
+use crate::inspector::Provenance;
+use crate::keybindings::{Action, Keybindings};
+use crate::rules::Rules;
+use crate::stepping::{StepBudget, plan_steps};
+use crate::timeline::Timeline;
+use crate::{
+    Cell, Contradicted, GRID_H, GRID_W, Seed, TileType, WfcGrid, collapse_step,
+    domain_from_neighbors,
+};
+
+/// Oldest states are dropped beyond this many undo steps.
+const MAX_HISTORY: usize = 256;
+
+/// Domain and collapse flag of every cell, indexed by `y * GRID_W + x`. Also
+/// the grid part of save files.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct GridState(Vec<(Vec<TileType>, bool)>);
+
+impl GridState {
+    pub fn capture(grid: &WfcGrid) -> Self {
+        Self(
+            grid.cells
+                .iter()
+                .map(|cell| (cell.possible.clone(), cell.collapsed))
+                .collect(),
+        )
+    }
+
+    /// The cells set in `grid` collapsed to their tile, and the others
+    /// narrowed by those neighbors, like a fresh map with locked cells.
+    pub fn from_grid(rules: &Rules, grid: &[Option<TileType>]) -> Self {
+        let mut cells = Vec::with_capacity(GRID_W * GRID_H);
+        for y in 0..GRID_H {
+            for x in 0..GRID_W {
+                cells.push(match grid[y * GRID_W + x] {
+                    Some(tile) => (vec![tile], true),
+                    None => (domain_from_neighbors(rules, grid, x, y), false),
+                });
+            }
+        }
+        Self(cells)
+    }
+
+    /// Writes the state back. Cells that differ lose their contradiction
+    /// markers and have their elimination logs rebuilt from the restored
+    /// neighbors.
+    pub fn restore(
+        &self,
+        commands: &mut Commands,
+        rules: &Rules,
+        provenance: &mut Provenance,
+        grid: &mut WfcGrid,
+    ) {
+        let mut restored = Vec::new();
+        for (i, (possible, collapsed)) in self.0.iter().enumerate() {
+            let cell = &mut grid.cells[i];
+            if cell.possible != *possible || cell.collapsed != *collapsed {
+                *cell = Cell {
+                    possible: possible.clone(),
+                    collapsed: *collapsed,
+                };
+                commands
+                    .entity(grid.entities[i])
+                    .try_remove::<Contradicted>();
+                restored.push((i % GRID_W, i / GRID_W));
+            }
+        }
+
+        if !restored.is_empty() {
+            grid.invalidate();
+        }
+        let grid = grid.collapsed_grid();
+        for (x, y) in restored {
+            provenance.rebuild(rules, &grid, x, y);
+        }
+    }
+}
+
+/// Grid states before each manual edit, for undo and redo. Cleared when a new
+/// map is generated.
+#[derive(Resource, Default)]
+pub struct History {
+    undo: VecDeque<GridState>,
+    redo: Vec<GridState>,
+    /// Also record each frame of automatic collapses, so undo can rewind
+    /// the solver itself. Turned on by `--undo-collapses`.
+    pub record_collapses: bool,
+}
+
+impl History {
+    pub fn record(&mut self, state: GridState) {
+        if self.undo.len() == MAX_HISTORY {
+            self.undo.pop_front();
+        }
+        self.undo.push_back(state);
+        self.redo.clear();
+    }
+}
+
+pub struct HistoryPlugin;
+
+impl Plugin for HistoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<History>()
+            .add_systems(Startup, enable_from_cli)
+            .add_systems(
+                Update,
+                (
+                    clear_history.run_if(resource_changed::<Seed>),
+                    undo_redo,
+                    record_collapses
+                        .run_if(|history: Res<History>| history.record_collapses)
+                        .after(plan_steps),
+                )
+                    .chain()
+                    .before(collapse_step),
+            );
+    }
+}
+
+fn enable_from_cli(cli: Res<Cli>, mut history: ResMut<History>) {
+    history.record_collapses = cli.undo_collapses;
+}
+
+fn clear_history(mut history: ResMut<History>) {
+    history.undo.clear();
+    history.redo.clear();
+}
+
+#[allow(clippy::too_many_arguments)]
+fn undo_redo(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keybindings: Res<Keybindings>,
+    rules: Res<Rules>,
+    mut history: ResMut<History>,
+    mut provenance: ResMut<Provenance>,
+    mut timeline: ResMut<Timeline>,
+    mut grid: ResMut<WfcGrid>,
+) {
+    let undo = keybindings.just_pressed(Action::Undo, &keyboard_input);
+    let redo = keybindings.just_pressed(Action::Redo, &keyboard_input);
+
+    let target = if undo {
+        history.undo.pop_back()
+    } else if redo {
+        history.redo.pop()
+    } else {
+        return;
+    };
+    let Some(target) = target else {
+        return;
+    };
+
+    let current = GridState::capture(&grid);
+    if undo {
+        history.redo.push(current);
+    } else {
+        history.undo.push_back(current);
+    }
+    target.restore(&mut commands, &rules, &mut provenance, &mut grid);
+    timeline.rebase(GridState::capture(&grid));
+}
+
+fn record_collapses(budget: Res<StepBudget>, mut history: ResMut<History>, grid: Res<WfcGrid>) {
+    if budget.0 > 0 && grid.cells.iter().any(|cell| !cell.collapsed) {
+        history.record(GridState::capture(&grid));
+    }
+}
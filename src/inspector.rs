@@ -0,0 +1,275 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::camera::cursor_world_position;
+use crate::keybindings::{Action, Keybindings};
+use crate::rules::Rules;
+use crate::{
+    GRID_H, GRID_W, TILE_SIZE, TileSet, TileType, WfcGrid, cell_at, cell_center, collapse_step,
+    neighbor_coords,
+};
+
+const PANEL_TOP: f32 = 140.0;
+const PANEL_MARGIN: f32 = 8.0;
+const PANEL_FONT_SIZE: f32 = 14.0;
+const PANEL_BACKGROUND: Color = Color::srgba(0.05, 0.05, 0.05, 0.85);
+/// Older eliminations of a cell are summarized as a count.
+const MAX_LISTED: usize = 16;
+const INSPECTED_COLOR: Color = Color::srgb(0.2, 0.9, 1.0);
+const SOURCE_COLOR: Color = Color::srgba(0.2, 0.9, 1.0, 0.5);
+
+/// Tiles removed from a cell's domain because a neighbor collapsed, or with
+/// AC-4 propagation, because a neighbor's domain narrowed.
+pub struct Elimination {
+    /// Collapse during which this happened, counting from 1 for each map.
+    /// `None` when the entry was reconstructed after the domain was reset,
+    /// e.g. for a new map with locked cells, a reset or an undo.
+    pub step: Option<usize>,
+    pub source: (usize, usize),
+    /// What the source had collapsed to; `None` if it was still uncollapsed.
+    pub source_tile: Option<TileType>,
+    pub removed: TileSet,
+}
+
+/// Per-cell log of eliminations, indexed by `y * GRID_W + x`. Replaced for
+/// every map.
+#[derive(Resource)]
+pub struct Provenance {
+    cells: Vec<Vec<Elimination>>,
+    step: usize,
+    /// Tiles propagation has eliminated on this map, for the diagnostics.
+    eliminated: usize,
+}
+
+impl Default for Provenance {
+    fn default() -> Self {
+        Self {
+            // Room for a cell losing each of its tiles separately, the most
+            // propagation logs for it between resets.
+            cells: (0..GRID_W * GRID_H)
+                .map(|_| Vec::with_capacity(TileType::ALL.len()))
+                .collect(),
+            step: 0,
+            eliminated: 0,
+        }
+    }
+}
+
+impl Provenance {
+    /// Starts the next collapse and returns its number.
+    pub fn begin_step(&mut self) -> usize {
+        self.step += 1;
+        self.step
+    }
+
+    /// Collapses propagated from on this map, including ones backtracking
+    /// rewound.
+    pub fn collapses(&self) -> usize {
+        self.step
+    }
+
+    pub fn eliminated(&self) -> usize {
+        self.eliminated
+    }
+
+    pub fn record(&mut self, x: usize, y: usize, elimination: Elimination) {
+        if elimination.step.is_some() {
+            self.eliminated += elimination.removed.len();
+        }
+        self.cells[y * GRID_W + x].push(elimination);
+    }
+
+    /// Forgets a cell's log, e.g. when a contradiction resets its domain.
+    pub fn clear(&mut self, x: usize, y: usize) {
+        self.cells[y * GRID_W + x].clear();
+    }
+
+    /// Replaces a cell's log with what its collapsed neighbors in `grid`
+    /// eliminate from the ruleset's tiles, in no particular order.
+    pub fn rebuild(&mut self, rules: &Rules, grid: &[Option<TileType>], x: usize, y: usize) {
+        self.clear(x, y);
+        for (nx, ny) in neighbor_coords(x, y) {
+            let Some(source_tile) = grid[ny * GRID_W + nx] else {
+                continue;
+            };
+            let removed: TileSet = rules
+                .tiles()
+                .iter()
+                .filter(|&tile| !rules.allows(source_tile, tile))
+                .collect();
+            if !removed.is_empty() {
+                self.record(
+                    x,
+                    y,
+                    Elimination {
+                        step: None,
+                        source: (nx, ny),
+                        source_tile: Some(source_tile),
+                        removed,
+                    },
+                );
+            }
+        }
+    }
+
+    fn eliminations(&self, x: usize, y: usize) -> &[Elimination] {
+        &self.cells[y * GRID_W + x]
+    }
+}
+
+/// Debug mode toggled with `J`: left-clicking a cell shows how its domain
+/// was narrowed instead of collapsing it.
+#[derive(Resource, Default)]
+pub struct Inspector {
+    pub active: bool,
+    cell: Option<(usize, usize)>,
+}
+
+impl Inspector {
+    /// Turns the inspector on with `cell` inspected.
+    pub fn inspect(&mut self, cell: (usize, usize)) {
+        self.active = true;
+        self.cell = Some(cell);
+    }
+}
+
+#[derive(Component)]
+struct InspectorPanel;
+
+pub struct InspectorPlugin;
+
+impl Plugin for InspectorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Inspector>()
+            .add_systems(Startup, spawn_panel)
+            .add_systems(
+                Update,
+                (toggle_inspector, pick_cell).chain().before(collapse_step),
+            )
+            .add_systems(Update, (update_panel, draw_inspected).after(collapse_step));
+    }
+}
+
+fn spawn_panel(mut commands: Commands) {
+    commands.spawn((
+        InspectorPanel,
+        Text::default(),
+        TextFont::from_font_size(PANEL_FONT_SIZE),
+        Node {
+            position_type: PositionType::Absolute,
+            right: Val::Px(PANEL_MARGIN),
+            top: Val::Px(PANEL_TOP),
+            padding: UiRect::all(Val::Px(4.0)),
+            ..default()
+        },
+        BackgroundColor(PANEL_BACKGROUND),
+        Visibility::Hidden,
+    ));
+}
+
+fn toggle_inspector(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keybindings: Res<Keybindings>,
+    mut inspector: ResMut<Inspector>,
+) {
+    if keybindings.just_pressed(Action::ToggleInspector, &keyboard_input) {
+        inspector.active = !inspector.active;
+        inspector.cell = None;
+    }
+}
+
+fn pick_cell(
+    mouse: Res<ButtonInput<MouseButton>>,
+    mut inspector: ResMut<Inspector>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+    interactions: Query<&Interaction>,
+) {
+    if !inspector.active || !mouse.just_pressed(MouseButton::Left) {
+        return;
+    }
+    if interactions.iter().any(|i| *i != Interaction::None) {
+        return;
+    }
+    let Ok(window) = windows.single() else {
+        return;
+    };
+    inspector.cell = cursor_world_position(window, &cameras).and_then(cell_at);
+}
+
+fn update_panel(
+    inspector: Res<Inspector>,
+    provenance: Res<Provenance>,
+    grid: Res<WfcGrid>,
+    mut panels: Query<(&mut Text, &mut Visibility), With<InspectorPanel>>,
+) {
+    let Ok((mut text, mut visibility)) = panels.single_mut() else {
+        return;
+    };
+    let Some((x, y)) = inspector.cell.filter(|_| inspector.active) else {
+        visibility.set_if_neq(Visibility::Hidden);
+        return;
+    };
+    visibility.set_if_neq(Visibility::Inherited);
+    if !inspector.is_changed() && !provenance.is_changed() {
+        return;
+    }
+
+    let mut lines = vec![format!("cell ({x}, {y})")];
+    let tile = grid.cell(x, y);
+    lines.push(if tile.collapsed {
+        format!("collapsed: {:?}", tile.possible[0])
+    } else {
+        format!("domain: {}", tile_list(tile.possible.iter().copied()))
+    });
+
+    let eliminations = provenance.eliminations(x, y);
+    if eliminations.is_empty() {
+        lines.push("no eliminations".to_string());
+    }
+    let skipped = eliminations.len().saturating_sub(MAX_LISTED);
+    if skipped > 0 {
+        lines.push(format!("... {skipped} earlier"));
+    }
+    for elimination in &eliminations[skipped..] {
+        let step = elimination
+            .step
+            .map_or_else(|| "--".to_string(), |step| format!("#{step}"));
+        let (sx, sy) = elimination.source;
+        let source = match elimination.source_tile {
+            Some(tile) => format!("({sx}, {sy}) = {tile:?}"),
+            None => format!("({sx}, {sy}) narrowed"),
+        };
+        lines.push(format!(
+            "{step} {source} removed {}",
+            tile_list(elimination.removed.iter())
+        ));
+    }
+    text.0 = lines.join("\n");
+}
+
+fn tile_list(tiles: impl Iterator<Item = TileType>) -> String {
+    let names: Vec<String> = tiles.map(|tile| format!("{tile:?}")).collect();
+    names.join(", ")
+}
+
+/// Outlines the inspected cell and, more faintly, the neighbors that
+/// narrowed it.
+fn draw_inspected(mut gizmos: Gizmos, inspector: Res<Inspector>, provenance: Res<Provenance>) {
+    let Some((x, y)) = inspector.cell.filter(|_| inspector.active) else {
+        return;
+    };
+    for elimination in provenance.eliminations(x, y) {
+        let (sx, sy) = elimination.source;
+        gizmos.rect_2d(
+            Isometry2d::from_translation(cell_center(sx, sy)),
+            Vec2::splat(TILE_SIZE - 5.0),
+            SOURCE_COLOR,
+        );
+    }
+    gizmos.rect_2d(
+        Isometry2d::from_translation(cell_center(x, y)),
+        Vec2::splat(TILE_SIZE - 1.0),
+        INSPECTED_COLOR,
+    );
+}
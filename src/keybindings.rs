@@ -0,0 +1,493 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::asset_files::{asset_path, read_asset};
+
+/// Bindings file under the asset root, read at startup and whenever the
+/// config file's overrides change.
+const KEYBINDINGS_FILE: &str = "keybindings.ron";
+/// The bindings file as shipped, which holds the defaults.
+const DEFAULT_BINDINGS: &str = include_str!("../assets/keybindings.ron");
+const PANEL_FONT_SIZE: f32 = 14.0;
+const PANEL_BACKGROUND: Color = Color::srgba(0.05, 0.05, 0.05, 0.9);
+
+/// Everything that can be triggered from the keyboard.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    Restart,
+    PanUp,
+    PanDown,
+    PanLeft,
+    PanRight,
+    PauseResume,
+    Step,
+    SpeedUp,
+    SlowDown,
+    Undo,
+    Redo,
+    ToggleLockTool,
+    ToggleInspector,
+    TogglePathTool,
+    CycleComparison,
+    CycleFloor,
+    /// Palette entry, counting from 1.
+    SelectTile(usize),
+    CloseMenu,
+    CycleAutotile,
+    CycleTheme,
+    ToggleGrid,
+    ToggleCoordinates,
+    ToggleView3d,
+    ToggleCoastline,
+    ToggleMinimap,
+    ToggleDayNight,
+    ToggleWavefront,
+    ToggleBackend,
+    ToggleBackground,
+    ToggleSound,
+    ToggleStats,
+    TogglePerformance,
+    ToggleReport,
+    ToggleBindings,
+    ShowPresets,
+    ToggleFullscreen,
+    ToggleVsync,
+    CycleResolution,
+    ExportPng,
+    CycleExportStyle,
+    ExportTiled,
+    ExportJson,
+    ExportCsv,
+    ExportSvg,
+    ExportSchematic,
+    ExportObj,
+    ExportGltf,
+    ExportNav,
+    ExportGraph,
+    CaptureAnimation,
+    SaveReplay,
+    PlayReplay,
+    CopySeed,
+    Screenshot,
+}
+
+impl Action {
+    /// Every action, in the order they are listed in the bindings view.
+    pub const ALL: [Action; 62] = [
+        Action::Restart,
+        Action::PanUp,
+        Action::PanDown,
+        Action::PanLeft,
+        Action::PanRight,
+        Action::PauseResume,
+        Action::Step,
+        Action::SpeedUp,
+        Action::SlowDown,
+        Action::Undo,
+        Action::Redo,
+        Action::ToggleLockTool,
+        Action::ToggleInspector,
+        Action::TogglePathTool,
+        Action::CycleComparison,
+        Action::CycleFloor,
+        Action::SelectTile(1),
+        Action::SelectTile(2),
+        Action::SelectTile(3),
+        Action::SelectTile(4),
+        Action::SelectTile(5),
+        Action::SelectTile(6),
+        Action::SelectTile(7),
+        Action::SelectTile(8),
+        Action::SelectTile(9),
+        Action::CloseMenu,
+        Action::CycleAutotile,
+        Action::CycleTheme,
+        Action::ToggleGrid,
+        Action::ToggleCoordinates,
+        Action::ToggleView3d,
+        Action::ToggleCoastline,
+        Action::ToggleMinimap,
+        Action::ToggleDayNight,
+        Action::ToggleWavefront,
+        Action::ToggleBackend,
+        Action::ToggleBackground,
+        Action::ToggleSound,
+        Action::ToggleStats,
+        Action::TogglePerformance,
+        Action::ToggleReport,
+        Action::ToggleBindings,
+        Action::ShowPresets,
+        Action::ToggleFullscreen,
+        Action::ToggleVsync,
+        Action::CycleResolution,
+        Action::ExportPng,
+        Action::CycleExportStyle,
+        Action::ExportTiled,
+        Action::ExportJson,
+        Action::ExportCsv,
+        Action::ExportSvg,
+        Action::ExportSchematic,
+        Action::ExportObj,
+        Action::ExportGltf,
+        Action::ExportNav,
+        Action::ExportGraph,
+        Action::CaptureAnimation,
+        Action::SaveReplay,
+        Action::PlayReplay,
+        Action::CopySeed,
+        Action::Screenshot,
+    ];
+
+    pub fn label(self) -> String {
+        match self {
+            Action::Restart => "Restart with a new seed".into(),
+            Action::PanUp => "Pan up".into(),
+            Action::PanDown => "Pan down".into(),
+            Action::PanLeft => "Pan left".into(),
+            Action::PanRight => "Pan right".into(),
+            Action::PauseResume => "Pause / resume generation".into(),
+            Action::Step => "Single step while paused".into(),
+            Action::SpeedUp => "Faster generation".into(),
+            Action::SlowDown => "Slower generation".into(),
+            Action::Undo => "Undo".into(),
+            Action::Redo => "Redo".into(),
+            Action::ToggleLockTool => "Toggle lock tool (click to lock cells)".into(),
+            Action::ToggleInspector => "Toggle cell inspector (click to inspect)".into(),
+            Action::TogglePathTool => "Toggle path tool (click a start and a goal)".into(),
+            Action::CycleComparison => "Cycle side-by-side comparison".into(),
+            Action::CycleFloor => "Show the next dungeon floor".into(),
+            Action::SelectTile(n) => format!("Select palette tile {n}"),
+            Action::CloseMenu => "Close popup".into(),
+            Action::CycleAutotile => "Cycle autotile set".into(),
+            Action::CycleTheme => "Cycle color theme".into(),
+            Action::ToggleGrid => "Toggle grid lines".into(),
+            Action::ToggleCoordinates => "Toggle coordinate labels".into(),
+            Action::ToggleView3d => "Toggle 3D view".into(),
+            Action::ToggleCoastline => "Toggle coastline".into(),
+            Action::ToggleMinimap => "Toggle minimap".into(),
+            Action::ToggleDayNight => "Toggle day/night cycle".into(),
+            Action::ToggleWavefront => "Toggle propagation outlines".into(),
+            Action::ToggleBackend => "Cycle sprite / chunked / texture rendering".into(),
+            Action::ToggleBackground => "Toggle background generation".into(),
+            Action::ToggleSound => "Toggle collapse sound".into(),
+            Action::ToggleStats => "Toggle statistics".into(),
+            Action::TogglePerformance => "Toggle performance overlay".into(),
+            Action::ToggleReport => "Toggle generation reports".into(),
+            Action::ToggleBindings => "Show controls".into(),
+            Action::ShowPresets => "Show scenario presets".into(),
+            Action::ToggleFullscreen => "Toggle fullscreen".into(),
+            Action::ToggleVsync => "Toggle vsync".into(),
+            Action::CycleResolution => "Cycle window size".into(),
+            Action::ExportPng => "Export the map as a PNG".into(),
+            Action::CycleExportStyle => "Cycle PNG export scale".into(),
+            Action::ExportTiled => "Export the map for Tiled".into(),
+            Action::ExportJson => "Export the map as JSON".into(),
+            Action::ExportCsv => "Export the map as CSV".into(),
+            Action::ExportSvg => "Export the map as SVG".into(),
+            Action::ExportSchematic => "Export the map as a Minecraft schematic".into(),
+            Action::ExportObj => "Export the 3D view as OBJ".into(),
+            Action::ExportGltf => "Export the 3D view as glTF".into(),
+            Action::ExportNav => "Export the navigation grid".into(),
+            Action::ExportGraph => "Export the rules as a Graphviz graph".into(),
+            Action::CaptureAnimation => "Record an animation of the generation".into(),
+            Action::SaveReplay => "Save a replay of this map".into(),
+            Action::PlayReplay => "Play the newest replay".into(),
+            Action::CopySeed => "Copy the seed and map hash".into(),
+            Action::Screenshot => "Save a screenshot".into(),
+        }
+    }
+}
+
+/// A key, optionally with modifiers. Plain keys don't fire while `Ctrl` is
+/// held, so `Ctrl` combinations can reuse them.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Binding {
+    Key(KeyCode),
+    Ctrl(KeyCode),
+    CtrlShift(KeyCode),
+}
+
+impl Binding {
+    fn key(self) -> KeyCode {
+        match self {
+            Binding::Key(key) | Binding::Ctrl(key) | Binding::CtrlShift(key) => key,
+        }
+    }
+
+    fn modifiers_match(self, input: &ButtonInput<KeyCode>) -> bool {
+        let ctrl = input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+        let shift = input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+        match self {
+            Binding::Key(_) => !ctrl,
+            Binding::Ctrl(_) => ctrl && !shift,
+            Binding::CtrlShift(_) => ctrl && shift,
+        }
+    }
+
+    fn name(self) -> String {
+        let key = format!("{:?}", self.key());
+        let key = key
+            .strip_prefix("Key")
+            .or_else(|| key.strip_prefix("Digit"))
+            .unwrap_or(&key);
+        match self {
+            Binding::Key(_) => key.to_string(),
+            Binding::Ctrl(_) => format!("Ctrl+{key}"),
+            Binding::CtrlShift(_) => format!("Ctrl+Shift+{key}"),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct KeybindingsFile {
+    bindings: HashMap<Action, Vec<Binding>>,
+}
+
+/// Keys bound to each action. Defaults are overridden per action by
+/// `assets/keybindings.ron`, so an edited copy only needs the ones it
+/// changes.
+#[derive(Resource)]
+pub struct Keybindings {
+    bindings: HashMap<Action, Vec<Binding>>,
+}
+
+impl Default for Keybindings {
+    /// The bindings file as shipped, compiled in.
+    fn default() -> Self {
+        let file: KeybindingsFile =
+            ron::from_str(DEFAULT_BINDINGS).expect("the shipped bindings file parses");
+        Self {
+            bindings: file.bindings,
+        }
+    }
+}
+
+impl Keybindings {
+    /// Defaults overridden by the bindings file, if it exists and parses.
+    pub fn load() -> Self {
+        let mut keybindings = Self::default();
+        let path = asset_path(KEYBINDINGS_FILE);
+        let text = match read_asset(&path) {
+            Ok(text) => text,
+            Err(err) => {
+                warn!(
+                    "using default key bindings, could not read {}: {err}",
+                    path.display()
+                );
+                return keybindings;
+            }
+        };
+        match ron::from_str::<KeybindingsFile>(&text) {
+            Ok(file) => keybindings.bindings.extend(file.bindings),
+            Err(err) => warn!(
+                "using default key bindings, could not parse {}: {err}",
+                path.display()
+            ),
+        }
+        keybindings
+    }
+
+    /// Replaces the bindings of the given actions.
+    pub fn extend(&mut self, bindings: HashMap<Action, Vec<Binding>>) {
+        self.bindings.extend(bindings);
+    }
+
+    fn bindings(&self, action: Action) -> &[Binding] {
+        self.bindings.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn just_pressed(&self, action: Action, input: &ButtonInput<KeyCode>) -> bool {
+        self.bindings(action)
+            .iter()
+            .any(|binding| input.just_pressed(binding.key()) && binding.modifiers_match(input))
+    }
+
+    pub fn pressed(&self, action: Action, input: &ButtonInput<KeyCode>) -> bool {
+        self.bindings(action)
+            .iter()
+            .any(|binding| input.pressed(binding.key()) && binding.modifiers_match(input))
+    }
+
+    /// Like [`Keybindings::pressed`], but ignoring keys that are also bound
+    /// to `other`.
+    pub fn pressed_except(
+        &self,
+        action: Action,
+        other: Action,
+        input: &ButtonInput<KeyCode>,
+    ) -> bool {
+        let shared = self.bindings(other);
+        self.bindings(action).iter().any(|binding| {
+            !shared.contains(binding)
+                && input.pressed(binding.key())
+                && binding.modifiers_match(input)
+        })
+    }
+
+    /// Bound keys of an action for display, e.g. `W / ArrowUp`.
+    pub fn describe(&self, action: Action) -> String {
+        let names: Vec<String> = self.bindings(action).iter().map(|b| b.name()).collect();
+        if names.is_empty() {
+            "unbound".to_string()
+        } else {
+            names.join(" / ")
+        }
+    }
+}
+
+/// Mouse interactions for the controls panel, with the keys of the actions
+/// that switch tools filled in from the current bindings.
+fn mouse_controls(keybindings: &Keybindings) -> Vec<(String, String)> {
+    let lock = keybindings.describe(Action::ToggleLockTool);
+    let inspector = keybindings.describe(Action::ToggleInspector);
+    let path = keybindings.describe(Action::TogglePathTool);
+    let tile = keybindings.describe(Action::SelectTile(1));
+    vec![
+        (
+            "Left-click".into(),
+            format!("Collapse a cell to the palette tile ({tile}, ...) or pick one"),
+        ),
+        ("Right-click".into(), "Clear a collapsed cell".into()),
+        (
+            "Shift+Right-click".into(),
+            "Clear a collapsed cell and the cells around it".into(),
+        ),
+        (
+            format!("{lock}, then click"),
+            "Lock or unlock a collapsed cell".into(),
+        ),
+        (
+            format!("{inspector}, then click"),
+            "Inspect which collapses eliminated a cell's tiles".into(),
+        ),
+        (
+            format!("{path}, then click twice"),
+            "Show the cheapest path between two cells".into(),
+        ),
+        ("Mouse wheel".into(), "Zoom".into()),
+        (
+            format!(
+                "Drag in 3D view ({})",
+                keybindings.describe(Action::ToggleView3d)
+            ),
+            "Orbit the camera".into(),
+        ),
+        ("Drag timeline".into(), "Scrub through the collapses".into()),
+    ]
+}
+
+#[derive(Component)]
+struct BindingsPanel;
+
+pub struct KeybindingsPlugin;
+
+impl Plugin for KeybindingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Keybindings::load()).add_systems(
+            Update,
+            (
+                spawn_panel.run_if(resource_changed::<Keybindings>),
+                toggle_panel,
+            )
+                .chain(),
+        );
+    }
+}
+
+/// Centered list of the current bindings and the mouse controls, hidden until
+/// toggled. Rebuilt when the bindings change, keeping its visibility.
+fn spawn_panel(
+    mut commands: Commands,
+    keybindings: Res<Keybindings>,
+    panels: Query<(Entity, &Visibility), With<BindingsPanel>>,
+) {
+    let mut visibility = Visibility::Hidden;
+    for (panel, panel_visibility) in panels.iter() {
+        visibility = *panel_visibility;
+        commands.entity(panel).despawn();
+    }
+    commands
+        .spawn((
+            BindingsPanel,
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            visibility,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    Node {
+                        display: Display::Grid,
+                        grid_template_columns: vec![GridTrack::auto(), GridTrack::auto()],
+                        column_gap: Val::Px(12.0),
+                        padding: UiRect::all(Val::Px(8.0)),
+                        ..default()
+                    },
+                    BackgroundColor(PANEL_BACKGROUND),
+                ))
+                .with_children(|grid| {
+                    for action in Action::ALL {
+                        grid.spawn((
+                            Text::new(keybindings.describe(action)),
+                            TextFont::from_font_size(PANEL_FONT_SIZE),
+                        ));
+                        grid.spawn((
+                            Text::new(action.label()),
+                            TextFont::from_font_size(PANEL_FONT_SIZE),
+                        ));
+                    }
+                    grid.spawn((
+                        Text::new("Mouse"),
+                        TextFont::from_font_size(PANEL_FONT_SIZE),
+                        Node {
+                            grid_column: GridPlacement::span(2),
+                            margin: UiRect::top(Val::Px(8.0)),
+                            ..default()
+                        },
+                    ));
+                    for (input, description) in mouse_controls(&keybindings) {
+                        grid.spawn((Text::new(input), TextFont::from_font_size(PANEL_FONT_SIZE)));
+                        grid.spawn((
+                            Text::new(description),
+                            TextFont::from_font_size(PANEL_FONT_SIZE),
+                        ));
+                    }
+                });
+        });
+}
+
+fn toggle_panel(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keybindings: Res<Keybindings>,
+    mut panels: Query<&mut Visibility, With<BindingsPanel>>,
+) {
+    if keybindings.just_pressed(Action::ToggleBindings, &keyboard_input) {
+        for mut visibility in panels.iter_mut() {
+            visibility.toggle_visible_hidden();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_action_has_a_default_binding() {
+        let keybindings = Keybindings::default();
+        for action in Action::ALL {
+            assert!(
+                !keybindings.bindings(action).is_empty(),
+                "{action:?} is unbound"
+            );
+        }
+    }
+}
@@ -0,0 +1,27 @@
+//! Z ordering of everything drawn in the 2D view. Per-tile overlays are
+//! children of their tile, so their offsets are relative to it; the rest are
+//! absolute. Decorations and the player go in the gap between [`FLASH`] and
+//! [`LABEL`].
+
+/// Terrain tiles and chunk meshes.
+pub const TILE: f32 = 0.0;
+/// Border-blended tile colors over the whole grid.
+pub const BLEND: f32 = 0.4;
+/// Possibility swatches, relative to their tile.
+pub const SWATCH: f32 = 0.5;
+/// Material surfaces such as animated water, relative to their tile.
+pub const SURFACE: f32 = 0.6;
+/// Contradiction flash, relative to its tile.
+pub const FLASH: f32 = 0.8;
+/// Decorations, offset by up to [`DECORATION_SPREAD`] so lower ones are
+/// drawn over those above them.
+pub const DECORATION: f32 = 0.85;
+pub const DECORATION_SPREAD: f32 = 0.04;
+/// Spawn point markers, over decorations.
+pub const SPAWN_POINT: f32 = 0.9;
+/// The player, over decorations and markers.
+pub const PLAYER: f32 = 0.95;
+/// Coordinate labels.
+pub const LABEL: f32 = 1.0;
+/// Ambient tint over the whole map.
+pub const TINT: f32 = 5.0;
@@ -0,0 +1,1436 @@
+//! Wave function collapse map generator for Bevy.
+//!
+//! Add [`WfcPlugin`] to an app with `DefaultPlugins` to get the generator
+//! with its tools and views, configured by [`WfcConfig`]; the map being
+//! solved is the [`WfcGrid`] resource, and setting [`Seed`] starts a new
+//! one. [`headless::HeadlessSolver`] solves maps without an app, from
+//! [`rules::Rules`] loaded with [`rules::Rules::load`] and a
+//! [`strategy::Strategy`]. Order systems around the solver with the
+//! [`WfcSet`] sets.
+
+mod animation;
+mod asset_files;
+mod autotile;
+mod background;
+mod bench;
+mod blend;
+mod camera;
+#[cfg(not(target_arch = "wasm32"))]
+mod capture;
+mod chunked;
+pub mod cli;
+mod coastline;
+mod collision;
+mod compare;
+pub mod config;
+mod contradiction;
+mod day_night;
+mod debug_overlay;
+mod decorations;
+mod diagnostics;
+pub mod display;
+mod entropy;
+pub mod events;
+#[cfg(not(target_arch = "wasm32"))]
+mod export;
+mod feedback;
+mod floors;
+mod gamepad;
+mod generation;
+#[cfg(feature = "gpu")]
+mod gpu;
+pub mod headless;
+pub mod heightmap;
+mod history;
+mod inspector;
+mod keybindings;
+mod layers;
+pub mod lock;
+mod manual;
+mod map_import;
+#[cfg(not(target_arch = "wasm32"))]
+mod mesh_export;
+mod minimap;
+mod nav;
+mod path;
+mod perf_overlay;
+#[cfg(feature = "physics")]
+mod physics;
+mod player;
+mod presets;
+mod render;
+#[cfg(not(target_arch = "wasm32"))]
+mod replay;
+#[cfg(not(target_arch = "wasm32"))]
+mod report;
+mod reset;
+pub mod rules;
+mod ruleset_menu;
+mod sample;
+#[cfg(not(target_arch = "wasm32"))]
+mod saves;
+#[cfg(not(target_arch = "wasm32"))]
+mod schematic;
+#[cfg(not(target_arch = "wasm32"))]
+mod screenshot;
+mod seed_field;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod server;
+pub mod spawn_points;
+mod speed;
+mod stats;
+mod stepping;
+pub mod strategy;
+mod swatches;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod sweep;
+mod texture_map;
+mod theme;
+mod tile_palette;
+mod timeline;
+mod tooltip;
+mod touch;
+mod variation;
+mod view3d;
+mod wang;
+mod water;
+mod wavefront;
+mod weights;
+
+use animation::AnimationPlugin;
+use autotile::AutotilePlugin;
+use background::BackgroundPlugin;
+use bench::BenchPlugin;
+use blend::BlendPlugin;
+use camera::CameraControlsPlugin;
+#[cfg(not(target_arch = "wasm32"))]
+use capture::CapturePlugin;
+use chunked::ChunkedPlugin;
+use cli::Cli;
+use coastline::CoastlinePlugin;
+use collision::CollisionPlugin;
+use compare::ComparisonPlugin;
+use config::{Config, ConfigPlugin, LoadedConfig};
+use contradiction::{Contradicted, ContradictionPlugin};
+use day_night::DayNightPlugin;
+use debug_overlay::DebugOverlayPlugin;
+use decorations::DecorationPlugin;
+use diagnostics::{SOLVER_TIME, SolverDiagnosticsPlugin};
+use display::{DisplaySettings, DisplaySettingsPlugin};
+use entropy::CandidateQueue;
+use events::{CellCollapsed, Contradiction, DomainReduced, SolverEventsPlugin};
+#[cfg(not(target_arch = "wasm32"))]
+use export::ExportPlugin;
+use feedback::FeedbackPlugin;
+use floors::FloorsPlugin;
+use gamepad::GamepadPlugin;
+use generation::{GenerationState, GenerationStatePlugin};
+#[cfg(feature = "gpu")]
+use gpu::GpuPropagationPlugin;
+use heightmap::WeightBias;
+use history::{GridState, HistoryPlugin};
+use inspector::{Elimination, InspectorPlugin, Provenance};
+use keybindings::{Action, Keybindings, KeybindingsPlugin};
+use lock::{LockToolPlugin, Locks};
+use manual::ManualCollapsePlugin;
+use map_import::MapImportPlugin;
+#[cfg(not(target_arch = "wasm32"))]
+use mesh_export::MeshExportPlugin;
+use minimap::MinimapPlugin;
+use nav::NavPlugin;
+use path::PathToolPlugin;
+use perf_overlay::PerfOverlayPlugin;
+#[cfg(feature = "physics")]
+use physics::PhysicsPlugin;
+use player::PlayerPlugin;
+use presets::PresetMenuPlugin;
+use render::TileRenderPlugin;
+#[cfg(not(target_arch = "wasm32"))]
+use replay::ReplayPlugin;
+#[cfg(not(target_arch = "wasm32"))]
+use report::ReportPlugin;
+use reset::ResetToolPlugin;
+use rules::Rules;
+use ruleset_menu::RulesetMenuPlugin;
+#[cfg(not(target_arch = "wasm32"))]
+use saves::SavesPlugin;
+#[cfg(not(target_arch = "wasm32"))]
+use schematic::SchematicPlugin;
+#[cfg(not(target_arch = "wasm32"))]
+use screenshot::ScreenshotPlugin;
+use seed_field::SeedFieldPlugin;
+use spawn_points::SpawnPointPlugin;
+use speed::{GenerationSpeed, SpeedControlPlugin};
+use stats::StatsPlugin;
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use stepping::{StepBudget, SteppingPlugin};
+use strategy::{Backtrack, Heuristic, Propagation, Strategy, StrategyPlugin};
+use swatches::SwatchPlugin;
+use texture_map::TextureMapPlugin;
+use theme::ThemePlugin;
+use tile_palette::TilePalettePlugin;
+use timeline::{Timeline, TimelinePlugin};
+use tooltip::TooltipPlugin;
+use touch::TouchInputPlugin;
+use variation::VariationPlugin;
+use view3d::View3dPlugin;
+use water::WaterPlugin;
+use wavefront::WavefrontPlugin;
+use weights::WeightSlidersPlugin;
+
+use bevy::diagnostic::Diagnostics;
+use bevy::platform::time::Instant;
+use bevy::prelude::*;
+use rand::prelude::*;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+/// Grid width in cells.
+pub const GRID_W: usize = 32;
+/// Grid height in cells.
+pub const GRID_H: usize = 32;
+/// Side of a cell in world units.
+pub const TILE_SIZE: f32 = 20.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TileType {
+    Sand,
+    Water,
+    Grass,
+    Wall,
+    Floor,
+    Door,
+    Corridor,
+    StairsUp,
+    StairsDown,
+}
+
+impl TileType {
+    /// Terrain first, then the dungeon tiles. A ruleset only uses the tiles it
+    /// gives neighbors for; see [`Rules::tiles`].
+    pub const ALL: [TileType; 9] = [
+        TileType::Sand,
+        TileType::Water,
+        TileType::Grass,
+        TileType::Wall,
+        TileType::Floor,
+        TileType::Door,
+        TileType::Corridor,
+        TileType::StairsUp,
+        TileType::StairsDown,
+    ];
+
+    /// Position in [`TileType::ALL`].
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    /// Whether characters can walk on the tile, for engines importing maps.
+    pub fn walkable(self) -> bool {
+        self.move_cost().is_some()
+    }
+
+    /// Cost of walking onto the tile relative to grass, or `None` where
+    /// characters can't walk. Never below 1.
+    pub fn move_cost(self) -> Option<f32> {
+        match self {
+            TileType::Grass
+            | TileType::Floor
+            | TileType::Corridor
+            | TileType::StairsUp
+            | TileType::StairsDown => Some(1.0),
+            TileType::Door => Some(1.2),
+            TileType::Sand => Some(1.5),
+            TileType::Water | TileType::Wall => None,
+        }
+    }
+
+    /// Free-form labels for engines importing maps.
+    pub fn tags(self) -> &'static [&'static str] {
+        match self {
+            TileType::Sand => &["land", "coast"],
+            TileType::Water => &["liquid"],
+            TileType::Grass => &["land", "vegetation"],
+            TileType::Wall => &["dungeon", "solid"],
+            TileType::Floor => &["dungeon", "room"],
+            TileType::Door => &["dungeon", "room", "corridor"],
+            TileType::Corridor => &["dungeon", "corridor"],
+            TileType::StairsUp | TileType::StairsDown => &["dungeon", "room", "stairs"],
+        }
+    }
+}
+
+/// Tiles as a bitmask over [`TileType::ALL`], for what propagation removes
+/// from a domain without allocating a list for it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct TileSet(u32);
+
+impl TileSet {
+    pub fn contains(self, tile: TileType) -> bool {
+        self.0 & 1 << tile.index() != 0
+    }
+
+    pub fn insert(&mut self, tile: TileType) {
+        self.0 |= 1 << tile.index();
+    }
+
+    pub fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// The tiles in the order of [`TileType::ALL`].
+    pub fn iter(self) -> impl Iterator<Item = TileType> {
+        TileType::ALL
+            .into_iter()
+            .filter(move |&tile| self.contains(tile))
+    }
+}
+
+impl FromIterator<TileType> for TileSet {
+    fn from_iter<I: IntoIterator<Item = TileType>>(tiles: I) -> Self {
+        let mut set = TileSet::default();
+        for tile in tiles {
+            set.insert(tile);
+        }
+        set
+    }
+}
+
+/// Extra surface drawn over a collapsed tile's sprite, set per tile by the
+/// ruleset.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TileMaterial {
+    Flat,
+    Water,
+}
+
+/// Seed for everything derived deterministically from a map. Changing it
+/// regenerates the grid.
+#[derive(Resource)]
+pub struct Seed(pub u64);
+
+/// Random source of the solver, reseeded from [`Seed`] for every map so the
+/// same seed reproduces the same map. ChaCha8 is specified exactly, unlike
+/// `StdRng`, whose algorithm may change between rand releases, so a seed
+/// gives the same map on every platform and build.
+#[derive(Resource)]
+struct SolverRng(ChaCha8Rng);
+
+/// Sprite of one grid cell. What the cell holds lives in [`WfcGrid`].
+#[derive(Component)]
+struct Tile {
+    x: usize,
+    y: usize,
+}
+
+/// Tiles still possible for a cell; once collapsed, only the chosen one.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cell {
+    possible: Vec<TileType>,
+    collapsed: bool,
+}
+
+impl Cell {
+    pub fn possible(&self) -> &[TileType] {
+        &self.possible
+    }
+
+    pub fn is_collapsed(&self) -> bool {
+        self.collapsed
+    }
+
+    /// Collapses the cell to `tile` in place, keeping the domain's buffer.
+    fn collapse(&mut self, tile: TileType) {
+        self.possible.clear();
+        self.possible.push(tile);
+        self.collapsed = true;
+    }
+
+    /// Makes every tile of the ruleset possible again, keeping the domain's
+    /// buffer.
+    fn reset(&mut self, rules: &Rules) {
+        self.possible.clear();
+        self.possible.extend(rules.tiles().iter());
+    }
+}
+
+/// Every cell of the map, indexed by `y * GRID_W + x`. This is the state the
+/// solver and tools work on; systems that draw it look for
+/// `resource_changed::<WfcGrid>` rather than changed tile entities.
+#[derive(Resource, Clone)]
+pub struct WfcGrid {
+    cells: Vec<Cell>,
+    /// Tile entity of each cell, for the markers attached to it.
+    entities: Vec<Entity>,
+    /// Cells left to observe. The solver keeps it up to date and rebuilds it
+    /// after anything else changes the grid.
+    queue: CandidateQueue,
+    /// Whether every tile left is supported from every side, so arc-consistent
+    /// propagation only has to start from the cell that changed.
+    arc_consistent: bool,
+    scratch: Ac4Scratch,
+}
+
+impl Default for WfcGrid {
+    fn default() -> Self {
+        Self {
+            cells: vec![
+                Cell {
+                    possible: TileType::ALL.to_vec(),
+                    collapsed: false,
+                };
+                GRID_W * GRID_H
+            ],
+            entities: vec![Entity::PLACEHOLDER; GRID_W * GRID_H],
+            queue: CandidateQueue::default(),
+            arc_consistent: false,
+            scratch: Ac4Scratch::default(),
+        }
+    }
+}
+
+impl WfcGrid {
+    pub fn cell(&self, x: usize, y: usize) -> &Cell {
+        &self.cells[y * GRID_W + x]
+    }
+
+    fn cell_mut(&mut self, x: usize, y: usize) -> &mut Cell {
+        &mut self.cells[y * GRID_W + x]
+    }
+
+    fn entity(&self, x: usize, y: usize) -> Entity {
+        self.entities[y * GRID_W + x]
+    }
+
+    /// Every cell with its coordinates, row by row from the bottom.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, usize, &Cell)> {
+        self.cells
+            .iter()
+            .enumerate()
+            .map(|(i, cell)| (i % GRID_W, i / GRID_W, cell))
+    }
+
+    /// Drops what the solver tracks alongside the domains, after something
+    /// other than the solver changed them.
+    fn invalidate(&mut self) {
+        self.queue.invalidate();
+        self.arc_consistent = false;
+    }
+
+    /// Collapsed tile of every cell, indexed by `y * GRID_W + x`.
+    pub fn collapsed_grid(&self) -> Vec<Option<TileType>> {
+        self.cells
+            .iter()
+            .map(|cell| cell.collapsed.then(|| cell.possible[0]))
+            .collect()
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+/// Ruleset, seed and strategy of the generator [`WfcPlugin`] adds, on the
+/// fixed [`GRID_W`] by [`GRID_H`] grid. Everything else starts out as the
+/// binary's defaults.
+#[derive(Default)]
+pub struct WfcConfig {
+    /// Ruleset file in the format of `assets/tilesets`, or the built-in
+    /// rules if `None`.
+    pub tileset: Option<PathBuf>,
+    /// Seed of the first map. Random if `None`.
+    pub seed: Option<u64>,
+    pub strategy: Strategy,
+}
+
+/// The whole generator: the grid, the solver and every tool, view and menu
+/// on top of it, for adding to an app with `DefaultPlugins`. The binary
+/// builds it from the command line and `wfc.toml`.
+pub struct WfcPlugin {
+    cli: Cli,
+    config: Config,
+    /// Loaded from the settings file when the app starts if `None`.
+    display: Option<DisplaySettings>,
+}
+
+impl WfcPlugin {
+    /// The generator with the given configuration. The saved display
+    /// settings are read and applied to the primary window once the app
+    /// starts, not here.
+    pub fn new(config: WfcConfig) -> Self {
+        // The command line's defaults, from a fixed argument list rather
+        // than the process's.
+        let mut cli = <Cli as clap::Parser>::parse_from(["wfc"]);
+        cli.tileset = config.tileset;
+        cli.seed = config.seed;
+        cli.heuristic = config.strategy.heuristic;
+        cli.propagation = config.strategy.propagation;
+        cli.backtracking = config.strategy.backtracking;
+        Self {
+            cli,
+            config: Config::default(),
+            display: None,
+        }
+    }
+
+    /// The generator the binary runs, with every command line option and
+    /// the loaded config file.
+    pub fn from_cli(cli: Cli, config: Config, display: DisplaySettings) -> Self {
+        Self {
+            cli,
+            config,
+            display: Some(display),
+        }
+    }
+}
+
+impl Plugin for WfcPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            AnimationPlugin,
+            AutotilePlugin,
+            CameraControlsPlugin,
+            CoastlinePlugin,
+            ContradictionPlugin,
+            DayNightPlugin,
+            DebugOverlayPlugin,
+            MinimapPlugin,
+            #[cfg(not(target_arch = "wasm32"))]
+            ScreenshotPlugin,
+            SwatchPlugin,
+            ThemePlugin,
+            VariationPlugin,
+            View3dPlugin,
+            WaterPlugin,
+            WavefrontPlugin,
+        ))
+        .add_plugins((
+            BlendPlugin,
+            ChunkedPlugin,
+            FeedbackPlugin,
+            HistoryPlugin,
+            KeybindingsPlugin,
+            ManualCollapsePlugin,
+            ResetToolPlugin,
+            RulesetMenuPlugin,
+            SeedFieldPlugin,
+            SpeedControlPlugin,
+            StatsPlugin,
+            SteppingPlugin,
+            TilePalettePlugin,
+            TileRenderPlugin,
+            TooltipPlugin,
+        ))
+        .add_plugins((
+            BenchPlugin,
+            ComparisonPlugin,
+            DisplaySettingsPlugin,
+            #[cfg(not(target_arch = "wasm32"))]
+            ExportPlugin,
+            GamepadPlugin,
+            InspectorPlugin,
+            LockToolPlugin,
+            PresetMenuPlugin,
+            #[cfg(not(target_arch = "wasm32"))]
+            ReplayPlugin,
+            #[cfg(not(target_arch = "wasm32"))]
+            SavesPlugin,
+            StrategyPlugin,
+            TimelinePlugin,
+            TouchInputPlugin,
+            WeightSlidersPlugin,
+        ))
+        .add_plugins((
+            BackgroundPlugin,
+            #[cfg(not(target_arch = "wasm32"))]
+            CapturePlugin,
+            ConfigPlugin,
+            GenerationStatePlugin,
+            #[cfg(feature = "gpu")]
+            GpuPropagationPlugin,
+            MapImportPlugin,
+            #[cfg(not(target_arch = "wasm32"))]
+            MeshExportPlugin,
+            NavPlugin,
+            #[cfg(feature = "physics")]
+            PhysicsPlugin,
+            #[cfg(not(target_arch = "wasm32"))]
+            ReportPlugin,
+            #[cfg(not(target_arch = "wasm32"))]
+            SchematicPlugin,
+            SolverDiagnosticsPlugin {
+                log: self.cli.log_diagnostics,
+            },
+            PerfOverlayPlugin,
+            SolverEventsPlugin,
+            TextureMapPlugin,
+        ))
+        .add_plugins((
+            CollisionPlugin,
+            DecorationPlugin,
+            FloorsPlugin,
+            PathToolPlugin,
+            PlayerPlugin,
+            SpawnPointPlugin,
+        ))
+        .insert_resource(self.cli.rules())
+        .insert_resource(self.cli.bias())
+        .insert_resource(self.cli.strategy())
+        .insert_resource(LoadedConfig::new(
+            self.cli.config.clone(),
+            self.config.clone(),
+        ))
+        .insert_resource(Seed(self.cli.seed.unwrap_or_else(rand::random)))
+        .insert_resource(self.cli.clone())
+        .init_resource::<WfcGrid>()
+        .add_systems(Startup, setup)
+        .configure_sets(
+            Update,
+            (WfcSet::Prepare, WfcSet::Solve, WfcSet::React).chain(),
+        )
+        // Paused maps still take single steps. Finished ones are left alone
+        // until an edit gives the solver something to observe again; the
+        // state lags a frame behind edits, the grid doesn't.
+        .configure_sets(
+            Update,
+            WfcSet::Solve.run_if(
+                in_state(GenerationState::Generating)
+                    .or(in_state(GenerationState::Paused))
+                    .and(any_observable),
+            ),
+        )
+        .add_systems(Update, collapse_step.in_set(WfcSet::Solve))
+        // Ordered first so its resets are applied before any system queues
+        // commands for tiles of the old grid.
+        .add_systems(
+            Update,
+            (
+                refresh_on_r,
+                regenerate.run_if(resource_changed::<Seed>.and(not(resource_added::<Seed>))),
+            )
+                .chain()
+                .in_set(WfcSet::Prepare),
+        );
+        if let Some(display) = &self.display {
+            app.insert_resource(display.clone());
+        }
+    }
+}
+
+fn setup(mut commands: Commands, seed: Res<Seed>, rules: Res<Rules>, locks: Res<Locks>) {
+    commands.spawn(camera::grid_camera());
+    let mut wfc_grid = start_map(&mut commands, &seed, &rules, &locks);
+    for y in 0..GRID_H {
+        for x in 0..GRID_W {
+            wfc_grid.entities[y * GRID_W + x] = commands.spawn(tile_bundle(x, y)).id();
+        }
+    }
+    // Inserted with the spawns, so no system sees the new grid with the old
+    // entities.
+    commands.insert_resource(wfc_grid);
+}
+
+/// Resets the solver for a new map and returns its grid, without entities.
+fn start_map(commands: &mut Commands, seed: &Seed, rules: &Rules, locks: &Locks) -> WfcGrid {
+    commands.insert_resource(SolverRng(ChaCha8Rng::seed_from_u64(seed.0)));
+    commands.insert_resource(Backtrack::default());
+    let (wfc_grid, provenance) = locked_grid(rules, locks);
+    commands.insert_resource(provenance);
+    wfc_grid
+}
+
+/// Grid of a new map, without entities, and the inspector's log of what its
+/// locked cells eliminate. Locked cells start out collapsed and constrain
+/// their neighbors. Shared by the app and the headless solver.
+pub(crate) fn locked_grid(rules: &Rules, locks: &Locks) -> (WfcGrid, Provenance) {
+    let grid = locks.grid();
+    let mut provenance = Provenance::default();
+    let mut wfc_grid = WfcGrid::default();
+    for y in 0..GRID_H {
+        for x in 0..GRID_W {
+            let (possible, collapsed) = match grid[y * GRID_W + x] {
+                Some(tile) => (vec![tile], true),
+                None => {
+                    provenance.rebuild(rules, &grid, x, y);
+                    (domain_from_neighbors(rules, &grid, x, y), false)
+                }
+            };
+            *wfc_grid.cell_mut(x, y) = Cell {
+                possible,
+                collapsed,
+            };
+        }
+    }
+    (wfc_grid, provenance)
+}
+
+/// Components of a new tile, also written over a reused one to reset it.
+fn tile_bundle(x: usize, y: usize) -> impl Bundle {
+    (
+        Tile { x, y },
+        Sprite {
+            color: Color::WHITE,
+            custom_size: Some(Vec2::splat(TILE_SIZE)),
+            ..default()
+        },
+        Transform::from_translation(cell_center(x, y).extend(layers::TILE)),
+    )
+}
+
+fn cell_center(x: usize, y: usize) -> Vec2 {
+    Vec2::new(
+        x as f32 * TILE_SIZE - GRID_W as f32 * TILE_SIZE / 2.0,
+        y as f32 * TILE_SIZE - GRID_H as f32 * TILE_SIZE / 2.0,
+    )
+}
+
+/// Cell containing a world position, if it lies on the grid.
+fn cell_at(world: Vec2) -> Option<(usize, usize)> {
+    let cell = ((world - cell_center(0, 0)) / TILE_SIZE).round();
+    if cell.x < 0.0 || cell.y < 0.0 {
+        return None;
+    }
+    let (x, y) = (cell.x as usize, cell.y as usize);
+    (x < GRID_W && y < GRID_H).then_some((x, y))
+}
+
+/// World position of the middle of the grid.
+fn grid_center() -> Vec2 {
+    cell_center(0, 0) + Vec2::new(GRID_W as f32 - 1.0, GRID_H as f32 - 1.0) * TILE_SIZE / 2.0
+}
+
+#[allow(clippy::too_many_arguments)]
+fn collapse_step(
+    mut commands: Commands,
+    budget: Res<StepBudget>,
+    mut speed: ResMut<GenerationSpeed>,
+    mut diagnostics: Diagnostics,
+    rules: Res<Rules>,
+    bias: Res<WeightBias>,
+    strategy: Res<Strategy>,
+    mut rng: ResMut<SolverRng>,
+    mut backtrack: ResMut<Backtrack>,
+    mut provenance: ResMut<Provenance>,
+    mut timeline: ResMut<Timeline>,
+    mut grid: ResMut<WfcGrid>,
+    mut collapsed: MessageWriter<CellCollapsed>,
+) {
+    // Edits by tools and loads since the last run bypass the queue.
+    if grid.is_changed() {
+        grid.bypass_change_detection().invalidate();
+    }
+    // Only flagged as changed when a step changes something, so a finished
+    // map isn't redrawn every frame.
+    let mut changed = false;
+    let started = Instant::now();
+    let deadline = speed.frame_budget.map(|budget| started + budget);
+    let mut steps = 0;
+    for _ in 0..budget.0 {
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            break;
+        }
+        match collapse_once(
+            &mut commands,
+            &mut rng.0,
+            &rules,
+            &bias,
+            &strategy,
+            &mut backtrack,
+            &mut provenance,
+            grid.bypass_change_detection(),
+        ) {
+            Step::Collapsed(x, y, tile) => {
+                timeline.record(x, y, tile);
+                collapsed.write(CellCollapsed { x, y, tile });
+            }
+            // The recorded collapses no longer lead to the rewound grid.
+            Step::Backtracked => timeline.rebase(GridState::capture(&grid)),
+            Step::Done => break,
+        }
+        steps += 1;
+        changed = true;
+    }
+    // Timing isn't a setting change, so don't wake the slider.
+    let elapsed = started.elapsed();
+    speed.bypass_change_detection().measure(steps, elapsed);
+    diagnostics.add_measurement(&SOLVER_TIME, || elapsed.as_secs_f64() * 1000.0);
+    if changed {
+        grid.set_changed();
+    }
+}
+
+/// Stages of the generator's `Update` systems, run in this order. Systems of
+/// an app embedding [`WfcPlugin`] can go in them, or before or after them, to
+/// see the grid before or after this frame's solver steps.
+#[derive(SystemSet, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum WfcSet {
+    /// Input, tools, loads and regeneration, and planning this frame's steps.
+    Prepare,
+    /// Observation and propagation. Skipped once the map is finished, until
+    /// an edit gives the solver something to observe again.
+    Solve,
+    /// Keeping track of where generation is, once the solver is done for the
+    /// frame.
+    React,
+}
+
+/// Whether the solver has a cell left to observe.
+pub fn any_observable(grid: Res<WfcGrid>) -> bool {
+    grid.cells
+        .iter()
+        .any(|cell| !cell.collapsed && !cell.possible.is_empty())
+}
+
+/// Outcome of one observation.
+pub enum Step {
+    /// The cell at `(x, y)` collapsed to the tile.
+    Collapsed(usize, usize, TileType),
+    /// A contradiction rewound the grid to an earlier observation.
+    Backtracked,
+    /// Every cell is collapsed.
+    Done,
+}
+
+/// Observes the cell picked by the strategy's heuristic and propagates from
+/// it. With backtracking on, a contradiction rewinds to the state before the
+/// latest observation instead and rules out the tile chosen there.
+#[allow(clippy::too_many_arguments)]
+fn collapse_once(
+    commands: &mut Commands,
+    rng: &mut ChaCha8Rng,
+    rules: &Rules,
+    bias: &WeightBias,
+    strategy: &Strategy,
+    backtrack: &mut Backtrack,
+    provenance: &mut Provenance,
+    grid: &mut WfcGrid,
+) -> Step {
+    let Some((x, y)) = observe(strategy.heuristic, rules, rng, grid) else {
+        return Step::Done;
+    };
+    let before = strategy.backtracking.then(|| GridState::capture(grid));
+
+    let collapsed_choice = {
+        let mut choices = [TileType::Sand; TileType::ALL.len()];
+        let mut len = 0;
+        for &choice in &grid.cell(x, y).possible {
+            let supported = neighbor_coords(x, y).all(|(nx, ny)| {
+                grid.cell(nx, ny).possible.iter().any(|&n| {
+                    allowed_neighbor(rules, choice, n, neighbor_direction(x, y, nx, ny).unwrap())
+                })
+            });
+            if supported {
+                choices[len] = choice;
+                len += 1;
+            }
+        }
+        let valid_choices = &choices[..len];
+
+        let choice = if valid_choices.is_empty() {
+            if strategy.backtracking && backtrack.rewind(commands, rules, provenance, grid) {
+                return Step::Backtracked;
+            }
+            warn!("contradiction: no consistent tile for ({x}, {y})");
+            commands
+                .entity(grid.entity(x, y))
+                .try_insert(Contradicted::default());
+            commands.write_message(Contradiction { x, y });
+            *grid.cell(x, y).possible.choose(rng).unwrap()
+        } else {
+            commands
+                .entity(grid.entity(x, y))
+                .try_remove::<Contradicted>();
+            // Weighted by the rules and any heightmap, falling back to a
+            // uniform pick when every remaining tile has weight zero.
+            valid_choices
+                .choose_weighted(rng, |&choice| bias.weight(rules, choice, x, y))
+                .copied()
+                .unwrap_or_else(|_| *valid_choices.choose(rng).unwrap())
+        };
+
+        grid.cell_mut(x, y).collapse(choice);
+        choice
+    };
+
+    let contradiction = match strategy.propagation {
+        Propagation::Naive => {
+            propagate(commands, rules, provenance, grid, (x, y), collapsed_choice)
+        }
+        Propagation::Ac4 => propagate_ac4(commands, rules, provenance, grid, (x, y)),
+    };
+    if let Some(before) = before {
+        backtrack.push(before, (x, y), collapsed_choice);
+        if contradiction && backtrack.rewind(commands, rules, provenance, grid) {
+            return Step::Backtracked;
+        }
+    }
+    Step::Collapsed(x, y, collapsed_choice)
+}
+
+/// Picks the next uncollapsed cell to observe, from the grid's candidate
+/// queue. Ties go to the first cell in row order, except for
+/// [`Heuristic::Random`], which picks uniformly among all of them.
+fn observe(
+    heuristic: Heuristic,
+    rules: &Rules,
+    rng: &mut ChaCha8Rng,
+    grid: &mut WfcGrid,
+) -> Option<(usize, usize)> {
+    let _span = info_span!("observe", ?heuristic).entered();
+    if heuristic == Heuristic::Random {
+        let candidates: Vec<_> = grid
+            .iter()
+            .filter(|(_, _, cell)| !cell.collapsed && !cell.possible.is_empty())
+            .map(|(x, y, _)| (x, y))
+            .collect();
+        return candidates.choose(rng).copied();
+    }
+    grid.queue.pop(&grid.cells, rules, heuristic)
+}
+
+/// Collapses a cell to a tile picked by the user, propagating from it the
+/// same way as from an observed cell.
+fn force_collapse(
+    commands: &mut Commands,
+    rules: &Rules,
+    propagation: Propagation,
+    provenance: &mut Provenance,
+    grid: &mut WfcGrid,
+    (x, y): (usize, usize),
+    choice: TileType,
+) {
+    if grid.cell(x, y).collapsed {
+        return;
+    }
+    grid.cell_mut(x, y).collapse(choice);
+    commands
+        .entity(grid.entity(x, y))
+        .try_remove::<Contradicted>();
+
+    match propagation {
+        Propagation::Naive => {
+            propagate(commands, rules, provenance, grid, (x, y), choice);
+        }
+        Propagation::Ac4 => {
+            propagate_ac4(commands, rules, provenance, grid, (x, y));
+        }
+    }
+}
+
+/// Narrows the domains of the uncollapsed neighbors of a freshly collapsed
+/// cell, logging what each lost to `provenance`. Returns whether a domain
+/// emptied.
+fn propagate(
+    commands: &mut Commands,
+    rules: &Rules,
+    provenance: &mut Provenance,
+    grid: &mut WfcGrid,
+    (collapsed_x, collapsed_y): (usize, usize),
+    collapsed_choice: TileType,
+) -> bool {
+    let _span = info_span!("propagate", propagation = "naive").entered();
+    let step = provenance.begin_step();
+    let mut contradiction = false;
+    // Only the neighbors are narrowed, so cells further out may be left
+    // unsupported.
+    grid.arc_consistent = false;
+
+    for i in neighbor_indices(collapsed_y * GRID_W + collapsed_x) {
+        let (x, y) = (i % GRID_W, i / GRID_W);
+        if grid.cell(x, y).collapsed {
+            continue;
+        }
+
+        if let Some(dir) = neighbor_direction(collapsed_x, collapsed_y, x, y) {
+            let entity = grid.entity(x, y);
+            let other_tile = grid.cell_mut(x, y);
+            let mut removed = TileSet::default();
+            other_tile.possible.retain(|&n| {
+                let allowed = allowed_neighbor(rules, collapsed_choice, n, dir);
+                if !allowed {
+                    removed.insert(n);
+                }
+                allowed
+            });
+
+            if other_tile.possible.is_empty() {
+                warn!("contradiction: domain of ({x}, {y}) emptied, resetting it");
+                commands.entity(entity).try_insert(Contradicted::default());
+                commands.write_message(Contradiction { x, y });
+                other_tile.reset(rules);
+                provenance.clear(x, y);
+                contradiction = true;
+                grid.queue
+                    .update(y * GRID_W + x, &grid.cells[y * GRID_W + x]);
+            } else if !removed.is_empty() {
+                commands.write_message(DomainReduced { x, y, removed });
+                provenance.record(
+                    x,
+                    y,
+                    Elimination {
+                        step: Some(step),
+                        source: (collapsed_x, collapsed_y),
+                        source_tile: Some(collapsed_choice),
+                        removed,
+                    },
+                );
+                grid.queue
+                    .update(y * GRID_W + x, &grid.cells[y * GRID_W + x]);
+            }
+        }
+    }
+    contradiction
+}
+
+/// Arc-consistent propagation. Revises cells from a worklist, starting with
+/// the neighbors of the collapsed cell: each drops the tiles that some
+/// neighbor no longer allows, and a cell that drops any puts its own
+/// neighbors on the list, so only cells next to an actual change are looked
+/// at. Until a pass ends without a contradiction the grid isn't known to be
+/// consistent, and every cell starts on the list. Unlike [`propagate`],
+/// narrowing spreads past the neighbors of the collapsed cell. Returns
+/// whether a domain emptied.
+fn propagate_ac4(
+    commands: &mut Commands,
+    rules: &Rules,
+    provenance: &mut Provenance,
+    grid: &mut WfcGrid,
+    (x, y): (usize, usize),
+) -> bool {
+    let _span = info_span!("propagate", propagation = "ac4").entered();
+    let step = provenance.begin_step();
+    let cells = &mut grid.cells;
+    let scratch = &mut grid.scratch;
+    if grid.arc_consistent {
+        scratch.worklist.extend(neighbor_indices(y * GRID_W + x));
+    } else {
+        scratch.worklist.extend(0..cells.len());
+    }
+    for &i in &scratch.worklist {
+        scratch.queued[i] = true;
+    }
+
+    while let Some(i) = scratch.worklist.pop_front() {
+        scratch.queued[i] = false;
+        if cells[i].collapsed || scratch.is_emptied[i] {
+            continue;
+        }
+        let mut possible = std::mem::take(&mut cells[i].possible);
+        let before = possible.len();
+        possible.retain(|&tile| {
+            let unsupported = neighbor_indices(i).enumerate().find(|&(_, j)| {
+                !cells[j]
+                    .possible
+                    .iter()
+                    .any(|&other| rules.allows(other, tile))
+            });
+            let Some((slot, source)) = unsupported else {
+                return true;
+            };
+            match scratch.logged[i][slot] {
+                Some(entry) => scratch.log[entry].2.insert(tile),
+                None => {
+                    scratch.logged[i][slot] = Some(scratch.log.len());
+                    scratch.log.push((i, source, TileSet::from_iter([tile])));
+                }
+            }
+            false
+        });
+        let narrowed = possible.len() != before;
+        if possible.is_empty() {
+            scratch.is_emptied[i] = true;
+            scratch.emptied.push(i);
+        }
+        cells[i].possible = possible;
+        if narrowed {
+            for j in neighbor_indices(i) {
+                if !cells[j].collapsed && !scratch.queued[j] {
+                    scratch.queued[j] = true;
+                    scratch.worklist.push_back(j);
+                }
+            }
+        }
+    }
+
+    for &(i, source, removed) in &scratch.log {
+        scratch.logged[i] = [None; 4];
+        if scratch.is_emptied[i] {
+            continue;
+        }
+        commands.write_message(DomainReduced {
+            x: i % GRID_W,
+            y: i / GRID_W,
+            removed,
+        });
+        let source_cell = &cells[source];
+        provenance.record(
+            i % GRID_W,
+            i / GRID_W,
+            Elimination {
+                step: Some(step),
+                source: (source % GRID_W, source / GRID_W),
+                source_tile: source_cell
+                    .collapsed
+                    .then(|| source_cell.possible.first().copied())
+                    .flatten(),
+                removed,
+            },
+        );
+    }
+
+    scratch.emptied.sort_unstable();
+    for &i in &scratch.emptied {
+        let (x, y) = (i % GRID_W, i / GRID_W);
+        warn!("contradiction: domain of ({x}, {y}) emptied, resetting it");
+        commands
+            .entity(grid.entities[i])
+            .try_insert(Contradicted::default());
+        commands.write_message(Contradiction { x, y });
+        cells[i].reset(rules);
+        scratch.is_emptied[i] = false;
+        provenance.clear(x, y);
+    }
+    for &(i, _, _) in &scratch.log {
+        grid.queue.update(i, &cells[i]);
+    }
+    let contradiction = !scratch.emptied.is_empty();
+    scratch.log.clear();
+    scratch.emptied.clear();
+    // Reset cells may not be supported by their neighbors.
+    grid.arc_consistent = !contradiction;
+    contradiction
+}
+
+/// Buffers [`propagate_ac4`] works in, kept with the grid and left empty
+/// between calls so propagating doesn't allocate. Sized for the worst case:
+/// every cell queued, and every cell losing every tile to a different
+/// neighbor.
+#[derive(Clone)]
+struct Ac4Scratch {
+    worklist: VecDeque<usize>,
+    /// Whether each cell is on the worklist.
+    queued: Vec<bool>,
+    /// What each cell lost to which neighbor, in order of removal.
+    log: Vec<(usize, usize, TileSet)>,
+    /// Entry in `log` of each cell's losses to each neighbor, in the order of
+    /// [`neighbor_indices`].
+    logged: Vec<[Option<usize>; 4]>,
+    /// Cells whose domain emptied, and the same as a flag per cell.
+    emptied: Vec<usize>,
+    is_emptied: Vec<bool>,
+}
+
+impl Default for Ac4Scratch {
+    fn default() -> Self {
+        let cells = GRID_W * GRID_H;
+        Self {
+            worklist: VecDeque::with_capacity(cells),
+            queued: vec![false; cells],
+            log: Vec::with_capacity(cells * TileType::ALL.len()),
+            logged: vec![[None; 4]; cells],
+            emptied: Vec::with_capacity(cells),
+            is_emptied: vec![false; cells],
+        }
+    }
+}
+
+/// Tiles allowed next to every collapsed neighbor in `grid`. Naive
+/// propagation only narrows domains from collapsed cells, so this is exactly
+/// what it leaves for an uncollapsed cell; AC-4 narrows further on its next
+/// pass.
+fn domain_from_neighbors(
+    rules: &Rules,
+    grid: &[Option<TileType>],
+    x: usize,
+    y: usize,
+) -> Vec<TileType> {
+    let domain: Vec<TileType> = rules
+        .tiles()
+        .iter()
+        .filter(|&candidate| {
+            neighbor_coords(x, y).all(|(nx, ny)| {
+                grid[ny * GRID_W + nx].is_none_or(|neighbor| rules.allows(neighbor, candidate))
+            })
+        })
+        .collect();
+    // Same fallback as propagation when a domain empties.
+    if domain.is_empty() {
+        rules.tiles().iter().collect()
+    } else {
+        domain
+    }
+}
+
+/// Cells next to `(x, y)`: up, down, right, left, leaving out the ones past
+/// the edges. Built on the stack, since the solver asks for them in its inner
+/// loops.
+fn neighbor_coords(x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> {
+    [
+        (y + 1 < GRID_H).then(|| (x, y + 1)),
+        (y > 0).then(|| (x, y - 1)),
+        (x + 1 < GRID_W).then(|| (x + 1, y)),
+        (x > 0).then(|| (x - 1, y)),
+    ]
+    .into_iter()
+    .flatten()
+}
+
+/// Indices of the cells next to cell `i`, in the order of [`neighbor_coords`].
+fn neighbor_indices(i: usize) -> impl Iterator<Item = usize> {
+    neighbor_coords(i % GRID_W, i / GRID_W).map(|(x, y)| y * GRID_W + x)
+}
+
+fn neighbor_direction(x1: usize, y1: usize, x2: usize, y2: usize) -> Option<Direction> {
+    if x1 == x2 && y1 + 1 == y2 {
+        Some(Direction::Up)
+    } else if x1 == x2 && y1 == y2 + 1 {
+        Some(Direction::Down)
+    } else if x1 + 1 == x2 && y1 == y2 {
+        Some(Direction::Right)
+    } else if x1 == x2 + 1 && y1 == y2 {
+        Some(Direction::Left)
+    } else {
+        None
+    }
+}
+
+fn allowed_neighbor(rules: &Rules, tile: TileType, neighbor: TileType, _dir: Direction) -> bool {
+    rules.allows(tile, neighbor)
+}
+
+fn refresh_on_r(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keybindings: Res<Keybindings>,
+    mut seed: ResMut<Seed>,
+) {
+    if keybindings.just_pressed(Action::Restart, &keyboard_input) {
+        seed.0 = rand::random();
+    }
+}
+
+/// Replaces the grid with a fresh one for the current seed. The tile
+/// entities and the camera are kept, with the tiles' components reset, so a
+/// new map doesn't move thousands of entities between archetypes.
+fn regenerate(
+    mut commands: Commands,
+    seed: Res<Seed>,
+    rules: Res<Rules>,
+    locks: Res<Locks>,
+    old: Res<WfcGrid>,
+) {
+    let mut wfc_grid = start_map(&mut commands, &seed, &rules, &locks);
+    wfc_grid.entities.clone_from(&old.entities);
+    for (i, &entity) in wfc_grid.entities.iter().enumerate() {
+        commands
+            .entity(entity)
+            .try_remove::<Contradicted>()
+            .try_insert(tile_bundle(i % GRID_W, i / GRID_W));
+    }
+    commands.insert_resource(wfc_grid);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell as Counter;
+
+    use super::*;
+    use crate::strategy::Heuristic;
+
+    /// The system allocator, counting allocations per thread so tests running
+    /// in parallel don't count each other's.
+    struct CountingAllocator;
+
+    thread_local! {
+        static ALLOCATIONS: Counter<usize> = const { Counter::new(0) };
+    }
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+            unsafe { System.alloc(layout) }
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            unsafe { System.dealloc(ptr, layout) }
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    fn allocations() -> usize {
+        ALLOCATIONS.with(Counter::get)
+    }
+
+    /// Solves a fresh map in place, collapsing each observed cell to a random
+    /// tile, and returns the allocations made while propagating from them.
+    fn solve(
+        world: &mut World,
+        grid: &mut WfcGrid,
+        provenance: &mut Provenance,
+        propagation: Propagation,
+    ) -> usize {
+        let rules = Rules::default();
+        let mut rng = ChaCha8Rng::seed_from_u64(42);
+        for (i, cell) in grid.cells.iter_mut().enumerate() {
+            cell.reset(&rules);
+            cell.collapsed = false;
+            provenance.clear(i % GRID_W, i / GRID_W);
+        }
+        grid.invalidate();
+        let mut counted = 0;
+        while let Some((x, y)) = observe(Heuristic::MinRemaining, &rules, &mut rng, grid) {
+            let choice = *grid.cell(x, y).possible.choose(&mut rng).unwrap();
+            let mut commands = world.commands();
+            let before = allocations();
+            force_collapse(
+                &mut commands,
+                &rules,
+                propagation,
+                provenance,
+                grid,
+                (x, y),
+                choice,
+            );
+            counted += allocations() - before;
+            world.flush();
+        }
+        counted
+    }
+
+    /// Propagation runs for every collapse, so once the buffers it reuses
+    /// have grown on one map it shouldn't allocate at all.
+    #[test]
+    fn propagation_does_not_allocate() {
+        for propagation in [Propagation::Naive, Propagation::Ac4] {
+            let mut world = World::new();
+            world.init_resource::<Messages<DomainReduced>>();
+            world.init_resource::<Messages<Contradiction>>();
+            let mut grid = WfcGrid::default();
+            for entity in &mut grid.entities {
+                *entity = world.spawn_empty().id();
+            }
+            let mut provenance = Provenance::default();
+            solve(&mut world, &mut grid, &mut provenance, propagation);
+            let allocated = solve(&mut world, &mut grid, &mut provenance, propagation);
+            assert_eq!(allocated, 0, "{propagation:?} propagation allocated");
+        }
+    }
+
+    /// A world the propagators can write their messages and markers to, and a
+    /// fresh grid with entities in it, every cell holding every tile of
+    /// `rules`.
+    fn fresh_grid(rules: &Rules) -> (World, WfcGrid) {
+        let mut world = World::new();
+        world.init_resource::<Messages<DomainReduced>>();
+        world.init_resource::<Messages<Contradiction>>();
+        let mut grid = WfcGrid::default();
+        for (cell, entity) in grid.cells.iter_mut().zip(&mut grid.entities) {
+            cell.reset(rules);
+            *entity = world.spawn_empty().id();
+        }
+        (world, grid)
+    }
+
+    /// Collapses `cell` to `tile` and propagates from it.
+    fn collapse_and_propagate(
+        rules: &Rules,
+        propagation: Propagation,
+        cell: (usize, usize),
+        tile: TileType,
+    ) -> WfcGrid {
+        let (mut world, mut grid) = fresh_grid(rules);
+        let mut commands = world.commands();
+        force_collapse(
+            &mut commands,
+            rules,
+            propagation,
+            &mut Provenance::default(),
+            &mut grid,
+            cell,
+            tile,
+        );
+        world.flush();
+        grid
+    }
+
+    /// Revises every cell against its neighbors until none changes: the
+    /// arc-consistent domains AC-4 should reach, found the slow way.
+    fn revise_to_fixpoint(rules: &Rules, grid: &mut WfcGrid) {
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for i in 0..grid.cells.len() {
+                if grid.cells[i].collapsed {
+                    continue;
+                }
+                let mut possible = grid.cells[i].possible.clone();
+                possible.retain(|&tile| {
+                    neighbor_indices(i).all(|j| {
+                        grid.cells[j]
+                            .possible
+                            .iter()
+                            .any(|&other| rules.allows(other, tile))
+                    })
+                });
+                if possible != grid.cells[i].possible {
+                    grid.cells[i].possible = possible;
+                    changed = true;
+                }
+            }
+        }
+    }
+
+    /// With the built-in rules, a collapse only ever narrows its neighbors,
+    /// so both propagators leave the same domains.
+    #[test]
+    fn ac4_matches_naive_propagation_next_to_a_collapse() {
+        let rules = Rules::default();
+        for tile in rules.tiles().iter() {
+            let naive = collapse_and_propagate(&rules, Propagation::Naive, (5, 5), tile);
+            let ac4 = collapse_and_propagate(&rules, Propagation::Ac4, (5, 5), tile);
+            assert_eq!(naive.cells, ac4.cells, "after collapsing to {tile:?}");
+        }
+    }
+
+    /// Where narrowing spreads further, AC-4 goes on past the neighbors to
+    /// the arc-consistent fixpoint, and keeps at most what naive keeps.
+    #[test]
+    fn ac4_reaches_the_arc_consistent_fixpoint() {
+        // A chain of tiles that never touch their own kind: next to water
+        // can only be sand, and next to that only water or grass.
+        let chain = [
+            TileType::Water,
+            TileType::Sand,
+            TileType::Grass,
+            TileType::Wall,
+        ];
+        let rules = Rules::from_sample("Chain".to_string(), &chain, chain.len());
+        for tile in chain {
+            let naive = collapse_and_propagate(&rules, Propagation::Naive, (5, 5), tile);
+            let ac4 = collapse_and_propagate(&rules, Propagation::Ac4, (5, 5), tile);
+            let mut expected = naive.clone();
+            revise_to_fixpoint(&rules, &mut expected);
+            assert_eq!(ac4.cells, expected.cells, "after collapsing to {tile:?}");
+            assert_ne!(ac4.cells, naive.cells, "after collapsing to {tile:?}");
+            for (narrowed, naive) in ac4.cells.iter().zip(&naive.cells) {
+                assert!(
+                    narrowed
+                        .possible
+                        .iter()
+                        .all(|tile| naive.possible.contains(tile))
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use serde::{Deserialize, Serialize};
+
+use crate::camera::cursor_world_position;
+use crate::inspector::Inspector;
+use crate::keybindings::{Action, Keybindings};
+use crate::{GRID_H, GRID_W, TILE_SIZE, TileType, WfcGrid, cell_at, cell_center, collapse_step};
+
+const LOCK_COLOR: Color = Color::srgb(1.0, 0.8, 0.2);
+const LOCK_ICON_SIZE: f32 = TILE_SIZE * 0.3;
+
+/// Cells protected from regeneration and region resets, with the tile they
+/// were locked to. Kept across maps and used as fixed constraints for them.
+#[derive(Resource, Clone, Default, Serialize, Deserialize)]
+pub struct Locks(HashMap<(usize, usize), TileType>);
+
+impl Locks {
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        self.0.contains_key(&(x, y))
+    }
+
+    /// Locks a cell to `tile`, or unlocks it if it is locked already.
+    pub fn toggle(&mut self, x: usize, y: usize, tile: TileType) {
+        if self.0.remove(&(x, y)).is_none() {
+            self.0.insert((x, y), tile);
+        }
+    }
+
+    /// Replaces every lock, e.g. with a preset's fixed cells.
+    pub fn replace(&mut self, locks: HashMap<(usize, usize), TileType>) {
+        self.0 = locks;
+    }
+
+    /// Locked tile of every cell, indexed by `y * GRID_W + x`.
+    pub fn grid(&self) -> Vec<Option<TileType>> {
+        let mut grid = vec![None; GRID_W * GRID_H];
+        for (&(x, y), &tile) in &self.0 {
+            grid[y * GRID_W + x] = Some(tile);
+        }
+        grid
+    }
+}
+
+/// While active, left-clicking a collapsed cell locks or unlocks it instead
+/// of collapsing cells by hand. Toggled with `O`.
+#[derive(Resource, Default)]
+pub struct LockTool {
+    pub active: bool,
+}
+
+pub struct LockToolPlugin;
+
+impl Plugin for LockToolPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Locks>()
+            .init_resource::<LockTool>()
+            .add_systems(
+                Update,
+                (toggle_tool, toggle_lock).chain().before(collapse_step),
+            )
+            .add_systems(
+                Update,
+                (drop_stale_locks, draw_locks).chain().after(collapse_step),
+            );
+    }
+}
+
+fn toggle_tool(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keybindings: Res<Keybindings>,
+    mut tool: ResMut<LockTool>,
+) {
+    if keybindings.just_pressed(Action::ToggleLockTool, &keyboard_input) {
+        tool.active = !tool.active;
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn toggle_lock(
+    mouse: Res<ButtonInput<MouseButton>>,
+    tool: Res<LockTool>,
+    inspector: Res<Inspector>,
+    mut locks: ResMut<Locks>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+    interactions: Query<&Interaction>,
+    grid: Res<WfcGrid>,
+) {
+    // The inspector takes precedence so cells can be inspected mid-edit.
+    if !tool.active || inspector.active || !mouse.just_pressed(MouseButton::Left) {
+        return;
+    }
+    if interactions.iter().any(|i| *i != Interaction::None) {
+        return;
+    }
+    let Ok(window) = windows.single() else {
+        return;
+    };
+    let Some((x, y)) = cursor_world_position(window, &cameras).and_then(cell_at) else {
+        return;
+    };
+    let tile = grid.cell(x, y);
+    if tile.collapsed {
+        locks.toggle(x, y, tile.possible[0]);
+    }
+}
+
+/// Unlocks cells that no longer hold their locked tile, e.g. after undoing
+/// past the collapse that was locked.
+fn drop_stale_locks(mut locks: ResMut<Locks>, grid: Res<WfcGrid>) {
+    if !grid.is_changed() || locks.0.is_empty() {
+        return;
+    }
+    locks.0.retain(|&(x, y), &mut locked| {
+        let tile = grid.cell(x, y);
+        tile.collapsed && tile.possible[0] == locked
+    });
+}
+
+fn draw_locks(mut gizmos: Gizmos, locks: Res<Locks>) {
+    for &(x, y) in locks.0.keys() {
+        let center = cell_center(x, y);
+        gizmos.rect_2d(
+            Isometry2d::from_translation(center),
+            Vec2::splat(TILE_SIZE - 3.0),
+            LOCK_COLOR,
+        );
+        // Badge in the top-right corner.
+        gizmos.rect_2d(
+            Isometry2d::from_translation(center + Vec2::splat(TILE_SIZE * 0.25)),
+            Vec2::splat(LOCK_ICON_SIZE),
+            LOCK_COLOR,
+        );
+    }
+}
@@ -0,0 +1,223 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::camera::cursor_world_position;
+use crate::history::{GridState, History};
+use crate::inspector::{Inspector, Provenance};
+use crate::keybindings::{Action, Keybindings};
+use crate::lock::LockTool;
+use crate::path::PathTool;
+use crate::rules::Rules;
+use crate::strategy::Strategy;
+use crate::theme::Theme;
+use crate::tile_palette::SelectedTile;
+use crate::timeline::Timeline;
+use crate::{TileType, WfcGrid, cell_at, collapse_step, force_collapse};
+
+const CHOOSER_FONT_SIZE: f32 = 14.0;
+const CHOOSER_BACKGROUND: Color = Color::srgba(0.05, 0.05, 0.05, 0.9);
+const CHOICE_HOVERED: Color = Color::srgba(1.0, 1.0, 1.0, 0.15);
+const CHOICE_SWATCH_PX: f32 = 12.0;
+
+/// Popup listing the tiles still possible for a clicked cell.
+#[derive(Component)]
+struct Chooser;
+
+/// Button in the chooser that collapses `cell` to `tile`.
+#[derive(Component)]
+struct Choice {
+    cell: (usize, usize),
+    tile: TileType,
+}
+
+pub struct ManualCollapsePlugin;
+
+impl Plugin for ManualCollapsePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (pick_choice, open_chooser, highlight_choices)
+                .chain()
+                .before(collapse_step),
+        );
+    }
+}
+
+/// Everything a manual collapse updates besides the grid itself.
+#[derive(SystemParam)]
+pub struct ManualCollapse<'w> {
+    rules: Res<'w, Rules>,
+    strategy: Res<'w, Strategy>,
+    history: ResMut<'w, History>,
+    provenance: ResMut<'w, Provenance>,
+    timeline: ResMut<'w, Timeline>,
+}
+
+impl ManualCollapse<'_> {
+    /// Records the edit for undo and the timeline, then force-collapses the
+    /// cell. Cells that are already collapsed are left alone.
+    pub fn collapse(
+        &mut self,
+        commands: &mut Commands,
+        grid: &mut WfcGrid,
+        (x, y): (usize, usize),
+        choice: TileType,
+    ) {
+        if grid.cell(x, y).collapsed {
+            return;
+        }
+        self.timeline.record(x, y, choice);
+        self.history.record(GridState::capture(grid));
+        force_collapse(
+            commands,
+            &self.rules,
+            self.strategy.propagation,
+            &mut self.provenance,
+            grid,
+            (x, y),
+            choice,
+        );
+    }
+}
+
+/// Force-collapses the cell when one of the chooser's tiles is clicked.
+fn pick_choice(
+    mut commands: Commands,
+    choices: Query<(&Interaction, &Choice), Changed<Interaction>>,
+    choosers: Query<Entity, With<Chooser>>,
+    mut manual: ManualCollapse,
+    mut grid: ResMut<WfcGrid>,
+) {
+    for (interaction, choice) in choices.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        manual.collapse(&mut commands, &mut grid, choice.cell, choice.tile);
+        for chooser in choosers.iter() {
+            commands.entity(chooser).despawn();
+        }
+    }
+}
+
+/// Left-clicking an uncollapsed cell collapses it to the palette's selected
+/// tile if that is still possible there, and otherwise opens a chooser at the
+/// cursor; clicking anywhere else or the close binding dismisses it.
+#[allow(clippy::too_many_arguments)]
+fn open_chooser(
+    mut commands: Commands,
+    mouse: Res<ButtonInput<MouseButton>>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keybindings: Res<Keybindings>,
+    theme: Res<Theme>,
+    selected: Res<SelectedTile>,
+    lock_tool: Res<LockTool>,
+    inspector: Res<Inspector>,
+    path_tool: Res<PathTool>,
+    mut manual: ManualCollapse,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+    interactions: Query<&Interaction>,
+    choosers: Query<Entity, With<Chooser>>,
+    mut grid: ResMut<WfcGrid>,
+) {
+    if keybindings.just_pressed(Action::CloseMenu, &keyboard_input) {
+        for chooser in choosers.iter() {
+            commands.entity(chooser).despawn();
+        }
+    }
+    // The lock tool, the inspector and the path tool take over left-clicks
+    // while active.
+    if !mouse.just_pressed(MouseButton::Left)
+        || lock_tool.active
+        || inspector.active
+        || path_tool.active
+    {
+        return;
+    }
+    // Clicks on UI, including the chooser itself, are handled there.
+    if interactions.iter().any(|i| *i != Interaction::None) {
+        return;
+    }
+    for chooser in choosers.iter() {
+        commands.entity(chooser).despawn();
+    }
+
+    let Ok(window) = windows.single() else {
+        return;
+    };
+    let Some(cursor) = window.cursor_position() else {
+        return;
+    };
+    let Some((x, y)) = cursor_world_position(window, &cameras).and_then(cell_at) else {
+        return;
+    };
+    let tile = grid.cell(x, y);
+    if tile.collapsed {
+        return;
+    }
+    if let Some(choice) = selected.0.filter(|choice| tile.possible.contains(choice)) {
+        manual.collapse(&mut commands, &mut grid, (x, y), choice);
+        return;
+    }
+    let possible = tile.possible.clone();
+
+    commands
+        .spawn((
+            Chooser,
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Px(cursor.x),
+                top: Val::Px(cursor.y),
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(4.0)),
+                ..default()
+            },
+            BackgroundColor(CHOOSER_BACKGROUND),
+        ))
+        .with_children(|parent| {
+            for option in possible {
+                parent
+                    .spawn((
+                        Choice {
+                            cell: (x, y),
+                            tile: option,
+                        },
+                        Button,
+                        Node {
+                            align_items: AlignItems::Center,
+                            column_gap: Val::Px(6.0),
+                            padding: UiRect::axes(Val::Px(6.0), Val::Px(2.0)),
+                            ..default()
+                        },
+                        BackgroundColor(Color::NONE),
+                    ))
+                    .with_children(|button| {
+                        button.spawn((
+                            Node {
+                                width: Val::Px(CHOICE_SWATCH_PX),
+                                height: Val::Px(CHOICE_SWATCH_PX),
+                                ..default()
+                            },
+                            BackgroundColor(theme.color(option)),
+                        ));
+                        button.spawn((
+                            Text::new(format!("{option:?}")),
+                            TextFont::from_font_size(CHOOSER_FONT_SIZE),
+                        ));
+                    });
+            }
+        });
+}
+
+#[allow(clippy::type_complexity)]
+fn highlight_choices(
+    mut choices: Query<(&Interaction, &mut BackgroundColor), (With<Choice>, Changed<Interaction>)>,
+) {
+    for (interaction, mut background) in choices.iter_mut() {
+        background.0 = match interaction {
+            Interaction::None => Color::NONE,
+            Interaction::Hovered | Interaction::Pressed => CHOICE_HOVERED,
+        };
+    }
+}
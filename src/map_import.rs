@@ -0,0 +1,108 @@
+use std::path::Path;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::cli::Cli;
+use crate::history::GridState;
+use crate::inspector::Provenance;
+use crate::rules::Rules;
+use crate::timeline::Timeline;
+use crate::{GRID_H, GRID_W, TileType, WfcGrid, collapse_step, regenerate};
+
+/// The parts of a JSON export an import needs; see `docs/map.schema.json`.
+#[derive(Deserialize)]
+struct JsonCells {
+    cells: Vec<Vec<Option<usize>>>,
+}
+
+/// Map read from the file given with `--map`, applied once the first grid
+/// exists.
+#[derive(Resource, Default)]
+struct PendingImport(Option<Vec<Option<TileType>>>);
+
+pub struct MapImportPlugin;
+
+impl Plugin for MapImportPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PendingImport>()
+            .add_systems(Startup, read_import)
+            .add_systems(
+                Update,
+                apply_import
+                    .run_if(|pending: Res<PendingImport>| pending.0.is_some())
+                    .after(regenerate)
+                    .before(collapse_step),
+            );
+    }
+}
+
+/// Reads a JSON or CSV export into a grid. Unknown cells are `null` in
+/// JSON and empty or any other non-id value in CSV. Rows count from the top,
+/// and a map smaller than the grid fills its top-left corner, leaving the
+/// rest unknown.
+pub fn read_map(path: &Path) -> Result<Vec<Option<TileType>>, String> {
+    let text = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let rows: Vec<Vec<Option<usize>>> = if path.extension().is_some_and(|ext| ext == "json") {
+        serde_json::from_str::<JsonCells>(&text)
+            .map_err(|err| err.to_string())?
+            .cells
+    } else {
+        text.lines()
+            .map(|line| {
+                line.split(',')
+                    .map(|value| value.trim().parse().ok())
+                    .collect()
+            })
+            .collect()
+    };
+
+    let width = rows.iter().map(Vec::len).max().unwrap_or_default();
+    if rows.len() > GRID_H || width > GRID_W {
+        return Err(format!(
+            "map is {width}x{}, larger than the {GRID_W}x{GRID_H} grid",
+            rows.len()
+        ));
+    }
+    let mut grid = vec![None; GRID_W * GRID_H];
+    for (row, cells) in rows.iter().enumerate() {
+        let y = GRID_H - 1 - row;
+        for (x, id) in cells.iter().enumerate() {
+            grid[y * GRID_W + x] = id.and_then(|id| TileType::ALL.get(id).copied());
+        }
+    }
+    Ok(grid)
+}
+
+/// Reads the map given with `--map`, e.g. `cargo run -- --map map-123.csv`.
+fn read_import(cli: Res<Cli>, mut pending: ResMut<PendingImport>) {
+    let Some(path) = &cli.map else {
+        return;
+    };
+    match read_map(path) {
+        Ok(grid) => {
+            info!("filling in the unknown cells of {}", path.display());
+            pending.0 = Some(grid);
+        }
+        Err(err) => warn!("could not import {}: {err}", path.display()),
+    }
+}
+
+/// Writes the imported cells as collapsed over the first grid, so the solver
+/// only fills in the unknown ones. Like any new map, the timeline starts
+/// from it.
+fn apply_import(
+    mut commands: Commands,
+    rules: Res<Rules>,
+    mut pending: ResMut<PendingImport>,
+    mut provenance: ResMut<Provenance>,
+    mut timeline: ResMut<Timeline>,
+    mut wfc_grid: ResMut<WfcGrid>,
+) {
+    let Some(grid) = pending.0.take() else {
+        return;
+    };
+    let state = GridState::from_grid(&rules, &grid);
+    state.restore(&mut commands, &rules, &mut provenance, &mut wfc_grid);
+    timeline.rebase(state);
+}
@@ -0,0 +1,277 @@
+use std::fmt::Write as _;
+
+use bevy::prelude::*;
+use serde_json::json;
+
+use crate::export::export_stem;
+use crate::keybindings::{Action, Keybindings};
+use crate::theme::Theme;
+use crate::view3d::tile_height;
+use crate::{GRID_H, GRID_W, TILE_SIZE, TileType, WfcGrid};
+
+// glTF constants.
+const GLB_MAGIC: u32 = 0x4654_6c67;
+const GLB_VERSION: u32 = 2;
+const CHUNK_JSON: u32 = 0x4e4f_534a;
+const CHUNK_BIN: u32 = 0x004e_4942;
+const FLOAT: u32 = 5126;
+const UNSIGNED_INT: u32 = 5125;
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+
+pub struct MeshExportPlugin;
+
+impl Plugin for MeshExportPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (export_obj, export_gltf));
+    }
+}
+
+/// Triangles of every cell of one tile type.
+#[derive(Default)]
+struct TileMesh {
+    positions: Vec<Vec3>,
+    normals: Vec<Vec3>,
+    indices: Vec<u32>,
+}
+
+impl TileMesh {
+    /// Adds the quad spanned by `u` and `v` from `origin`, facing `u × v`.
+    fn quad(&mut self, origin: Vec3, u: Vec3, v: Vec3) {
+        let first = self.positions.len() as u32;
+        let normal = u.cross(v).normalize();
+        for corner in [origin, origin + u, origin + u + v, origin + v] {
+            self.positions.push(corner);
+            self.normals.push(normal);
+        }
+        self.indices
+            .extend([0, 1, 2, 0, 2, 3].map(|offset| first + offset));
+    }
+}
+
+/// The 3D view's geometry, merged into a mesh per tile type, in cells rather
+/// than pixels: one unit per cell, Y up and the grid centered on the origin.
+/// Hidden faces are left out: bottoms, and sides against a cell at least as
+/// high. Uncollapsed cells have no geometry.
+fn map_meshes(grid: &[Option<TileType>]) -> Vec<(TileType, TileMesh)> {
+    let height = |x: i32, y: i32| {
+        if x < 0 || y < 0 || x >= GRID_W as i32 || y >= GRID_H as i32 {
+            return 0.0;
+        }
+        grid[y as usize * GRID_W + x as usize].map_or(0.0, |tile| tile_height(tile) / TILE_SIZE)
+    };
+
+    let mut meshes: Vec<(TileType, TileMesh)> = TileType::ALL
+        .iter()
+        .map(|&tile| (tile, TileMesh::default()))
+        .collect();
+    for y in 0..GRID_H {
+        for x in 0..GRID_W {
+            let Some(tile) = grid[y * GRID_W + x] else {
+                continue;
+            };
+            let mesh = &mut meshes[tile.index()].1;
+            let top = tile_height(tile) / TILE_SIZE;
+            // Grid rows run north along -Z, as in the 3D view.
+            let x0 = x as f32 - GRID_W as f32 / 2.0 - 0.5;
+            let x1 = x0 + 1.0;
+            let z1 = GRID_H as f32 / 2.0 - y as f32 + 0.5;
+            let z0 = z1 - 1.0;
+            mesh.quad(Vec3::new(x0, top, z1), Vec3::X, Vec3::NEG_Z);
+
+            let (x, y) = (x as i32, y as i32);
+            let sides = [
+                (height(x + 1, y), Vec3::new(x1, 0.0, z1), Vec3::NEG_Z),
+                (height(x - 1, y), Vec3::new(x0, 0.0, z0), Vec3::Z),
+                (height(x, y + 1), Vec3::new(x1, 0.0, z0), Vec3::NEG_X),
+                (height(x, y - 1), Vec3::new(x0, 0.0, z1), Vec3::X),
+            ];
+            for (neighbor, corner, along) in sides {
+                if neighbor < top {
+                    let origin = corner.with_y(neighbor);
+                    mesh.quad(origin, along, Vec3::Y * (top - neighbor));
+                }
+            }
+        }
+    }
+    meshes.retain(|(_, mesh)| !mesh.indices.is_empty());
+    meshes
+}
+
+fn material_name(tile: TileType) -> String {
+    format!("{tile:?}").to_lowercase()
+}
+
+/// Wavefront OBJ with an object per tile type, and the MTL file its
+/// materials are in.
+fn obj(grid: &[Option<TileType>], theme: &Theme, mtl_name: &str) -> (String, String) {
+    let mut obj = format!("mtllib {mtl_name}\n");
+    let mut mtl = String::new();
+    // OBJ indices are 1-based and shared across objects.
+    let mut offset = 1;
+    for (tile, mesh) in map_meshes(grid) {
+        let name = material_name(tile);
+        let color = theme.color(tile).to_srgba();
+        let _ = writeln!(
+            mtl,
+            "newmtl {name}\nKd {} {} {}\n",
+            color.red, color.green, color.blue
+        );
+
+        let _ = writeln!(obj, "o {name}\nusemtl {name}");
+        for position in &mesh.positions {
+            let _ = writeln!(obj, "v {} {} {}", position.x, position.y, position.z);
+        }
+        for normal in &mesh.normals {
+            let _ = writeln!(obj, "vn {} {} {}", normal.x, normal.y, normal.z);
+        }
+        for triangle in mesh.indices.chunks(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| triangle[i] + offset);
+            let _ = writeln!(obj, "f {a}//{a} {b}//{b} {c}//{c}");
+        }
+        offset += mesh.positions.len() as u32;
+    }
+    (obj, mtl)
+}
+
+/// Binary glTF with one mesh holding a primitive and material per tile type.
+fn glb(grid: &[Option<TileType>], theme: &Theme) -> Result<Vec<u8>, String> {
+    let mut bin: Vec<u8> = Vec::new();
+    let mut views = Vec::new();
+    let mut accessors = Vec::new();
+    let mut primitives = Vec::new();
+    let mut materials = Vec::new();
+    let mut add_view = |bin: &mut Vec<u8>, bytes: &[u8], target: u32| {
+        views.push(json!({
+            "buffer": 0,
+            "byteOffset": bin.len(),
+            "byteLength": bytes.len(),
+            "target": target,
+        }));
+        bin.extend_from_slice(bytes);
+        views.len() - 1
+    };
+
+    for (tile, mesh) in map_meshes(grid) {
+        let floats = |vectors: &[Vec3]| -> Vec<u8> {
+            vectors
+                .iter()
+                .flat_map(|v| v.to_array())
+                .flat_map(f32::to_le_bytes)
+                .collect()
+        };
+        let positions = add_view(&mut bin, &floats(&mesh.positions), ARRAY_BUFFER);
+        let normals = add_view(&mut bin, &floats(&mesh.normals), ARRAY_BUFFER);
+        let indices: Vec<u8> = mesh.indices.iter().flat_map(|i| i.to_le_bytes()).collect();
+        let indices = add_view(&mut bin, &indices, ELEMENT_ARRAY_BUFFER);
+
+        let (min, max) = mesh.positions.iter().fold(
+            (Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)),
+            |(min, max), &p| (min.min(p), max.max(p)),
+        );
+        let count = mesh.positions.len();
+        accessors.push(json!({
+            "bufferView": positions, "componentType": FLOAT, "count": count,
+            "type": "VEC3", "min": min.to_array(), "max": max.to_array(),
+        }));
+        accessors.push(json!({
+            "bufferView": normals, "componentType": FLOAT, "count": count, "type": "VEC3",
+        }));
+        accessors.push(json!({
+            "bufferView": indices, "componentType": UNSIGNED_INT,
+            "count": mesh.indices.len(), "type": "SCALAR",
+        }));
+        let first = accessors.len() - 3;
+        primitives.push(json!({
+            "attributes": { "POSITION": first, "NORMAL": first + 1 },
+            "indices": first + 2,
+            "material": materials.len(),
+        }));
+
+        let color = theme.color(tile).to_linear();
+        materials.push(json!({
+            "name": material_name(tile),
+            "pbrMetallicRoughness": {
+                "baseColorFactor": [color.red, color.green, color.blue, 1.0],
+                "metallicFactor": 0.0,
+                "roughnessFactor": if tile == TileType::Water { 0.1 } else { 0.9 },
+            },
+        }));
+    }
+
+    if primitives.is_empty() {
+        return Err("no cells are collapsed yet".into());
+    }
+    let gltf = json!({
+        "asset": { "version": "2.0", "generator": "wfc" },
+        "scene": 0,
+        "scenes": [{ "nodes": [0] }],
+        "nodes": [{ "name": "map", "mesh": 0 }],
+        "meshes": [{ "name": "map", "primitives": primitives }],
+        "materials": materials,
+        "buffers": [{ "byteLength": bin.len() }],
+        "bufferViews": views,
+        "accessors": accessors,
+    });
+
+    let mut json = serde_json::to_vec(&gltf).map_err(|err| err.to_string())?;
+    // Chunks are 4-byte aligned: JSON padded with spaces, binary with zeros.
+    json.resize(json.len().next_multiple_of(4), b' ');
+    bin.resize(bin.len().next_multiple_of(4), 0);
+
+    let length = 12 + 8 + json.len() + 8 + bin.len();
+    let mut bytes = Vec::with_capacity(length);
+    for word in [GLB_MAGIC, GLB_VERSION, length as u32] {
+        bytes.extend(word.to_le_bytes());
+    }
+    for (kind, chunk) in [(CHUNK_JSON, &json), (CHUNK_BIN, &bin)] {
+        bytes.extend((chunk.len() as u32).to_le_bytes());
+        bytes.extend(kind.to_le_bytes());
+        bytes.extend_from_slice(chunk);
+    }
+    Ok(bytes)
+}
+
+/// Writes the 3D view's geometry as `map-<unix millis>.obj` with its
+/// materials in a matching `.mtl`.
+fn export_obj(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keybindings: Res<Keybindings>,
+    theme: Res<Theme>,
+    grid: Res<WfcGrid>,
+) {
+    if !keybindings.just_pressed(Action::ExportObj, &keyboard_input) {
+        return;
+    }
+    let stem = export_stem("map");
+    let (obj_path, mtl_path) = (stem.with_extension("obj"), stem.with_extension("mtl"));
+    let mtl_name = mtl_path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let (obj, mtl) = obj(&grid.collapsed_grid(), &theme, &mtl_name);
+    let result = std::fs::write(&mtl_path, mtl).and_then(|()| std::fs::write(&obj_path, obj));
+    match result {
+        Ok(()) => info!("exported mesh to {}", obj_path.display()),
+        Err(err) => warn!("could not export {}: {err}", obj_path.display()),
+    }
+}
+
+/// Writes the 3D view's geometry as binary glTF, `map-<unix millis>.glb`.
+fn export_gltf(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keybindings: Res<Keybindings>,
+    theme: Res<Theme>,
+    grid: Res<WfcGrid>,
+) {
+    if !keybindings.just_pressed(Action::ExportGltf, &keyboard_input) {
+        return;
+    }
+    let path = export_stem("map").with_extension("glb");
+    let result = glb(&grid.collapsed_grid(), &theme)
+        .and_then(|bytes| std::fs::write(&path, bytes).map_err(|err| err.to_string()));
+    match result {
+        Ok(()) => info!("exported mesh to {}", path.display()),
+        Err(err) => warn!("could not export {}: {err}", path.display()),
+    }
+}
@@ -0,0 +1,96 @@
+use bevy::asset::RenderAssetUsages;
+use bevy::image::ImageSampler;
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+
+use crate::keybindings::{Action, Keybindings};
+use crate::theme::Theme;
+use crate::{GRID_H, GRID_W, WfcGrid, collapse_step};
+
+pub const MINIMAP_PX_PER_CELL: f32 = 4.0;
+const MINIMAP_MARGIN: f32 = 8.0;
+const UNCOLLAPSED_COLOR: Color = Color::srgb(0.15, 0.15, 0.15);
+
+#[derive(Resource)]
+struct MinimapImage(Handle<Image>);
+
+#[derive(Component)]
+struct Minimap;
+
+pub struct MinimapPlugin;
+
+impl Plugin for MinimapPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_minimap).add_systems(
+            Update,
+            (toggle_minimap, update_minimap.after(collapse_step)),
+        );
+    }
+}
+
+fn spawn_minimap(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    let mut image = Image::new_fill(
+        Extent3d {
+            width: GRID_W as u32,
+            height: GRID_H as u32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &UNCOLLAPSED_COLOR.to_srgba().to_u8_array(),
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    image.sampler = ImageSampler::nearest();
+    let handle = images.add(image);
+
+    commands.spawn((
+        Minimap,
+        ImageNode::new(handle.clone()),
+        Node {
+            position_type: PositionType::Absolute,
+            right: Val::Px(MINIMAP_MARGIN),
+            bottom: Val::Px(MINIMAP_MARGIN),
+            width: Val::Px(GRID_W as f32 * MINIMAP_PX_PER_CELL),
+            height: Val::Px(GRID_H as f32 * MINIMAP_PX_PER_CELL),
+            ..default()
+        },
+    ));
+    commands.insert_resource(MinimapImage(handle));
+}
+
+fn toggle_minimap(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keybindings: Res<Keybindings>,
+    mut minimaps: Query<&mut Visibility, With<Minimap>>,
+) {
+    if keybindings.just_pressed(Action::ToggleMinimap, &keyboard_input) {
+        for mut visibility in minimaps.iter_mut() {
+            visibility.toggle_visible_hidden();
+        }
+    }
+}
+
+/// Rewrites the pixels when the grid or the theme changed, one per cell.
+/// Image rows run top to bottom, grid rows bottom to top.
+fn update_minimap(
+    minimap: Res<MinimapImage>,
+    theme: Res<Theme>,
+    mut images: ResMut<Assets<Image>>,
+    grid: Res<WfcGrid>,
+) {
+    if !grid.is_changed() && !theme.is_changed() {
+        return;
+    }
+    let Some(image) = images.get_mut(&minimap.0) else {
+        return;
+    };
+
+    for (x, y, cell) in grid.iter() {
+        let color = if cell.collapsed {
+            theme.color(cell.possible[0])
+        } else {
+            UNCOLLAPSED_COLOR
+        };
+        let _ = image.set_color_at(x as u32, (GRID_H - 1 - y) as u32, color);
+    }
+}
@@ -0,0 +1,251 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use bevy::prelude::*;
+#[cfg(not(target_arch = "wasm32"))]
+use serde::Serialize;
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::export::export_stem;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::keybindings::{Action, Keybindings};
+use crate::{GRID_H, GRID_W, WfcGrid, collapse_step, neighbor_coords};
+
+#[cfg(not(target_arch = "wasm32"))]
+const NAV_FORMAT: &str = "wfc-nav";
+#[cfg(not(target_arch = "wasm32"))]
+const NAV_VERSION: u32 = 1;
+
+/// Movement cost of every cell for pathfinding, kept in step with the map.
+/// `None` where characters can't walk, including cells not collapsed yet.
+/// Moves go between the four cardinal neighbors and cost the cell entered;
+/// no cost is below 1, so the Manhattan distance is an admissible A*
+/// heuristic.
+#[derive(Resource)]
+pub struct NavGrid {
+    costs: Vec<Option<f32>>,
+}
+
+impl Default for NavGrid {
+    fn default() -> Self {
+        Self {
+            costs: vec![None; GRID_W * GRID_H],
+        }
+    }
+}
+
+impl NavGrid {
+    pub fn cost(&self, x: usize, y: usize) -> Option<f32> {
+        self.costs[y * GRID_W + x]
+    }
+
+    /// Cheapest path from `start` to `goal` by A*, both ends included, or
+    /// `None` if either is blocked or the goal can't be reached. Comes with
+    /// its cost: that of entering every cell on it but the start.
+    pub fn path(
+        &self,
+        start: (usize, usize),
+        goal: (usize, usize),
+    ) -> Option<(Vec<(usize, usize)>, f32)> {
+        self.cost(start.0, start.1)?;
+        self.cost(goal.0, goal.1)?;
+        let index = |(x, y): (usize, usize)| y * GRID_W + x;
+        let heuristic = |(x, y): (usize, usize)| (x.abs_diff(goal.0) + y.abs_diff(goal.1)) as f32;
+        let mut best = vec![f32::INFINITY; GRID_W * GRID_H];
+        let mut came_from = vec![None; GRID_W * GRID_H];
+        let mut open = BinaryHeap::new();
+        best[index(start)] = 0.0;
+        open.push(Open {
+            estimate: heuristic(start),
+            cell: start,
+        });
+        while let Some(Open { cell, .. }) = open.pop() {
+            if cell == goal {
+                let mut path = vec![goal];
+                while let Some(previous) = came_from[index(path[path.len() - 1])] {
+                    path.push(previous);
+                }
+                path.reverse();
+                return Some((path, best[index(goal)]));
+            }
+            // Cells pushed again with a better cost leave stale entries
+            // behind; expanding one again finds nothing new.
+            let cost = best[index(cell)];
+            for next in neighbor_coords(cell.0, cell.1) {
+                let Some(step) = self.cost(next.0, next.1) else {
+                    continue;
+                };
+                if cost + step < best[index(next)] {
+                    best[index(next)] = cost + step;
+                    came_from[index(next)] = Some(cell);
+                    open.push(Open {
+                        estimate: cost + step + heuristic(next),
+                        cell: next,
+                    });
+                }
+            }
+        }
+        None
+    }
+}
+
+/// A cell on the A* frontier, ordered so the heap's top is the lowest
+/// estimated total cost.
+struct Open {
+    estimate: f32,
+    cell: (usize, usize),
+}
+
+impl Ord for Open {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.estimate.total_cmp(&self.estimate)
+    }
+}
+
+impl PartialOrd for Open {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Open {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Open {}
+
+/// Contents of a navigation export.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Serialize)]
+struct NavFile {
+    format: &'static str,
+    version: u32,
+    width: usize,
+    height: usize,
+    /// Always 4: moves go between cardinal neighbors.
+    connectivity: u32,
+    /// Cost of entering each cell by row from the top, `null` where blocked.
+    costs: Vec<Vec<Option<f32>>>,
+}
+
+pub struct NavPlugin;
+
+impl Plugin for NavPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<NavGrid>()
+            .add_systems(Update, update_nav_grid.after(collapse_step));
+        #[cfg(not(target_arch = "wasm32"))]
+        app.add_systems(Update, export_nav.after(update_nav_grid));
+    }
+}
+
+pub fn update_nav_grid(grid: Res<WfcGrid>, mut nav: ResMut<NavGrid>) {
+    if !grid.is_changed() {
+        return;
+    }
+    nav.costs = grid
+        .collapsed_grid()
+        .into_iter()
+        .map(|tile| tile.and_then(|tile| tile.move_cost()))
+        .collect();
+}
+
+/// Writes the navigation grid as `nav-<unix millis>.json`.
+#[cfg(not(target_arch = "wasm32"))]
+fn export_nav(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keybindings: Res<Keybindings>,
+    nav: Res<NavGrid>,
+) {
+    if !keybindings.just_pressed(Action::ExportNav, &keyboard_input) {
+        return;
+    }
+    let file = NavFile {
+        format: NAV_FORMAT,
+        version: NAV_VERSION,
+        width: GRID_W,
+        height: GRID_H,
+        connectivity: 4,
+        costs: (0..GRID_H)
+            .rev()
+            .map(|y| (0..GRID_W).map(|x| nav.cost(x, y)).collect())
+            .collect(),
+    };
+    let path = export_stem("nav").with_extension("json");
+    let result = serde_json::to_string_pretty(&file)
+        .map_err(|err| err.to_string())
+        .and_then(|text| std::fs::write(&path, text).map_err(|err| err.to_string()));
+    match result {
+        Ok(()) => info!("exported navigation grid to {}", path.display()),
+        Err(err) => warn!("could not export {}: {err}", path.display()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TileType;
+
+    /// All grass, with the given cells set to `tile`.
+    fn grid_with(tile: TileType, cells: &[(usize, usize)]) -> NavGrid {
+        let mut costs = vec![TileType::Grass.move_cost(); GRID_W * GRID_H];
+        for &(x, y) in cells {
+            costs[y * GRID_W + x] = tile.move_cost();
+        }
+        NavGrid { costs }
+    }
+
+    #[test]
+    fn open_ground_takes_a_manhattan_path() {
+        let nav = grid_with(TileType::Grass, &[]);
+        let (path, cost) = nav.path((1, 1), (4, 3)).unwrap();
+        assert_eq!(path.len(), 6);
+        assert_eq!(path.first(), Some(&(1, 1)));
+        assert_eq!(path.last(), Some(&(4, 3)));
+        assert_eq!(cost, 5.0);
+        for pair in path.windows(2) {
+            assert_eq!(
+                pair[0].0.abs_diff(pair[1].0) + pair[0].1.abs_diff(pair[1].1),
+                1
+            );
+        }
+    }
+
+    #[test]
+    fn paths_go_around_costly_tiles_when_cheaper() {
+        // A strip across the direct route, open from row 3: crossing it as
+        // sand costs 0.5 more than open ground, going round it 6 more.
+        let sand: Vec<_> = (0..3).map(|y| (2, y)).collect();
+        let nav = grid_with(TileType::Sand, &sand);
+        let (path, cost) = nav.path((0, 0), (4, 0)).unwrap();
+        assert_eq!(cost, 4.5);
+        assert!(path.contains(&(2, 0)));
+
+        // Water can't be crossed at all, so the path goes round.
+        let nav = grid_with(TileType::Water, &sand);
+        let (path, cost) = nav.path((0, 0), (4, 0)).unwrap();
+        assert_eq!(cost, 10.0);
+        assert!(path.contains(&(2, 3)));
+    }
+
+    #[test]
+    fn blocked_ends_have_no_path() {
+        let nav = grid_with(TileType::Water, &[(3, 3)]);
+        assert!(nav.path((0, 0), (3, 3)).is_none());
+        assert!(nav.path((3, 3), (0, 0)).is_none());
+    }
+
+    #[test]
+    fn walled_off_goals_have_no_path() {
+        let nav = grid_with(TileType::Water, &[(4, 5), (6, 5), (5, 4), (5, 6)]);
+        assert!(nav.path((0, 0), (5, 5)).is_none());
+    }
+
+    #[test]
+    fn a_path_to_the_start_is_just_the_start() {
+        let nav = grid_with(TileType::Grass, &[]);
+        assert_eq!(nav.path((2, 2), (2, 2)), Some((vec![(2, 2)], 0.0)));
+    }
+}
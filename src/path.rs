@@ -0,0 +1,121 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::camera::cursor_world_position;
+use crate::inspector::Inspector;
+use crate::keybindings::{Action, Keybindings};
+use crate::lock::LockTool;
+use crate::nav::{NavGrid, update_nav_grid};
+use crate::{TILE_SIZE, cell_at, cell_center, collapse_step};
+
+const PATH_COLOR: Color = Color::srgb(1.0, 0.3, 0.8);
+const ENDPOINT_RADIUS: f32 = TILE_SIZE * 0.3;
+
+/// Shows the cheapest walkable path between two clicked cells, found on the
+/// [`NavGrid`] and found again whenever the map changes under it.
+#[derive(Resource, Default)]
+pub struct PathTool {
+    pub active: bool,
+    start: Option<(usize, usize)>,
+    goal: Option<(usize, usize)>,
+    /// The path between them and its cost, or `None` without both ends or
+    /// when the goal can't be reached.
+    path: Option<(Vec<(usize, usize)>, f32)>,
+}
+
+pub struct PathToolPlugin;
+
+impl Plugin for PathToolPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PathTool>()
+            .add_systems(
+                Update,
+                (toggle_tool, pick_endpoint).chain().before(collapse_step),
+            )
+            .add_systems(
+                Update,
+                (
+                    find_path.run_if(resource_changed::<PathTool>.or(resource_changed::<NavGrid>)),
+                    draw_path,
+                )
+                    .chain()
+                    .after(update_nav_grid),
+            );
+    }
+}
+
+fn toggle_tool(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keybindings: Res<Keybindings>,
+    mut tool: ResMut<PathTool>,
+) {
+    if keybindings.just_pressed(Action::TogglePathTool, &keyboard_input) {
+        *tool = PathTool {
+            active: !tool.active,
+            ..default()
+        };
+    }
+}
+
+/// The first click picks the start and the second the goal; another click
+/// starts over from a new start.
+#[allow(clippy::too_many_arguments)]
+fn pick_endpoint(
+    mouse: Res<ButtonInput<MouseButton>>,
+    lock_tool: Res<LockTool>,
+    inspector: Res<Inspector>,
+    mut tool: ResMut<PathTool>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+    interactions: Query<&Interaction>,
+) {
+    // The lock tool and the inspector take precedence, like over collapsing.
+    if !tool.active || lock_tool.active || inspector.active {
+        return;
+    }
+    if !mouse.just_pressed(MouseButton::Left)
+        || interactions.iter().any(|i| *i != Interaction::None)
+    {
+        return;
+    }
+    let Ok(window) = windows.single() else {
+        return;
+    };
+    let Some(cell) = cursor_world_position(window, &cameras).and_then(cell_at) else {
+        return;
+    };
+    if tool.start.is_none() || tool.goal.is_some() {
+        tool.start = Some(cell);
+        tool.goal = None;
+    } else {
+        tool.goal = Some(cell);
+    }
+}
+
+fn find_path(nav: Res<NavGrid>, mut tool: ResMut<PathTool>) {
+    let path = match (tool.start, tool.goal) {
+        (Some(start), Some(goal)) => nav.path(start, goal),
+        _ => None,
+    };
+    // Logged for new ends only, not for every collapse under the path.
+    if let Some((cells, cost)) = &path
+        && tool.is_changed()
+    {
+        info!("path of {} cells costs {cost}", cells.len());
+    }
+    // Storing the path isn't a change to react to.
+    tool.bypass_change_detection().path = path;
+}
+
+fn draw_path(mut gizmos: Gizmos, tool: Res<PathTool>) {
+    for cell in [tool.start, tool.goal].into_iter().flatten() {
+        gizmos.circle_2d(
+            Isometry2d::from_translation(cell_center(cell.0, cell.1)),
+            ENDPOINT_RADIUS,
+            PATH_COLOR,
+        );
+    }
+    if let Some((cells, _)) = &tool.path {
+        gizmos.linestrip_2d(cells.iter().map(|&(x, y)| cell_center(x, y)), PATH_COLOR);
+    }
+}
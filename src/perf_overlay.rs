@@ -0,0 +1,98 @@
+use bevy::diagnostic::{DiagnosticPath, DiagnosticsStore, FrameTimeDiagnosticsPlugin};
+use bevy::prelude::*;
+
+use crate::diagnostics::{COLLAPSES, DOMAIN_REDUCTIONS, SOLVER_TIME};
+use crate::keybindings::{Action, Keybindings};
+use crate::minimap::MINIMAP_PX_PER_CELL;
+use crate::{GRID_H, WfcGrid};
+
+const OVERLAY_MARGIN: f32 = 8.0;
+const OVERLAY_FONT_SIZE: f32 = 14.0;
+const OVERLAY_BACKGROUND: Color = Color::srgba(0.05, 0.05, 0.05, 0.7);
+/// Height of the minimap in the corner below, to sit above it.
+const MINIMAP_HEIGHT: f32 = GRID_H as f32 * MINIMAP_PX_PER_CELL;
+
+/// Whether the performance overlay is shown. Toggled with `F3`.
+#[derive(Resource, Default)]
+struct PerfOverlay {
+    visible: bool,
+}
+
+#[derive(Component)]
+struct PerfText;
+
+/// Solver throughput and frame time, read from the diagnostics store.
+pub struct PerfOverlayPlugin;
+
+impl Plugin for PerfOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<FrameTimeDiagnosticsPlugin>() {
+            app.add_plugins(FrameTimeDiagnosticsPlugin::default());
+        }
+        app.init_resource::<PerfOverlay>()
+            .add_systems(Startup, spawn_overlay)
+            .add_systems(Update, (toggle_overlay, update_overlay).chain());
+    }
+}
+
+fn spawn_overlay(mut commands: Commands) {
+    commands.spawn((
+        PerfText,
+        Text::default(),
+        TextFont::from_font_size(OVERLAY_FONT_SIZE),
+        Node {
+            position_type: PositionType::Absolute,
+            right: Val::Px(OVERLAY_MARGIN),
+            bottom: Val::Px(OVERLAY_MARGIN * 2.0 + MINIMAP_HEIGHT),
+            padding: UiRect::all(Val::Px(4.0)),
+            ..default()
+        },
+        BackgroundColor(OVERLAY_BACKGROUND),
+        Visibility::Hidden,
+    ));
+}
+
+fn toggle_overlay(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keybindings: Res<Keybindings>,
+    mut overlay: ResMut<PerfOverlay>,
+) {
+    if keybindings.just_pressed(Action::TogglePerformance, &keyboard_input) {
+        overlay.visible = !overlay.visible;
+    }
+}
+
+/// Rates and times are smoothed by the diagnostics; the rest of the frame is
+/// whatever the solver didn't take, rendering included.
+fn update_overlay(
+    overlay: Res<PerfOverlay>,
+    store: Res<DiagnosticsStore>,
+    grid: Res<WfcGrid>,
+    mut texts: Query<(&mut Text, &mut Visibility), With<PerfText>>,
+) {
+    for (mut text, mut visibility) in texts.iter_mut() {
+        visibility.set_if_neq(if overlay.visible {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        });
+        if !overlay.visible {
+            continue;
+        }
+        let smoothed = |path: &DiagnosticPath| {
+            store
+                .get(path)
+                .and_then(|diagnostic| diagnostic.smoothed())
+                .unwrap_or_default()
+        };
+        let frame = smoothed(&FrameTimeDiagnosticsPlugin::FRAME_TIME);
+        let solver = smoothed(&SOLVER_TIME);
+        text.0 = format!(
+            "collapses: {:.0}/s\ndomain reductions: {:.0}/s\nqueue: {} entries\nframe: {frame:.1} ms\n  solver: {solver:.1} ms\n  rest: {:.1} ms",
+            smoothed(&COLLAPSES),
+            smoothed(&DOMAIN_REDUCTIONS),
+            grid.queue.entries(),
+            (frame - solver).max(0.0),
+        );
+    }
+}
@@ -0,0 +1,62 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{GRID_H, GRID_W, TILE_SIZE, WfcGrid, cell_center, collapse_step};
+
+/// Static collider over a run of impassable cells in one grid row.
+#[derive(Component)]
+struct MapCollider;
+
+/// Spawns static rapier colliders over the collapsed tiles characters can't
+/// walk on, kept in step with the map. Adds rapier's own plugin unless the
+/// embedding app already has it.
+pub struct PhysicsPlugin;
+
+impl Plugin for PhysicsPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<RapierPhysicsPlugin<NoUserData>>() {
+            app.add_plugins(RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(
+                TILE_SIZE,
+            ));
+        }
+        app.add_systems(Update, sync_colliders.after(collapse_step));
+    }
+}
+
+/// Rebuilds the colliders whenever a tile changes, merging horizontally
+/// adjacent impassable cells into one box each.
+fn sync_colliders(
+    mut commands: Commands,
+    grid: Res<WfcGrid>,
+    colliders: Query<Entity, With<MapCollider>>,
+) {
+    if !grid.is_changed() {
+        return;
+    }
+    for collider in colliders.iter() {
+        commands.entity(collider).despawn();
+    }
+
+    let grid = grid.collapsed_grid();
+    let blocked = |x: usize, y: usize| grid[y * GRID_W + x].is_some_and(|tile| !tile.walkable());
+    for y in 0..GRID_H {
+        let mut x = 0;
+        while x < GRID_W {
+            if !blocked(x, y) {
+                x += 1;
+                continue;
+            }
+            let start = x;
+            while x < GRID_W && blocked(x, y) {
+                x += 1;
+            }
+            let center = (cell_center(start, y) + cell_center(x - 1, y)) / 2.0;
+            commands.spawn((
+                MapCollider,
+                RigidBody::Fixed,
+                Collider::cuboid((x - start) as f32 * TILE_SIZE / 2.0, TILE_SIZE / 2.0),
+                Transform::from_translation(center.extend(0.0)),
+            ));
+        }
+    }
+}
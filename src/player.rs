@@ -0,0 +1,120 @@
+use bevy::prelude::*;
+
+use crate::camera::FIT_MARGIN_TILES;
+use crate::collision::{Mover, move_movers};
+use crate::generation::GenerationState;
+use crate::keybindings::{Action, Keybindings};
+use crate::{GRID_H, GRID_W, TILE_SIZE, WfcGrid, cell_center, grid_center, layers};
+
+/// Walking speed in cells per second.
+const PLAYER_SPEED: f32 = 6.0;
+/// Side of the player's square, as a fraction of a cell.
+const PLAYER_SIZE: f32 = 0.6;
+const PLAYER_COLOR: Color = Color::srgb(0.95, 0.5, 0.1);
+
+/// The character walking a finished map. Spawned once generation completes
+/// and removed as soon as the map changes again; while it's there the pan
+/// keys walk it instead of panning the camera.
+#[derive(Component)]
+pub struct Player;
+
+pub struct PlayerPlugin;
+
+impl Plugin for PlayerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GenerationState::Complete), spawn_player)
+            .add_systems(OnExit(GenerationState::Complete), despawn_player)
+            .add_systems(
+                Update,
+                (
+                    move_player.before(move_movers),
+                    follow_player.after(move_movers),
+                )
+                    .run_if(in_state(GenerationState::Complete)),
+            );
+    }
+}
+
+/// Places the player on the walkable cell nearest the middle of the map, if
+/// there is one.
+fn spawn_player(mut commands: Commands, grid: Res<WfcGrid>) {
+    let Some((x, y, _)) = grid
+        .iter()
+        .filter(|(_, _, cell)| cell.collapsed && cell.possible[0].walkable())
+        .min_by_key(|(x, y, _)| x.abs_diff(GRID_W / 2).pow(2) + y.abs_diff(GRID_H / 2).pow(2))
+    else {
+        return;
+    };
+    let size = Vec2::splat(PLAYER_SIZE * TILE_SIZE);
+    commands.spawn((
+        Player,
+        Mover { size, ..default() },
+        Sprite {
+            color: PLAYER_COLOR,
+            custom_size: Some(size),
+            ..default()
+        },
+        Transform::from_translation(cell_center(x, y).extend(layers::PLAYER)),
+    ));
+}
+
+fn despawn_player(mut commands: Commands, players: Query<Entity, With<Player>>) {
+    for player in players.iter() {
+        commands.entity(player).despawn();
+    }
+}
+
+/// Walks the player with the pan keys; the [`CollisionGrid`] keeps it off
+/// tiles it can't walk on.
+///
+/// [`CollisionGrid`]: crate::collision::CollisionGrid
+fn move_player(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keybindings: Res<Keybindings>,
+    mut players: Query<&mut Mover, With<Player>>,
+) {
+    let mut direction = Vec2::ZERO;
+    let pressed = |action| keybindings.pressed(action, &keyboard_input);
+    if pressed(Action::PanUp) {
+        direction.y += 1.0;
+    }
+    if pressed(Action::PanDown) {
+        direction.y -= 1.0;
+    }
+    if pressed(Action::PanRight) {
+        direction.x += 1.0;
+    }
+    if pressed(Action::PanLeft) {
+        direction.x -= 1.0;
+    }
+    for mut mover in players.iter_mut() {
+        mover.velocity = direction.normalize_or_zero() * PLAYER_SPEED * TILE_SIZE;
+    }
+}
+
+/// Centers the camera on the player as far as that shows no more than the
+/// fitting margin past the grid's edges. At the default zoom the whole grid
+/// is in view, so the camera stays put until zoomed in.
+fn follow_player(
+    players: Query<&Transform, (With<Player>, Without<Camera2d>)>,
+    mut cameras: Query<(&Camera, &mut Transform, &Projection), With<Camera2d>>,
+) {
+    let Ok(player) = players.single() else {
+        return;
+    };
+    let half_grid = (Vec2::new(GRID_W as f32, GRID_H as f32) / 2.0 + FIT_MARGIN_TILES) * TILE_SIZE;
+    for (camera, mut transform, projection) in cameras.iter_mut() {
+        let Projection::Orthographic(ortho) = projection else {
+            continue;
+        };
+        if !camera.is_active {
+            continue;
+        }
+        let slack = (half_grid - ortho.area.size() / 2.0).max(Vec2::ZERO);
+        let target = player
+            .translation
+            .truncate()
+            .clamp(grid_center() - slack, grid_center() + slack);
+        transform.translation = target.extend(transform.translation.z);
+    }
+}
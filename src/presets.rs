@@ -0,0 +1,249 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::asset_files::{list_assets, read_asset};
+use crate::keybindings::{Action, Keybindings};
+use crate::lock::Locks;
+use crate::rules::{Rules, tileset_path};
+use crate::{GRID_H, GRID_W, Seed, TileType, regenerate};
+
+/// Directory under the asset root holding preset files.
+const PRESET_DIR: &str = "presets";
+const PRESET_EXTENSION: &str = "ron";
+const MENU_FONT_SIZE: f32 = 14.0;
+const TITLE_FONT_SIZE: f32 = 20.0;
+const DESCRIPTION_COLOR: Color = Color::srgb(0.7, 0.7, 0.7);
+const MENU_BACKGROUND: Color = Color::srgba(0.05, 0.05, 0.05, 0.9);
+const MENU_HOVERED: Color = Color::srgba(0.3, 0.3, 0.3, 0.9);
+
+/// A ready-made scenario: a tileset, tile weights and cells fixed before
+/// generation. The grid size is fixed, so the border sets how much room the
+/// rest of the map gets.
+#[derive(Deserialize)]
+struct Preset {
+    name: String,
+    description: String,
+    /// File name in `assets/tilesets`.
+    tileset: String,
+    #[serde(default)]
+    weights: HashMap<TileType, f32>,
+    #[serde(default)]
+    border: Option<Border>,
+}
+
+/// Ring of cells along the edge of the grid, locked to one tile.
+#[derive(Deserialize)]
+struct Border {
+    tile: TileType,
+    width: usize,
+}
+
+impl Preset {
+    fn load(path: &Path) -> Result<Self, String> {
+        let text = read_asset(path)?;
+        ron::from_str(&text).map_err(|err| err.to_string())
+    }
+
+    fn locks(&self) -> HashMap<(usize, usize), TileType> {
+        let mut locks = HashMap::new();
+        let Some(border) = &self.border else {
+            return locks;
+        };
+        for y in 0..GRID_H {
+            for x in 0..GRID_W {
+                let edge_distance = x.min(y).min(GRID_W - 1 - x).min(GRID_H - 1 - y);
+                if edge_distance < border.width {
+                    locks.insert((x, y), border.tile);
+                }
+            }
+        }
+        locks
+    }
+}
+
+/// Centered start menu listing the presets, shown at startup and reopened
+/// with `F2`.
+#[derive(Component)]
+struct PresetMenu;
+
+#[derive(Component)]
+struct PresetOption(PathBuf);
+
+/// Closes the menu without changing anything.
+#[derive(Component)]
+struct KeepCurrent;
+
+pub struct PresetMenuPlugin;
+
+impl Plugin for PresetMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_menu).add_systems(
+            Update,
+            (
+                reopen_menu,
+                choose_preset.before(regenerate),
+                highlight_buttons,
+            )
+                .chain(),
+        );
+    }
+}
+
+/// Preset files in `assets/presets`, sorted by file name.
+fn list_presets() -> Vec<(PathBuf, Preset)> {
+    list_assets(PRESET_DIR, PRESET_EXTENSION)
+        .into_iter()
+        .filter_map(|path| match Preset::load(&path) {
+            Ok(preset) => Some((path, preset)),
+            Err(err) => {
+                warn!("could not load {}: {err}", path.display());
+                None
+            }
+        })
+        .collect()
+}
+
+fn spawn_menu(mut commands: Commands) {
+    let presets = list_presets();
+    commands
+        .spawn((
+            PresetMenu,
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    Node {
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Val::Px(4.0),
+                        padding: UiRect::all(Val::Px(8.0)),
+                        ..default()
+                    },
+                    BackgroundColor(MENU_BACKGROUND),
+                ))
+                .with_children(|menu| {
+                    menu.spawn((
+                        Text::new("Choose a scenario"),
+                        TextFont::from_font_size(TITLE_FONT_SIZE),
+                    ));
+                    for (path, preset) in presets {
+                        menu.spawn((
+                            PresetOption(path),
+                            Button,
+                            Node {
+                                flex_direction: FlexDirection::Column,
+                                padding: UiRect::axes(Val::Px(6.0), Val::Px(2.0)),
+                                ..default()
+                            },
+                            BackgroundColor(Color::NONE),
+                            children![
+                                (
+                                    Text::new(preset.name),
+                                    TextFont::from_font_size(MENU_FONT_SIZE),
+                                ),
+                                (
+                                    Text::new(preset.description),
+                                    TextFont::from_font_size(MENU_FONT_SIZE),
+                                    TextColor(DESCRIPTION_COLOR),
+                                ),
+                            ],
+                        ));
+                    }
+                    menu.spawn((
+                        KeepCurrent,
+                        Button,
+                        Node {
+                            padding: UiRect::axes(Val::Px(6.0), Val::Px(2.0)),
+                            ..default()
+                        },
+                        BackgroundColor(Color::NONE),
+                        children![(
+                            Text::new("Keep current settings"),
+                            TextFont::from_font_size(MENU_FONT_SIZE),
+                        )],
+                    ));
+                });
+        });
+}
+
+/// Rebuilt on every open so edited or new preset files show up.
+fn reopen_menu(
+    commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keybindings: Res<Keybindings>,
+    menus: Query<(), With<PresetMenu>>,
+) {
+    if menus.is_empty() && keybindings.just_pressed(Action::ShowPresets, &keyboard_input) {
+        spawn_menu(commands);
+    }
+}
+
+/// Applies the chosen preset: its tileset and weights become the rules and
+/// its fixed cells replace the locks, then the map regenerates.
+#[allow(clippy::type_complexity)]
+fn choose_preset(
+    mut commands: Commands,
+    options: Query<
+        (&Interaction, Option<&PresetOption>),
+        (
+            Changed<Interaction>,
+            Or<(With<PresetOption>, With<KeepCurrent>)>,
+        ),
+    >,
+    menus: Query<Entity, With<PresetMenu>>,
+    mut rules: ResMut<Rules>,
+    mut locks: ResMut<Locks>,
+    mut seed: ResMut<Seed>,
+) {
+    for (interaction, option) in options.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        if let Some(option) = option {
+            match Preset::load(&option.0).and_then(|preset| {
+                let mut loaded = Rules::load(&tileset_path(&preset.tileset))?;
+                loaded.set_weights(&preset.weights);
+                Ok((preset, loaded))
+            }) {
+                Ok((preset, loaded)) => {
+                    info!("preset: {}", preset.name);
+                    *rules = loaded;
+                    locks.replace(preset.locks());
+                    seed.0 = rand::random();
+                }
+                Err(err) => warn!("could not load {}: {err}", option.0.display()),
+            }
+        }
+        for menu in menus.iter() {
+            commands.entity(menu).despawn();
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn highlight_buttons(
+    mut buttons: Query<
+        (&Interaction, &mut BackgroundColor),
+        (
+            Or<(With<PresetOption>, With<KeepCurrent>)>,
+            Changed<Interaction>,
+        ),
+    >,
+) {
+    for (interaction, mut background) in buttons.iter_mut() {
+        background.0 = match interaction {
+            Interaction::None => Color::NONE,
+            Interaction::Hovered | Interaction::Pressed => MENU_HOVERED,
+        };
+    }
+}
@@ -0,0 +1,86 @@
+use bevy::prelude::*;
+
+use crate::animation::CollapseTween;
+use crate::chunked::RenderBackend;
+use crate::theme::Theme;
+use crate::variation::TileVariation;
+use crate::{Tile, TileType, WfcGrid, collapse_step};
+
+/// Tile last reflected in the sprite, if the cell was collapsed, to tell
+/// fresh collapses apart from other changes.
+#[derive(Component)]
+pub struct RenderedState {
+    collapsed: Option<TileType>,
+}
+
+pub struct TileRenderPlugin;
+
+impl Plugin for TileRenderPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            // The texture backend doesn't draw the sprites; they catch up on
+            // the way back.
+            sync_tile_sprites
+                .run_if(
+                    resource_changed::<WfcGrid>
+                        .or(resource_changed::<RenderBackend>)
+                        .and(not(resource_equals(RenderBackend::Texture))),
+                )
+                .after(collapse_step),
+        );
+    }
+}
+
+/// Updates sprites only for cells whose collapsed tile changed, starting the
+/// collapse tween for newly collapsed ones.
+#[allow(clippy::type_complexity)]
+pub fn sync_tile_sprites(
+    mut commands: Commands,
+    theme: Res<Theme>,
+    grid: Res<WfcGrid>,
+    mut tiles: Query<(
+        Entity,
+        Ref<Tile>,
+        &mut Sprite,
+        Option<&mut RenderedState>,
+        Option<&TileVariation>,
+    )>,
+) {
+    let _span = info_span!("render_sync").entered();
+    for (entity, tile, mut sprite, rendered, variation) in tiles.iter_mut() {
+        let cell = grid.cell(tile.x, tile.y);
+        let collapsed = cell.collapsed.then(|| cell.possible[0]);
+        let was_collapsed = match rendered {
+            // Regeneration resets reused tiles' sprites, so they start over
+            // like new ones.
+            Some(mut rendered) if tile.is_changed() => {
+                rendered.collapsed = collapsed;
+                false
+            }
+            Some(mut rendered) => {
+                if rendered.collapsed == collapsed {
+                    continue;
+                }
+                std::mem::replace(&mut rendered.collapsed, collapsed).is_some()
+            }
+            None => {
+                commands
+                    .entity(entity)
+                    .try_insert(RenderedState { collapsed });
+                false
+            }
+        };
+
+        let Some(collapsed) = collapsed else {
+            sprite.color = Color::WHITE;
+            continue;
+        };
+
+        let color = theme.color(collapsed);
+        sprite.color = variation.map_or(color, |v| v.tint(color));
+        if !was_collapsed {
+            commands.entity(entity).try_insert(CollapseTween::default());
+        }
+    }
+}
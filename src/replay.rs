@@ -0,0 +1,241 @@
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::asset_files::{exe_relative_dir, unix_millis, write_creating_dir};
+use crate::inspector::Provenance;
+use crate::keybindings::{Action, Keybindings};
+use crate::lock::Locks;
+use crate::rules::Rules;
+use crate::stepping::{StepBudget, plan_steps};
+use crate::strategy::Strategy;
+use crate::timeline::{Timeline, TimelineEvent};
+use crate::{GRID_H, GRID_W, Seed, WfcGrid, collapse_step, force_collapse, regenerate};
+
+const REPLAYS_DIR: &str = "replays";
+const REPLAY_EXTENSION: &str = "ron";
+
+/// Contents of a replay file: the configuration a map was generated with and
+/// everything that happened to it. Observations are stored as the cell and
+/// tile they picked rather than as random draws, so playback doesn't depend
+/// on the random number generator and matches on any machine.
+#[derive(Serialize, Deserialize)]
+struct Replay {
+    seed: u64,
+    width: usize,
+    height: usize,
+    rules: Rules,
+    strategy: Strategy,
+    locks: Locks,
+    events: Vec<TimelineEvent>,
+}
+
+impl Replay {
+    fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+        let replay: Self = ron::from_str(&text).map_err(|err| err.to_string())?;
+        if (replay.width, replay.height) != (GRID_W, GRID_H) {
+            return Err(format!(
+                "replay grid is {}x{}, expected {GRID_W}x{GRID_H}",
+                replay.width, replay.height
+            ));
+        }
+        Ok(replay)
+    }
+}
+
+/// Events of the replay being played, applied in place of the solver's own
+/// steps at the current generation speed. Pausing and single steps work as
+/// they do for the solver.
+#[derive(Resource, Default)]
+struct Playback {
+    events: Option<VecDeque<TimelineEvent>>,
+    /// Set on the frame the replay's map is regenerated, before its tiles
+    /// and timeline exist.
+    starting: bool,
+}
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Playback>().add_systems(
+            Update,
+            (
+                save_replay,
+                start_playback.before(regenerate),
+                play_events
+                    .run_if(|playback: Res<Playback>| playback.events.is_some())
+                    .after(regenerate)
+                    .after(plan_steps)
+                    .before(collapse_step),
+            )
+                .chain(),
+        );
+    }
+}
+
+/// `replays/` next to the executable.
+fn replays_dir() -> PathBuf {
+    exe_relative_dir(REPLAYS_DIR)
+}
+
+/// Replay files are named by timestamp, so the newest sorts last.
+fn newest_replay() -> Option<PathBuf> {
+    let entries = std::fs::read_dir(replays_dir()).ok()?;
+    entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == REPLAY_EXTENSION))
+        .max()
+}
+
+/// Writes the current map's configuration and timeline log to
+/// `replays/replay-<unix millis>.ron`.
+fn save_replay(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keybindings: Res<Keybindings>,
+    seed: Res<Seed>,
+    rules: Res<Rules>,
+    strategy: Res<Strategy>,
+    locks: Res<Locks>,
+    timeline: Res<Timeline>,
+) {
+    if !keybindings.just_pressed(Action::SaveReplay, &keyboard_input) {
+        return;
+    }
+    let replay = Replay {
+        seed: seed.0,
+        width: GRID_W,
+        height: GRID_H,
+        rules: rules.clone(),
+        strategy: *strategy,
+        locks: locks.clone(),
+        events: timeline.log().to_vec(),
+    };
+    let path = replays_dir().join(format!("replay-{}.{REPLAY_EXTENSION}", unix_millis()));
+
+    let result = ron::ser::to_string_pretty(&replay, ron::ser::PrettyConfig::default())
+        .map_err(|err| err.to_string())
+        .and_then(|text| write_creating_dir(&path, &text));
+    match result {
+        Ok(()) => info!(
+            "saved replay of {} events to {}",
+            replay.events.len(),
+            path.display()
+        ),
+        Err(err) => warn!("could not save {}: {err}", path.display()),
+    }
+}
+
+/// Applies the newest replay's configuration and regenerates with its seed,
+/// so per-cell variation matches, then queues its events.
+#[allow(clippy::too_many_arguments)]
+fn start_playback(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keybindings: Res<Keybindings>,
+    mut seed: ResMut<Seed>,
+    mut rules: ResMut<Rules>,
+    mut strategy: ResMut<Strategy>,
+    mut locks: ResMut<Locks>,
+    mut playback: ResMut<Playback>,
+) {
+    if !keybindings.just_pressed(Action::PlayReplay, &keyboard_input) {
+        return;
+    }
+    let Some(path) = newest_replay() else {
+        warn!("no replays in {}", replays_dir().display());
+        return;
+    };
+    let replay = match Replay::load(&path) {
+        Ok(replay) => replay,
+        Err(err) => {
+            warn!("could not load {}: {err}", path.display());
+            return;
+        }
+    };
+    info!(
+        "playing {} events from {}",
+        replay.events.len(),
+        path.display()
+    );
+    *rules = replay.rules;
+    *strategy = replay.strategy;
+    *locks = replay.locks;
+    seed.0 = replay.seed;
+    seed.set_changed();
+    let mut events: VecDeque<TimelineEvent> = replay.events.into();
+    // The log opens with the map's starting grid, which regenerating from
+    // the same configuration rebuilds and the timeline logs by itself.
+    if matches!(events.front(), Some(TimelineEvent::Restore(_))) {
+        events.pop_front();
+    }
+    playback.events = Some(events);
+    playback.starting = true;
+}
+
+/// Uses up the frame's step budget on replay events, leaving none for the
+/// solver. Stops early if a new map is generated some other way.
+#[allow(clippy::too_many_arguments)]
+fn play_events(
+    mut commands: Commands,
+    seed: Res<Seed>,
+    rules: Res<Rules>,
+    strategy: Res<Strategy>,
+    mut budget: ResMut<StepBudget>,
+    mut playback: ResMut<Playback>,
+    mut timeline: ResMut<Timeline>,
+    mut provenance: ResMut<Provenance>,
+    mut grid: ResMut<WfcGrid>,
+) {
+    let steps = std::mem::take(&mut budget.0);
+    if std::mem::take(&mut playback.starting) {
+        return;
+    }
+    if seed.is_changed() {
+        info!("replay stopped");
+        playback.events = None;
+        return;
+    }
+    let Some(events) = &mut playback.events else {
+        return;
+    };
+
+    for _ in 0..steps {
+        let Some(event) = events.pop_front() else {
+            break;
+        };
+        match event {
+            TimelineEvent::Collapse(x, y, choice) => {
+                force_collapse(
+                    &mut commands,
+                    &rules,
+                    strategy.propagation,
+                    &mut provenance,
+                    &mut grid,
+                    (x, y),
+                    choice,
+                );
+                timeline.record(x, y, choice);
+            }
+            TimelineEvent::Seek(position) => timeline.seek(
+                position,
+                &mut commands,
+                &rules,
+                strategy.propagation,
+                &mut provenance,
+                &mut grid,
+            ),
+            TimelineEvent::Restore(state) => {
+                state.restore(&mut commands, &rules, &mut provenance, &mut grid);
+                timeline.rebase(state);
+            }
+        }
+    }
+
+    if events.is_empty() {
+        info!("replay finished");
+        playback.events = None;
+    }
+}
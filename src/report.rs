@@ -0,0 +1,248 @@
+use std::io::Write;
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use serde::Serialize;
+
+use crate::cli::Cli;
+use crate::export::export_stem;
+use crate::generation::GenerationState;
+use crate::keybindings::{Action, Keybindings};
+use crate::rules::Rules;
+use crate::stats::{GenerationStats, measure};
+use crate::{GRID_H, GRID_W, Seed, TileType, WfcGrid};
+
+/// Summary file every report adds a line to, next to the JSON reports.
+const SUMMARY_FILE: &str = "reports.csv";
+
+/// Statistics of one finished map, for tuning rulesets over many seeds.
+#[derive(Serialize)]
+pub struct Report {
+    seed: u64,
+    rules: String,
+    width: usize,
+    height: usize,
+    collapsed: usize,
+    contradictions: usize,
+    /// Regenerations before this map. Always 0 for headless runs.
+    restarts: usize,
+    /// Unpaused generation time.
+    seconds: f32,
+    tiles: Vec<TileReport>,
+}
+
+#[derive(Serialize)]
+struct TileReport {
+    name: String,
+    cells: usize,
+    /// Fraction of all cells.
+    share: f32,
+    /// Contiguous 4-connected areas of the tile.
+    regions: usize,
+    largest_region: usize,
+}
+
+impl Report {
+    pub fn new(
+        seed: u64,
+        rules_name: &str,
+        grid: &[Option<TileType>],
+        contradictions: usize,
+        restarts: usize,
+        seconds: f32,
+    ) -> Self {
+        let regions = regions(grid);
+        let tiles = TileType::ALL
+            .iter()
+            .map(|&tile| {
+                let sizes: Vec<usize> = regions
+                    .iter()
+                    .filter(|&&(region_tile, _)| region_tile == tile)
+                    .map(|&(_, size)| size)
+                    .collect();
+                let cells: usize = sizes.iter().sum();
+                TileReport {
+                    name: format!("{tile:?}"),
+                    cells,
+                    share: cells as f32 / grid.len() as f32,
+                    regions: sizes.len(),
+                    largest_region: sizes.iter().copied().max().unwrap_or_default(),
+                }
+            })
+            .collect();
+        Self {
+            seed,
+            rules: rules_name.to_string(),
+            width: GRID_W,
+            height: GRID_H,
+            collapsed: grid.iter().flatten().count(),
+            contradictions,
+            restarts,
+            seconds,
+            tiles,
+        }
+    }
+
+    fn csv_header(&self) -> String {
+        let mut columns: Vec<String> = [
+            "seed",
+            "rules",
+            "collapsed",
+            "contradictions",
+            "restarts",
+            "seconds",
+        ]
+        .map(String::from)
+        .to_vec();
+        for tile in &self.tiles {
+            let name = tile.name.to_lowercase();
+            columns.extend(["cells", "regions", "largest_region"].map(|c| format!("{name}_{c}")));
+        }
+        columns.join(",")
+    }
+
+    fn csv_row(&self) -> String {
+        let mut values = vec![
+            self.seed.to_string(),
+            // Ruleset names are free text; keep them from splitting the row.
+            self.rules.replace(',', " "),
+            self.collapsed.to_string(),
+            self.contradictions.to_string(),
+            self.restarts.to_string(),
+            format!("{:.3}", self.seconds),
+        ];
+        for tile in &self.tiles {
+            values.extend([tile.cells, tile.regions, tile.largest_region].map(|v| v.to_string()));
+        }
+        values.join(",")
+    }
+
+    /// Writes the report as `report-<unix millis>.json` and adds a line to
+    /// `reports.csv` in the same directory, starting it with a header.
+    /// Returns the JSON file's path.
+    pub fn write(&self) -> Result<PathBuf, String> {
+        let path = export_stem("report").with_extension("json");
+        let json = serde_json::to_string_pretty(self).map_err(|err| err.to_string())?;
+        std::fs::write(&path, json).map_err(|err| err.to_string())?;
+
+        let summary = path.with_file_name(SUMMARY_FILE);
+        let new = !summary.exists();
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&summary)
+            .map_err(|err| err.to_string())?;
+        if new {
+            writeln!(file, "{}", self.csv_header()).map_err(|err| err.to_string())?;
+        }
+        writeln!(file, "{}", self.csv_row()).map_err(|err| err.to_string())?;
+        Ok(path)
+    }
+}
+
+/// Tile and size of every contiguous 4-connected area of collapsed cells.
+fn regions(grid: &[Option<TileType>]) -> Vec<(TileType, usize)> {
+    let mut seen = vec![false; grid.len()];
+    let mut regions = Vec::new();
+    for start in 0..grid.len() {
+        let Some(tile) = grid[start] else {
+            continue;
+        };
+        if seen[start] {
+            continue;
+        }
+        seen[start] = true;
+        let mut stack = vec![start];
+        let mut size = 0;
+        while let Some(cell) = stack.pop() {
+            size += 1;
+            let (x, y) = (cell % GRID_W, cell / GRID_W);
+            let neighbors = [
+                (x + 1 < GRID_W).then(|| cell + 1),
+                (x > 0).then(|| cell - 1),
+                (y + 1 < GRID_H).then(|| cell + GRID_W),
+                (y > 0).then(|| cell - GRID_W),
+            ];
+            for neighbor in neighbors.into_iter().flatten() {
+                if !seen[neighbor] && grid[neighbor] == Some(tile) {
+                    seen[neighbor] = true;
+                    stack.push(neighbor);
+                }
+            }
+        }
+        regions.push((tile, size));
+    }
+    regions
+}
+
+/// Whether a report is written when a map finishes. Toggled with `Ctrl+I`
+/// and turned on from the start by `--report`.
+#[derive(Resource, Default)]
+struct ReportSettings {
+    enabled: bool,
+    /// Set once the current map's report is written.
+    written: bool,
+}
+
+pub struct ReportPlugin;
+
+impl Plugin for ReportPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ReportSettings>()
+            .add_systems(Startup, enable_from_cli)
+            .add_systems(
+                Update,
+                (toggle_reports, write_report.after(measure)).chain(),
+            );
+    }
+}
+
+fn enable_from_cli(cli: Res<Cli>, mut settings: ResMut<ReportSettings>) {
+    settings.enabled = cli.report;
+}
+
+fn toggle_reports(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keybindings: Res<Keybindings>,
+    mut settings: ResMut<ReportSettings>,
+) {
+    if keybindings.just_pressed(Action::ToggleReport, &keyboard_input) {
+        settings.enabled = !settings.enabled;
+        info!(
+            "generation reports {}",
+            if settings.enabled { "on" } else { "off" }
+        );
+    }
+}
+
+/// Writes a report once a map is finished.
+fn write_report(
+    seed: Res<Seed>,
+    state: Res<State<GenerationState>>,
+    rules: Res<Rules>,
+    stats: Res<GenerationStats>,
+    mut settings: ResMut<ReportSettings>,
+    grid: Res<WfcGrid>,
+) {
+    // The old map may still be around on the frame the seed changes.
+    if seed.is_changed() {
+        settings.written = false;
+        return;
+    }
+    if !settings.enabled || settings.written || !state.get().finished() {
+        return;
+    }
+    settings.written = true;
+    let report = Report::new(
+        seed.0,
+        &rules.name,
+        &grid.collapsed_grid(),
+        stats.map_contradictions,
+        stats.restarts,
+        stats.elapsed_secs,
+    );
+    match report.write() {
+        Ok(path) => info!("wrote generation report to {}", path.display()),
+        Err(err) => warn!("could not write generation report: {err}"),
+    }
+}
@@ -0,0 +1,117 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::camera::cursor_world_position;
+use crate::history::{GridState, History};
+use crate::inspector::Provenance;
+use crate::lock::Locks;
+use crate::rules::Rules;
+use crate::timeline::Timeline;
+use crate::{Contradicted, GRID_H, GRID_W, WfcGrid, cell_at, collapse_step, domain_from_neighbors};
+
+/// Cells reset around the clicked one when `Shift` is held, in each direction.
+const NEIGHBORHOOD_RADIUS: usize = 2;
+
+pub struct ResetToolPlugin;
+
+impl Plugin for ResetToolPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, reset_on_right_click.before(collapse_step));
+    }
+}
+
+/// Everything a region reset updates besides the grid itself.
+#[derive(SystemParam)]
+pub struct ResetRegion<'w> {
+    rules: Res<'w, Rules>,
+    locks: Res<'w, Locks>,
+    history: ResMut<'w, History>,
+    provenance: ResMut<'w, Provenance>,
+    timeline: ResMut<'w, Timeline>,
+}
+
+impl ResetRegion<'_> {
+    /// Un-collapses the collapsed cell at `(cx, cy)` and every cell within
+    /// `radius` of it, and recomputes the domains around them so the solver
+    /// fills the hole again. Locked cells are kept, and nothing happens if
+    /// the center cell is locked or not collapsed.
+    pub fn reset(
+        &mut self,
+        commands: &mut Commands,
+        wfc_grid: &mut WfcGrid,
+        (cx, cy): (usize, usize),
+        radius: usize,
+    ) {
+        if self.locks.contains(cx, cy) || !wfc_grid.cell(cx, cy).collapsed {
+            return;
+        }
+        let within = |x: usize, y: usize, r: usize| x.abs_diff(cx) <= r && y.abs_diff(cy) <= r;
+
+        self.history.record(GridState::capture(wfc_grid));
+
+        let mut grid = wfc_grid.collapsed_grid();
+        for y in 0..GRID_H {
+            for x in 0..GRID_W {
+                if within(x, y, radius) && !self.locks.contains(x, y) {
+                    grid[y * GRID_W + x] = None;
+                }
+            }
+        }
+
+        // One ring further out too: uncollapsed cells there had been narrowed
+        // by cells that are now open again.
+        for y in 0..GRID_H {
+            for x in 0..GRID_W {
+                if !within(x, y, radius + 1) || grid[y * GRID_W + x].is_some() {
+                    continue;
+                }
+                let domain = domain_from_neighbors(&self.rules, &grid, x, y);
+                let tile = wfc_grid.cell_mut(x, y);
+                if tile.collapsed || tile.possible != domain {
+                    self.provenance.rebuild(&self.rules, &grid, x, y);
+                    tile.possible = domain;
+                    tile.collapsed = false;
+                    commands
+                        .entity(wfc_grid.entity(x, y))
+                        .try_remove::<Contradicted>();
+                }
+            }
+        }
+
+        self.timeline.rebase(GridState::capture(wfc_grid));
+    }
+}
+
+/// Right-clicking a collapsed cell resets it, or every cell within
+/// [`NEIGHBORHOOD_RADIUS`] with `Shift`.
+#[allow(clippy::too_many_arguments)]
+fn reset_on_right_click(
+    mut commands: Commands,
+    mouse: Res<ButtonInput<MouseButton>>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut region: ResetRegion,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+    interactions: Query<&Interaction>,
+    mut grid: ResMut<WfcGrid>,
+) {
+    if !mouse.just_pressed(MouseButton::Right) {
+        return;
+    }
+    if interactions.iter().any(|i| *i != Interaction::None) {
+        return;
+    }
+    let Ok(window) = windows.single() else {
+        return;
+    };
+    let Some(cell) = cursor_world_position(window, &cameras).and_then(cell_at) else {
+        return;
+    };
+    let radius = if keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        NEIGHBORHOOD_RADIUS
+    } else {
+        0
+    };
+    region.reset(&mut commands, &mut grid, cell, radius);
+}
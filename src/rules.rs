@@ -0,0 +1,278 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::asset_files::{asset_path, list_assets, read_asset};
+use crate::{TileMaterial, TileSet, TileType};
+
+/// Directory under the asset root holding ruleset files.
+const TILESET_DIR: &str = "tilesets";
+const TILESET_EXTENSION: &str = "ron";
+
+/// Ruleset file contents: for each tile type, the types allowed next to it,
+/// and optionally how often the solver picks it relative to the others and
+/// the surface drawn over it. Saves and replays store rules the same way.
+#[derive(Serialize, Deserialize)]
+struct RulesetFile {
+    name: String,
+    adjacency: HashMap<TileType, Vec<TileType>>,
+    #[serde(default)]
+    weights: HashMap<TileType, f32>,
+    #[serde(default)]
+    materials: HashMap<TileType, TileMaterial>,
+}
+
+/// Rules as saves and replays hold them.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SavedRules {
+    Ruleset(RulesetFile),
+    Tables(RuleTables),
+}
+
+/// Rules as saves and replays held them before the dungeon tiles: tables
+/// indexed in the order of [`TileType::ALL`], which has only grown at the
+/// end since.
+#[derive(Deserialize)]
+struct RuleTables {
+    name: String,
+    allowed: Vec<Vec<bool>>,
+    weights: Vec<f32>,
+    /// Missing from replays recorded before rulesets named their surfaces.
+    #[serde(default)]
+    materials: Option<Vec<TileMaterial>>,
+}
+
+/// Which tile types may be neighbors, their weights when the solver picks
+/// among the tiles possible for a cell, and the surface drawn over each.
+/// Replaced at runtime from the files in `assets/tilesets`.
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+#[serde(into = "RulesetFile", from = "SavedRules")]
+pub struct Rules {
+    pub name: String,
+    allowed: [[bool; TileType::ALL.len()]; TileType::ALL.len()],
+    weights: [f32; TileType::ALL.len()],
+    materials: [TileMaterial; TileType::ALL.len()],
+}
+
+/// Water drawn as water and every other tile flat, as the built-in ruleset
+/// and learned samples have it.
+fn water_surfaces() -> [TileMaterial; TileType::ALL.len()] {
+    TileType::ALL.map(|tile| match tile {
+        TileType::Water => TileMaterial::Water,
+        _ => TileMaterial::Flat,
+    })
+}
+
+impl Default for Rules {
+    /// Water and grass meet only through sand.
+    fn default() -> Self {
+        let mut rules = Self {
+            name: "Coast".to_string(),
+            allowed: [[false; TileType::ALL.len()]; TileType::ALL.len()],
+            weights: [1.0; TileType::ALL.len()],
+            materials: water_surfaces(),
+        };
+        for (tile, neighbors) in [
+            (TileType::Water, &[TileType::Water, TileType::Sand][..]),
+            (
+                TileType::Sand,
+                &[TileType::Sand, TileType::Water, TileType::Grass][..],
+            ),
+            (TileType::Grass, &[TileType::Grass, TileType::Sand][..]),
+        ] {
+            for &neighbor in neighbors {
+                rules.allowed[tile.index()][neighbor.index()] = true;
+            }
+        }
+        rules
+    }
+}
+
+impl Rules {
+    /// Tiles the ruleset gives any neighbors, which are the ones its maps are
+    /// made of. Cells start out with these and the rest are never possible.
+    pub fn tiles(&self) -> TileSet {
+        TileType::ALL
+            .into_iter()
+            .filter(|tile| self.allowed[tile.index()].contains(&true))
+            .collect()
+    }
+
+    pub fn allows(&self, tile: TileType, neighbor: TileType) -> bool {
+        self.allowed[tile.index()][neighbor.index()]
+    }
+
+    pub fn weight(&self, tile: TileType) -> f32 {
+        self.weights[tile.index()]
+    }
+
+    /// Surface drawn over the tile once a cell collapses to it.
+    pub fn material(&self, tile: TileType) -> TileMaterial {
+        self.materials[tile.index()]
+    }
+
+    pub fn set_weight(&mut self, tile: TileType, weight: f32) {
+        self.weights[tile.index()] = weight.max(0.0);
+    }
+
+    /// Overrides the weights of the given tiles; the others keep theirs.
+    pub fn set_weights(&mut self, weights: &HashMap<TileType, f32>) {
+        for (&tile, &weight) in weights {
+            self.set_weight(tile, weight);
+        }
+    }
+
+    /// Learns a ruleset from a sample grid indexed by `y * width + x`: tiles
+    /// seen side by side may be neighbors, and each tile is weighted by how
+    /// often it appears, relative to the most common one.
+    pub fn from_sample(name: String, cells: &[TileType], width: usize) -> Self {
+        let mut allowed = [[false; TileType::ALL.len()]; TileType::ALL.len()];
+        let mut counts = [0usize; TileType::ALL.len()];
+        for (i, &tile) in cells.iter().enumerate() {
+            counts[tile.index()] += 1;
+            let right = (i % width + 1 < width).then(|| cells[i + 1]);
+            let below = cells.get(i + width).copied();
+            for neighbor in [right, below].into_iter().flatten() {
+                allowed[tile.index()][neighbor.index()] = true;
+                allowed[neighbor.index()][tile.index()] = true;
+            }
+        }
+
+        let most = counts.into_iter().max().unwrap_or_default().max(1);
+        Self {
+            name,
+            allowed,
+            weights: counts.map(|count| count as f32 / most as f32),
+            materials: water_surfaces(),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = read_asset(path)?;
+        let file: RulesetFile = ron::from_str(&text).map_err(|err| err.to_string())?;
+        Ok(file.into())
+    }
+}
+
+impl From<RulesetFile> for Rules {
+    fn from(file: RulesetFile) -> Self {
+        let mut allowed = [[false; TileType::ALL.len()]; TileType::ALL.len()];
+        for (tile, neighbors) in &file.adjacency {
+            for neighbor in neighbors {
+                allowed[tile.index()][neighbor.index()] = true;
+            }
+        }
+        let mut rules = Self {
+            name: file.name,
+            allowed,
+            weights: [1.0; TileType::ALL.len()],
+            materials: [TileMaterial::Flat; TileType::ALL.len()],
+        };
+        rules.set_weights(&file.weights);
+        for (tile, &material) in &file.materials {
+            rules.materials[tile.index()] = material;
+        }
+        rules
+    }
+}
+
+impl From<Rules> for RulesetFile {
+    fn from(rules: Rules) -> Self {
+        Self {
+            adjacency: rules
+                .tiles()
+                .iter()
+                .map(|tile| {
+                    let neighbors = TileType::ALL
+                        .into_iter()
+                        .filter(|&neighbor| rules.allows(tile, neighbor))
+                        .collect();
+                    (tile, neighbors)
+                })
+                .collect(),
+            weights: TileType::ALL.map(|tile| (tile, rules.weight(tile))).into(),
+            materials: TileType::ALL
+                .map(|tile| (tile, rules.material(tile)))
+                .into(),
+            name: rules.name,
+        }
+    }
+}
+
+impl From<SavedRules> for Rules {
+    fn from(saved: SavedRules) -> Self {
+        let tables = match saved {
+            SavedRules::Ruleset(file) => return file.into(),
+            SavedRules::Tables(tables) => tables,
+        };
+        let mut rules = Self {
+            name: tables.name,
+            allowed: [[false; TileType::ALL.len()]; TileType::ALL.len()],
+            weights: [1.0; TileType::ALL.len()],
+            materials: water_surfaces(),
+        };
+        for (row, neighbors) in rules.allowed.iter_mut().zip(tables.allowed) {
+            for (allowed, neighbor) in row.iter_mut().zip(neighbors) {
+                *allowed = neighbor;
+            }
+        }
+        for (weight, saved) in rules.weights.iter_mut().zip(tables.weights) {
+            *weight = saved;
+        }
+        if let Some(materials) = tables.materials {
+            rules.materials = [TileMaterial::Flat; TileType::ALL.len()];
+            for (material, saved) in rules.materials.iter_mut().zip(materials) {
+                *material = saved;
+            }
+        }
+        rules
+    }
+}
+
+/// Path of a ruleset file in `assets/tilesets`, given its file name.
+pub fn tileset_path(file_name: &str) -> PathBuf {
+    asset_path(TILESET_DIR).join(file_name)
+}
+
+/// Ruleset files in `assets/tilesets`, sorted by file name.
+pub fn list_tilesets() -> Vec<PathBuf> {
+    list_assets(TILESET_DIR, TILESET_EXTENSION)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Saved rules come back as they were, dungeon tiles included.
+    #[test]
+    fn saved_rules_round_trip() {
+        let mut rules = Rules::load(&tileset_path("dungeon.ron")).unwrap();
+        rules.set_weight(TileType::Door, 0.25);
+        let text = ron::to_string(&rules).unwrap();
+        let loaded: Rules = ron::from_str(&text).unwrap();
+        assert_eq!(loaded.name, rules.name);
+        assert_eq!(loaded.allowed, rules.allowed);
+        assert_eq!(loaded.weights, rules.weights);
+        assert_eq!(loaded.materials, rules.materials);
+    }
+
+    /// Saves and replays from when there were three tiles still load, with
+    /// the tiles added since left out of the ruleset.
+    #[test]
+    fn three_tile_tables_still_load() {
+        let text = "(
+            name: \"Coast\",
+            allowed: [[true, true, false], [true, true, false], [false, false, true]],
+            weights: [1.0, 2.0, 0.5],
+        )";
+        let rules: Rules = ron::from_str(text).unwrap();
+        assert!(rules.allows(TileType::Sand, TileType::Water));
+        assert!(!rules.allows(TileType::Water, TileType::Grass));
+        assert_eq!(rules.weight(TileType::Water), 2.0);
+        assert_eq!(rules.material(TileType::Water), TileMaterial::Water);
+        assert!(!rules.tiles().contains(TileType::Wall));
+    }
+}
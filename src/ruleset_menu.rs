@@ -0,0 +1,175 @@
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+
+use crate::rules::{Rules, list_tilesets};
+use crate::{Seed, regenerate};
+
+const MENU_LEFT: f32 = 8.0;
+/// Below the seed field.
+const MENU_TOP: f32 = 68.0;
+const MENU_FONT_SIZE: f32 = 14.0;
+const MENU_BACKGROUND: Color = Color::srgba(0.05, 0.05, 0.05, 0.8);
+const MENU_HOVERED: Color = Color::srgba(0.3, 0.3, 0.3, 0.9);
+
+/// Button showing the active ruleset; pressing it opens the list.
+#[derive(Component)]
+struct MenuHeader;
+
+#[derive(Component)]
+struct MenuHeaderText;
+
+/// Container for the open list of ruleset files.
+#[derive(Component)]
+struct MenuList;
+
+#[derive(Component)]
+struct RulesetOption(PathBuf);
+
+pub struct RulesetMenuPlugin;
+
+impl Plugin for RulesetMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_menu).add_systems(
+            Update,
+            (
+                toggle_list,
+                choose_ruleset.before(regenerate),
+                highlight_buttons,
+                update_header.run_if(resource_changed::<Rules>),
+            )
+                .chain(),
+        );
+    }
+}
+
+fn spawn_menu(mut commands: Commands) {
+    commands
+        .spawn(Node {
+            position_type: PositionType::Absolute,
+            left: Val::Px(MENU_LEFT),
+            top: Val::Px(MENU_TOP),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Start,
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn((
+                MenuHeader,
+                Button,
+                Node {
+                    padding: UiRect::axes(Val::Px(6.0), Val::Px(2.0)),
+                    ..default()
+                },
+                BackgroundColor(MENU_BACKGROUND),
+                children![(
+                    MenuHeaderText,
+                    Text::default(),
+                    TextFont::from_font_size(MENU_FONT_SIZE),
+                )],
+            ));
+        });
+}
+
+/// Opens the list, rescanning the tileset directory so new files show up,
+/// or closes it if it is already open.
+#[allow(clippy::type_complexity)]
+fn toggle_list(
+    mut commands: Commands,
+    headers: Query<(&Interaction, &ChildOf), (With<MenuHeader>, Changed<Interaction>)>,
+    lists: Query<Entity, With<MenuList>>,
+) {
+    for (interaction, child_of) in headers.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        if !lists.is_empty() {
+            for list in lists.iter() {
+                commands.entity(list).despawn();
+            }
+            continue;
+        }
+
+        let paths = list_tilesets();
+        commands
+            .spawn((
+                MenuList,
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                BackgroundColor(MENU_BACKGROUND),
+                ChildOf(child_of.parent()),
+            ))
+            .with_children(|list| {
+                for path in paths {
+                    let label = path
+                        .file_stem()
+                        .map(|stem| stem.to_string_lossy().into_owned())
+                        .unwrap_or_default();
+                    list.spawn((
+                        RulesetOption(path),
+                        Button,
+                        Node {
+                            padding: UiRect::axes(Val::Px(6.0), Val::Px(2.0)),
+                            ..default()
+                        },
+                        BackgroundColor(Color::NONE),
+                        children![(Text::new(label), TextFont::from_font_size(MENU_FONT_SIZE))],
+                    ));
+                }
+            });
+    }
+}
+
+/// Loads the chosen file and regenerates the map with the same seed, so the
+/// effect of the rules is easy to compare.
+fn choose_ruleset(
+    mut commands: Commands,
+    options: Query<(&Interaction, &RulesetOption), Changed<Interaction>>,
+    lists: Query<Entity, With<MenuList>>,
+    mut rules: ResMut<Rules>,
+    mut seed: ResMut<Seed>,
+) {
+    for (interaction, option) in options.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match Rules::load(&option.0) {
+            Ok(loaded) => {
+                info!("rules: {}", loaded.name);
+                *rules = loaded;
+                seed.set_changed();
+            }
+            Err(err) => warn!("could not load {}: {err}", option.0.display()),
+        }
+        for list in lists.iter() {
+            commands.entity(list).despawn();
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn highlight_buttons(
+    mut buttons: Query<
+        (&Interaction, &mut BackgroundColor, Has<MenuHeader>),
+        (
+            Or<(With<MenuHeader>, With<RulesetOption>)>,
+            Changed<Interaction>,
+        ),
+    >,
+) {
+    for (interaction, mut background, header) in buttons.iter_mut() {
+        let idle = if header { MENU_BACKGROUND } else { Color::NONE };
+        background.0 = match interaction {
+            Interaction::None => idle,
+            Interaction::Hovered | Interaction::Pressed => MENU_HOVERED,
+        };
+    }
+}
+
+fn update_header(rules: Res<Rules>, mut texts: Query<&mut Text, With<MenuHeaderText>>) {
+    for mut text in texts.iter_mut() {
+        text.0 = format!("rules: {} \u{25be}", rules.name);
+    }
+}
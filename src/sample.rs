@@ -0,0 +1,57 @@
+use std::path::Path;
+
+use bevy::asset::RenderAssetUsages;
+use bevy::image::{CompressedImageFormats, ImageSampler, ImageType};
+use bevy::prelude::*;
+
+use crate::TileType;
+use crate::rules::Rules;
+use crate::theme::Palette;
+
+/// The tile whose color in `palette` is closest to `color`.
+pub fn nearest_tile(palette: &Palette, color: Color) -> TileType {
+    let color = color.to_srgba();
+    let distance = |tile: TileType| {
+        let tile = palette.color(tile).to_srgba();
+        (tile.red - color.red).powi(2)
+            + (tile.green - color.green).powi(2)
+            + (tile.blue - color.blue).powi(2)
+    };
+    TileType::ALL
+        .into_iter()
+        .min_by(|&a, &b| distance(a).total_cmp(&distance(b)))
+        .unwrap()
+}
+
+/// Decodes a PNG file, keeping its pixels on the CPU for sampling.
+pub fn read_png(path: &Path) -> Result<Image, String> {
+    let bytes = std::fs::read(path).map_err(|err| err.to_string())?;
+    Image::from_buffer(
+        &bytes,
+        ImageType::Extension("png"),
+        CompressedImageFormats::NONE,
+        true,
+        ImageSampler::Default,
+        RenderAssetUsages::default(),
+    )
+    .map_err(|err| err.to_string())
+}
+
+/// Reads a sample image, snapping every pixel to the tile of the nearest
+/// palette color, and learns a ruleset from the resulting grid.
+pub fn learn_rules(path: &Path, palette: &Palette) -> Result<Rules, String> {
+    let image = read_png(path)?;
+    let (width, height) = (image.width(), image.height());
+    let mut cells = Vec::with_capacity((width * height) as usize);
+    for y in 0..height {
+        for x in 0..width {
+            let color = image.get_color_at(x, y).map_err(|err| err.to_string())?;
+            cells.push(nearest_tile(palette, color));
+        }
+    }
+    let name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    Ok(Rules::from_sample(name, &cells, width as usize))
+}
@@ -0,0 +1,379 @@
+use std::path::{Path, PathBuf};
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::asset_files::{exe_relative_dir, unix_millis, write_creating_dir};
+use crate::cli::Cli;
+use crate::history::GridState;
+use crate::inspector::Provenance;
+use crate::lock::Locks;
+use crate::rules::Rules;
+use crate::strategy::Strategy;
+use crate::timeline::Timeline;
+use crate::{GRID_H, GRID_W, Seed, WfcGrid, collapse_step, regenerate, setup};
+
+const SAVES_DIR: &str = "saves";
+const SAVE_EXTENSION: &str = "ron";
+const MENU_LEFT: f32 = 8.0;
+/// Below the ruleset dropdown.
+const MENU_TOP: f32 = 96.0;
+const MENU_FONT_SIZE: f32 = 14.0;
+const MENU_BACKGROUND: Color = Color::srgba(0.05, 0.05, 0.05, 0.8);
+const MENU_HOVERED: Color = Color::srgba(0.3, 0.3, 0.3, 0.9);
+
+/// Contents of a save file. The configuration fields are missing from older
+/// saves, which load with the current configuration instead.
+#[derive(Serialize, Deserialize)]
+struct SavedGrid {
+    seed: u64,
+    /// Name of the ruleset the grid was generated with, for reference.
+    rules: String,
+    width: usize,
+    height: usize,
+    grid: GridState,
+    /// The full ruleset, including weights changed at runtime.
+    #[serde(default)]
+    ruleset: Option<Rules>,
+    #[serde(default)]
+    strategy: Option<Strategy>,
+    #[serde(default)]
+    locks: Option<Locks>,
+}
+
+impl SavedGrid {
+    fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+        let saved: Self = ron::from_str(&text).map_err(|err| err.to_string())?;
+        if (saved.width, saved.height) != (GRID_W, GRID_H) {
+            return Err(format!(
+                "saved grid is {}x{}, expected {GRID_W}x{GRID_H}",
+                saved.width, saved.height
+            ));
+        }
+        Ok(saved)
+    }
+}
+
+/// Loaded grid waiting for the regeneration its seed triggers, applied once
+/// the fresh tiles exist.
+#[derive(Resource, Default)]
+struct PendingLoad(Option<GridState>);
+
+/// Everything a loaded save replaces.
+#[derive(SystemParam)]
+struct SaveLoader<'w> {
+    rules: ResMut<'w, Rules>,
+    strategy: ResMut<'w, Strategy>,
+    locks: ResMut<'w, Locks>,
+    seed: ResMut<'w, Seed>,
+    pending: ResMut<'w, PendingLoad>,
+}
+
+impl SaveLoader<'_> {
+    /// Applies the saved configuration and regenerates with the saved seed,
+    /// so per-cell variation matches, then queues the saved grid to replace
+    /// the fresh one. Cells the save left uncollapsed are solved from the
+    /// seed again rather than continuing the saved solver's random stream.
+    fn apply(&mut self, saved: SavedGrid) {
+        match saved.ruleset {
+            Some(rules) => *self.rules = rules,
+            None if saved.rules != self.rules.name => warn!(
+                "grid was saved with rules {}, current rules are {}",
+                saved.rules, self.rules.name
+            ),
+            None => {}
+        }
+        if let Some(strategy) = saved.strategy {
+            *self.strategy = strategy;
+        }
+        if let Some(locks) = saved.locks {
+            *self.locks = locks;
+        }
+        self.seed.0 = saved.seed;
+        self.seed.set_changed();
+        self.pending.0 = Some(saved.grid);
+    }
+}
+
+#[derive(Component)]
+struct SaveButton;
+
+/// Button that opens the list of saves.
+#[derive(Component)]
+struct LoadButton;
+
+/// Container for the open list of save files.
+#[derive(Component)]
+struct SaveList;
+
+#[derive(Component)]
+struct SaveOption(PathBuf);
+
+pub struct SavesPlugin;
+
+impl Plugin for SavesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PendingLoad>()
+            .add_systems(Startup, (spawn_buttons, load_on_startup.before(setup)))
+            .add_systems(
+                Update,
+                (
+                    save_grid,
+                    toggle_list,
+                    choose_save.before(regenerate),
+                    highlight_buttons,
+                )
+                    .chain(),
+            )
+            .add_systems(
+                Update,
+                apply_pending_load
+                    .run_if(|pending: Res<PendingLoad>| pending.0.is_some())
+                    .after(regenerate)
+                    .before(collapse_step),
+            );
+    }
+}
+
+/// `saves/` next to the executable.
+fn saves_dir() -> PathBuf {
+    exe_relative_dir(SAVES_DIR)
+}
+
+/// Save files, newest first since their names are timestamps.
+fn list_saves() -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(saves_dir()) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == SAVE_EXTENSION))
+        .collect();
+    paths.sort();
+    paths.reverse();
+    paths
+}
+
+fn spawn_buttons(mut commands: Commands) {
+    commands
+        .spawn(Node {
+            position_type: PositionType::Absolute,
+            left: Val::Px(MENU_LEFT),
+            top: Val::Px(MENU_TOP),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Start,
+            ..default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn(Node {
+                    column_gap: Val::Px(4.0),
+                    ..default()
+                })
+                .with_children(|row| {
+                    row.spawn((
+                        SaveButton,
+                        Button,
+                        Node {
+                            padding: UiRect::axes(Val::Px(6.0), Val::Px(2.0)),
+                            ..default()
+                        },
+                        BackgroundColor(MENU_BACKGROUND),
+                        children![(Text::new("Save"), TextFont::from_font_size(MENU_FONT_SIZE))],
+                    ));
+                    row.spawn((
+                        LoadButton,
+                        Button,
+                        Node {
+                            padding: UiRect::axes(Val::Px(6.0), Val::Px(2.0)),
+                            ..default()
+                        },
+                        BackgroundColor(MENU_BACKGROUND),
+                        children![(
+                            Text::new("Load \u{25be}"),
+                            TextFont::from_font_size(MENU_FONT_SIZE),
+                        )],
+                    ));
+                });
+        });
+}
+
+/// Writes the current grid and configuration to
+/// `saves/grid-<unix millis>.ron`.
+fn save_grid(
+    buttons: Query<&Interaction, (With<SaveButton>, Changed<Interaction>)>,
+    seed: Res<Seed>,
+    rules: Res<Rules>,
+    strategy: Res<Strategy>,
+    locks: Res<Locks>,
+    wfc_grid: Res<WfcGrid>,
+) {
+    if !buttons.iter().any(|i| *i == Interaction::Pressed) {
+        return;
+    }
+    let saved = SavedGrid {
+        seed: seed.0,
+        rules: rules.name.clone(),
+        width: GRID_W,
+        height: GRID_H,
+        grid: GridState::capture(&wfc_grid),
+        ruleset: Some(rules.clone()),
+        strategy: Some(*strategy),
+        locks: Some(locks.clone()),
+    };
+    let path = saves_dir().join(format!("grid-{}.{SAVE_EXTENSION}", unix_millis()));
+
+    let result = ron::ser::to_string_pretty(&saved, ron::ser::PrettyConfig::default())
+        .map_err(|err| err.to_string())
+        .and_then(|text| write_creating_dir(&path, &text));
+    match result {
+        Ok(()) => info!("saved grid to {}", path.display()),
+        Err(err) => warn!("could not save {}: {err}", path.display()),
+    }
+}
+
+/// Opens the list, rescanning the saves directory, or closes it if it is
+/// already open.
+#[allow(clippy::type_complexity)]
+fn toggle_list(
+    mut commands: Commands,
+    buttons: Query<(&Interaction, &ChildOf), (With<LoadButton>, Changed<Interaction>)>,
+    rows: Query<&ChildOf>,
+    lists: Query<Entity, With<SaveList>>,
+) {
+    for (interaction, child_of) in buttons.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        if !lists.is_empty() {
+            for list in lists.iter() {
+                commands.entity(list).despawn();
+            }
+            continue;
+        }
+        // The button sits in a row; the list goes below that row.
+        let Ok(row) = rows.get(child_of.parent()) else {
+            continue;
+        };
+
+        let paths = list_saves();
+        commands
+            .spawn((
+                SaveList,
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                BackgroundColor(MENU_BACKGROUND),
+                ChildOf(row.parent()),
+            ))
+            .with_children(|list| {
+                if paths.is_empty() {
+                    list.spawn((
+                        Node {
+                            padding: UiRect::axes(Val::Px(6.0), Val::Px(2.0)),
+                            ..default()
+                        },
+                        children![(
+                            Text::new("no saves yet"),
+                            TextFont::from_font_size(MENU_FONT_SIZE),
+                        )],
+                    ));
+                }
+                for path in paths {
+                    let label = path
+                        .file_stem()
+                        .map(|stem| stem.to_string_lossy().into_owned())
+                        .unwrap_or_default();
+                    list.spawn((
+                        SaveOption(path),
+                        Button,
+                        Node {
+                            padding: UiRect::axes(Val::Px(6.0), Val::Px(2.0)),
+                            ..default()
+                        },
+                        BackgroundColor(Color::NONE),
+                        children![(Text::new(label), TextFont::from_font_size(MENU_FONT_SIZE))],
+                    ));
+                }
+            });
+    }
+}
+
+/// Loads a save given as the positional command-line argument, e.g.
+/// `target/debug/saves/grid-123.ron` as written by the Save button under
+/// `cargo run`. Runs before the first grid is set up so that grid already
+/// uses the saved configuration, overriding the options.
+fn load_on_startup(cli: Res<Cli>, mut loader: SaveLoader) {
+    let Some(path) = &cli.save else {
+        return;
+    };
+    match SavedGrid::load(path) {
+        Ok(saved) => {
+            info!("loading grid from {}", path.display());
+            loader.apply(saved);
+        }
+        Err(err) => warn!("could not load {}: {err}", path.display()),
+    }
+}
+
+fn choose_save(
+    mut commands: Commands,
+    options: Query<(&Interaction, &SaveOption), Changed<Interaction>>,
+    lists: Query<Entity, With<SaveList>>,
+    mut loader: SaveLoader,
+) {
+    for (interaction, option) in options.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match SavedGrid::load(&option.0) {
+            Ok(saved) => {
+                info!("loading grid from {}", option.0.display());
+                loader.apply(saved);
+            }
+            Err(err) => warn!("could not load {}: {err}", option.0.display()),
+        }
+        for list in lists.iter() {
+            commands.entity(list).despawn();
+        }
+    }
+}
+
+/// Writes the loaded grid over the fresh tiles. Like any new map, the
+/// timeline starts from it.
+fn apply_pending_load(
+    mut commands: Commands,
+    rules: Res<Rules>,
+    mut pending: ResMut<PendingLoad>,
+    mut provenance: ResMut<Provenance>,
+    mut timeline: ResMut<Timeline>,
+    mut wfc_grid: ResMut<WfcGrid>,
+) {
+    let Some(grid) = pending.0.take() else {
+        return;
+    };
+    grid.restore(&mut commands, &rules, &mut provenance, &mut wfc_grid);
+    timeline.rebase(grid);
+}
+
+#[allow(clippy::type_complexity)]
+fn highlight_buttons(
+    mut buttons: Query<
+        (&Interaction, &mut BackgroundColor, Has<SaveOption>),
+        (
+            Or<(With<SaveButton>, With<LoadButton>, With<SaveOption>)>,
+            Changed<Interaction>,
+        ),
+    >,
+) {
+    for (interaction, mut background, option) in buttons.iter_mut() {
+        let idle = if option { Color::NONE } else { MENU_BACKGROUND };
+        background.0 = match interaction {
+            Interaction::None => idle,
+            Interaction::Hovered | Interaction::Pressed => MENU_HOVERED,
+        };
+    }
+}
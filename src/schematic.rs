@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+use std::io::Write;
+
+use bevy::prelude::*;
+use flate2::Compression;
+use flate2::write::GzEncoder;
+
+use crate::export::export_stem;
+use crate::keybindings::{Action, Keybindings};
+use crate::{GRID_H, GRID_W, TileType, WfcGrid};
+
+/// Sponge schematic format version, read by WorldEdit and most other tools.
+const SCHEMATIC_VERSION: i32 = 2;
+/// Minecraft 1.20.4. Newer versions upgrade the blocks on load.
+const DATA_VERSION: i32 = 3700;
+const AIR: &str = "minecraft:air";
+
+// NBT tag ids.
+const TAG_END: u8 = 0;
+const TAG_SHORT: u8 = 2;
+const TAG_INT: u8 = 3;
+const TAG_BYTE_ARRAY: u8 = 7;
+const TAG_COMPOUND: u8 = 10;
+
+pub struct SchematicPlugin;
+
+impl Plugin for SchematicPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, export_schematic);
+    }
+}
+
+/// Blocks stacked for a tile, bottom first. Column heights follow the 3D
+/// view: water lowest and grass highest, or floors low and walls high.
+fn block_column(tile: TileType) -> &'static [&'static str] {
+    match tile {
+        TileType::Water => &["minecraft:water"],
+        TileType::Sand => &["minecraft:sandstone", "minecraft:sand"],
+        TileType::Grass => &[
+            "minecraft:dirt",
+            "minecraft:dirt",
+            "minecraft:dirt",
+            "minecraft:grass_block",
+        ],
+        TileType::Floor => &["minecraft:stone", "minecraft:oak_planks"],
+        TileType::Corridor => &["minecraft:stone", "minecraft:cobblestone"],
+        TileType::Door => &["minecraft:stone", "minecraft:spruce_planks"],
+        TileType::StairsDown => &["minecraft:stone", "minecraft:ladder"],
+        TileType::StairsUp => &["minecraft:stone", "minecraft:oak_stairs"],
+        TileType::Wall => &[
+            "minecraft:stone",
+            "minecraft:stone_bricks",
+            "minecraft:stone_bricks",
+            "minecraft:stone_bricks",
+            "minecraft:stone_bricks",
+        ],
+    }
+}
+
+/// Big-endian NBT writer for the few tag types a schematic needs.
+#[derive(Default)]
+struct Nbt(Vec<u8>);
+
+impl Nbt {
+    fn name(&mut self, tag: u8, name: &str) {
+        self.0.push(tag);
+        self.0.extend((name.len() as u16).to_be_bytes());
+        self.0.extend(name.as_bytes());
+    }
+
+    fn short(&mut self, name: &str, value: i16) {
+        self.name(TAG_SHORT, name);
+        self.0.extend(value.to_be_bytes());
+    }
+
+    fn int(&mut self, name: &str, value: i32) {
+        self.name(TAG_INT, name);
+        self.0.extend(value.to_be_bytes());
+    }
+
+    fn byte_array(&mut self, name: &str, bytes: &[u8]) {
+        self.name(TAG_BYTE_ARRAY, name);
+        self.0.extend((bytes.len() as i32).to_be_bytes());
+        self.0.extend(bytes);
+    }
+
+    fn begin_compound(&mut self, name: &str) {
+        self.name(TAG_COMPOUND, name);
+    }
+
+    fn end_compound(&mut self) {
+        self.0.push(TAG_END);
+    }
+}
+
+/// Appends `value` as an unsigned LEB128 varint, as schematic block data
+/// stores palette indices.
+fn push_varint(bytes: &mut Vec<u8>, mut value: u32) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(byte);
+            return;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+/// Builds the gzipped schematic: a column of blocks per collapsed cell, with
+/// grid rows running north to south. Cells not collapsed yet stay air.
+fn schematic(grid: &[Option<TileType>]) -> Result<Vec<u8>, String> {
+    let height = TileType::ALL
+        .iter()
+        .map(|&tile| block_column(tile).len())
+        .max()
+        .unwrap_or_default();
+    let mut palette: HashMap<&str, u32> = HashMap::from([(AIR, 0)]);
+    let mut block_data = Vec::new();
+    // Index order is x, then z, then y.
+    for level in 0..height {
+        for z in 0..GRID_H {
+            // Minecraft's z grows towards the south, grid rows towards the north.
+            let y = GRID_H - 1 - z;
+            for x in 0..GRID_W {
+                let block = grid[y * GRID_W + x]
+                    .and_then(|tile| block_column(tile).get(level).copied())
+                    .unwrap_or(AIR);
+                let next = palette.len() as u32;
+                let id = *palette.entry(block).or_insert(next);
+                push_varint(&mut block_data, id);
+            }
+        }
+    }
+
+    let mut nbt = Nbt::default();
+    nbt.begin_compound("Schematic");
+    nbt.int("Version", SCHEMATIC_VERSION);
+    nbt.int("DataVersion", DATA_VERSION);
+    nbt.short("Width", GRID_W as i16);
+    nbt.short("Height", height as i16);
+    nbt.short("Length", GRID_H as i16);
+    nbt.int("PaletteMax", palette.len() as i32);
+    nbt.begin_compound("Palette");
+    for (block, id) in &palette {
+        nbt.int(block, *id as i32);
+    }
+    nbt.end_compound();
+    nbt.byte_array("BlockData", &block_data);
+    nbt.end_compound();
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&nbt.0).map_err(|err| err.to_string())?;
+    encoder.finish().map_err(|err| err.to_string())
+}
+
+/// Writes the map as a Sponge schematic, `map-<unix millis>.schem`, to paste
+/// into Minecraft worlds with WorldEdit.
+fn export_schematic(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keybindings: Res<Keybindings>,
+    grid: Res<WfcGrid>,
+) {
+    if !keybindings.just_pressed(Action::ExportSchematic, &keyboard_input) {
+        return;
+    }
+    let path = export_stem("map").with_extension("schem");
+    let result = schematic(&grid.collapsed_grid())
+        .and_then(|bytes| std::fs::write(&path, bytes).map_err(|err| err.to_string()));
+    match result {
+        Ok(()) => info!("exported schematic to {}", path.display()),
+        Err(err) => warn!("could not export {}: {err}", path.display()),
+    }
+}
@@ -0,0 +1,36 @@
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use bevy::render::view::screenshot::{Screenshot, save_to_disk};
+
+use crate::asset_files::{exe_relative_dir, unix_millis};
+use crate::keybindings::{Action, Keybindings};
+
+pub struct ScreenshotPlugin;
+
+impl Plugin for ScreenshotPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, take_screenshot);
+    }
+}
+
+/// `wfc-<unix millis>.png` in the executable's directory, falling back to the
+/// working directory if that can't be determined.
+fn screenshot_path() -> PathBuf {
+    exe_relative_dir(&format!("wfc-{}.png", unix_millis()))
+}
+
+/// Captures the current frame to a PNG next to the executable.
+fn take_screenshot(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keybindings: Res<Keybindings>,
+) {
+    if keybindings.just_pressed(Action::Screenshot, &keyboard_input) {
+        let path = screenshot_path();
+        info!("saving screenshot to {}", path.display());
+        commands
+            .spawn(Screenshot::primary_window())
+            .observe(save_to_disk(path));
+    }
+}
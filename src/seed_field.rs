@@ -0,0 +1,229 @@
+use bevy::input::ButtonState;
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::prelude::*;
+
+use crate::keybindings::{Action, Keybindings};
+use crate::{Seed, TileType, WfcGrid, regenerate};
+
+const FIELD_LEFT: f32 = 8.0;
+/// Below the tile palette.
+const FIELD_TOP: f32 = 40.0;
+const FIELD_FONT_SIZE: f32 = 14.0;
+const FIELD_BACKGROUND: Color = Color::srgba(0.05, 0.05, 0.05, 0.8);
+const FIELD_EDITING_BACKGROUND: Color = Color::srgba(0.2, 0.2, 0.3, 0.9);
+const BUTTON_BACKGROUND: Color = Color::srgba(0.15, 0.15, 0.15, 0.8);
+const BUTTON_HOVERED: Color = Color::srgba(0.3, 0.3, 0.3, 0.9);
+/// Digits in `u64::MAX`.
+const MAX_DIGITS: usize = 20;
+
+/// Text typed into the seed field. While editing, digit keys go to the field
+/// instead of their usual bindings.
+#[derive(Resource, Default)]
+pub struct SeedEntry {
+    pub editing: bool,
+    text: String,
+}
+
+#[derive(Component)]
+struct SeedField;
+
+#[derive(Component)]
+struct SeedText;
+
+#[derive(Component)]
+struct CopySeedButton;
+
+pub struct SeedFieldPlugin;
+
+impl Plugin for SeedFieldPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SeedEntry>()
+            .add_systems(Startup, spawn_seed_field)
+            .add_systems(
+                Update,
+                (
+                    click_seed_field,
+                    type_seed.before(regenerate),
+                    copy_seed,
+                    update_seed_field,
+                )
+                    .chain(),
+            );
+    }
+}
+
+fn spawn_seed_field(mut commands: Commands) {
+    commands
+        .spawn(Node {
+            position_type: PositionType::Absolute,
+            left: Val::Px(FIELD_LEFT),
+            top: Val::Px(FIELD_TOP),
+            column_gap: Val::Px(4.0),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn((
+                SeedField,
+                Button,
+                Node {
+                    padding: UiRect::axes(Val::Px(6.0), Val::Px(2.0)),
+                    ..default()
+                },
+                BackgroundColor(FIELD_BACKGROUND),
+                children![(
+                    SeedText,
+                    Text::default(),
+                    TextFont::from_font_size(FIELD_FONT_SIZE),
+                )],
+            ));
+            parent.spawn((
+                CopySeedButton,
+                Button,
+                Node {
+                    padding: UiRect::axes(Val::Px(6.0), Val::Px(2.0)),
+                    ..default()
+                },
+                BackgroundColor(BUTTON_BACKGROUND),
+                children![(Text::new("Copy"), TextFont::from_font_size(FIELD_FONT_SIZE))],
+            ));
+        });
+}
+
+/// Clicking the field starts editing; clicking anywhere else cancels it.
+fn click_seed_field(
+    mouse: Res<ButtonInput<MouseButton>>,
+    seed: Res<Seed>,
+    mut entry: ResMut<SeedEntry>,
+    fields: Query<&Interaction, With<SeedField>>,
+) {
+    if !mouse.just_pressed(MouseButton::Left) {
+        return;
+    }
+    let clicked = fields.iter().any(|i| *i == Interaction::Pressed);
+    if clicked && !entry.editing {
+        entry.editing = true;
+        entry.text = seed.0.to_string();
+    } else if !clicked && entry.editing {
+        entry.editing = false;
+    }
+}
+
+/// Digits and `Backspace` edit the seed, `Enter` regenerates the map with
+/// it and `Escape` cancels.
+fn type_seed(
+    mut keys: MessageReader<KeyboardInput>,
+    mut entry: ResMut<SeedEntry>,
+    mut seed: ResMut<Seed>,
+) {
+    for key in keys.read() {
+        if !entry.editing || key.state != ButtonState::Pressed {
+            continue;
+        }
+        match &key.logical_key {
+            Key::Enter => {
+                entry.editing = false;
+                match entry.text.parse() {
+                    Ok(value) => seed.0 = value,
+                    Err(err) => warn!("invalid seed {:?}: {err}", entry.text),
+                }
+            }
+            Key::Escape => entry.editing = false,
+            Key::Backspace => {
+                entry.text.pop();
+            }
+            _ => {
+                if let Some(text) = &key.text {
+                    for c in text.chars().filter(char::is_ascii_digit) {
+                        if entry.text.len() < MAX_DIGITS {
+                            entry.text.push(c);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Short hash of the collapsed tiles, computed the same way on every
+/// machine, so two reports can tell whether they show the same map.
+pub fn map_hash(grid: &[Option<TileType>]) -> u32 {
+    // 32-bit FNV-1a over one byte per cell, 0 for cells not collapsed yet.
+    grid.iter().fold(0x811c_9dc5, |hash, tile| {
+        let byte = tile.map_or(0, |tile| tile.index() as u32 + 1);
+        (hash ^ byte).wrapping_mul(0x0100_0193)
+    })
+}
+
+/// The copy button and its binding copy the seed along with the map's hash,
+/// e.g. `seed 123 map 9f3a01c2`.
+#[allow(clippy::type_complexity)]
+fn copy_seed(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keybindings: Res<Keybindings>,
+    seed: Res<Seed>,
+    mut buttons: Query<
+        (&Interaction, &mut BackgroundColor),
+        (With<CopySeedButton>, Changed<Interaction>),
+    >,
+    grid: Res<WfcGrid>,
+) {
+    let mut pressed = keybindings.just_pressed(Action::CopySeed, &keyboard_input);
+    for (interaction, mut background) in buttons.iter_mut() {
+        background.0 = match interaction {
+            Interaction::None => BUTTON_BACKGROUND,
+            Interaction::Hovered | Interaction::Pressed => BUTTON_HOVERED,
+        };
+        pressed |= *interaction == Interaction::Pressed;
+    }
+    if !pressed {
+        return;
+    }
+
+    let grid = grid.collapsed_grid();
+    let mut text = format!("seed {} map {:08x}", seed.0, map_hash(&grid));
+    if grid.contains(&None) {
+        text += " (unfinished)";
+    }
+    match copy_text(&text) {
+        Ok(()) => info!("copied {text}"),
+        Err(err) => warn!("could not copy {text}: {err}"),
+    }
+}
+
+/// Puts `text` on the system clipboard.
+#[cfg(not(target_arch = "wasm32"))]
+fn copy_text(text: &str) -> Result<(), String> {
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.set_text(text))
+        .map_err(|err| err.to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn copy_text(_text: &str) -> Result<(), String> {
+    Err("no clipboard access in the web build".to_string())
+}
+
+fn update_seed_field(
+    seed: Res<Seed>,
+    entry: Res<SeedEntry>,
+    mut fields: Query<&mut BackgroundColor, With<SeedField>>,
+    mut texts: Query<&mut Text, With<SeedText>>,
+) {
+    if !seed.is_changed() && !entry.is_changed() {
+        return;
+    }
+    for mut background in fields.iter_mut() {
+        background.0 = if entry.editing {
+            FIELD_EDITING_BACKGROUND
+        } else {
+            FIELD_BACKGROUND
+        };
+    }
+    for mut text in texts.iter_mut() {
+        text.0 = if entry.editing {
+            format!("seed: {}_", entry.text)
+        } else {
+            format!("seed: {}", seed.0)
+        };
+    }
+}
@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+
+use bevy::prelude::*;
+
+use clap::ValueEnum;
+
+use crate::export::{map_json, map_png};
+use crate::headless::{HeadlessSolver, MAX_STEPS};
+use crate::lock::Locks;
+use crate::rules::{Rules, tileset_path};
+use crate::strategy::{Heuristic, Propagation, Strategy};
+use crate::theme::Theme;
+use crate::{GRID_H, GRID_W};
+
+/// A response status, content type and body.
+type Response = (&'static str, &'static str, Vec<u8>);
+
+/// Answers `GET /generate` requests until the process is stopped, one at a
+/// time and without opening a window. Query parameters:
+///
+/// - `seed`: defaults to a random one
+/// - `tileset`: file name in `assets/tilesets`, defaults to the built-in rules
+/// - `heuristic`: `min-remaining`, `entropy`, `scanline` or `random`
+/// - `propagation`: `naive` or `ac4`
+/// - `backtracking`: `true` or `false`
+/// - `width`, `height`: only the app's fixed grid size is accepted
+/// - `format`: `json` (the `Ctrl+J` export) or `png`
+///
+/// Listens only on the loopback interface unless `public` is set.
+pub fn serve(port: u16, public: bool) {
+    let host = if public { "0.0.0.0" } else { "127.0.0.1" };
+    let listener = match TcpListener::bind((host, port)) {
+        Ok(listener) => listener,
+        Err(err) => {
+            eprintln!("could not listen on port {port}: {err}");
+            return;
+        }
+    };
+    if public {
+        println!("serving maps on port {port} of every interface at /generate");
+    } else {
+        println!("serving maps on http://localhost:{port}/generate");
+    }
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                if let Err(err) = handle(stream) {
+                    eprintln!("request failed: {err}");
+                }
+            }
+            Err(err) => eprintln!("connection failed: {err}"),
+        }
+    }
+}
+
+fn handle(mut stream: TcpStream) -> std::io::Result<()> {
+    let mut request_line = String::new();
+    let mut reader = BufReader::new(&stream);
+    reader.read_line(&mut request_line)?;
+    // Skip the headers; requests carry everything in the query.
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let (status, content_type, body) = match request_line.split_whitespace().collect::<Vec<_>>()[..]
+    {
+        ["GET", target, _] => route(target),
+        _ => error("405 Method Not Allowed", "only GET is supported"),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nAccess-Control-Allow-Origin: *\r\nConnection: close\r\n\r\n",
+        body.len()
+    )?;
+    stream.write_all(&body)
+}
+
+fn error(status: &'static str, message: &str) -> Response {
+    (status, "text/plain", format!("{message}\n").into_bytes())
+}
+
+fn route(target: &str) -> Response {
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    if path != "/generate" {
+        return error("404 Not Found", "try /generate?seed=1&format=png");
+    }
+    let params: Result<HashMap<String, String>, String> = query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(key, value)| -> Result<_, String> { Ok((decode(key)?, decode(value)?)) })
+        .collect();
+    match params.and_then(|params| generate(&params)) {
+        Ok(response) => response,
+        Err(message) => error("400 Bad Request", &message),
+    }
+}
+
+/// Decodes a query string key or value: `%XX` escapes, and `+` for spaces.
+fn decode(text: &str) -> Result<String, String> {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' => {
+                let byte = bytes
+                    .get(i + 1..i + 3)
+                    .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+                    .and_then(|hex| std::str::from_utf8(hex).ok())
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                    .ok_or_else(|| format!("invalid escape in {text}"))?;
+                decoded.push(byte);
+                i += 2;
+            }
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8(decoded).map_err(|_| format!("{text} doesn't decode to UTF-8"))
+}
+
+fn generate(params: &HashMap<String, String>) -> Result<Response, String> {
+    let seed = match params.get("seed") {
+        Some(seed) => seed.parse().map_err(|_| format!("invalid seed {seed}"))?,
+        None => rand::random(),
+    };
+    for (param, size) in [("width", GRID_W), ("height", GRID_H)] {
+        if params
+            .get(param)
+            .is_some_and(|value| *value != size.to_string())
+        {
+            return Err(format!("the grid is fixed at {GRID_W}x{GRID_H}"));
+        }
+    }
+    let rules = match params.get("tileset") {
+        Some(name) if name.contains(['/', '\\']) || name.starts_with('.') => {
+            return Err(format!("invalid tileset {name}"));
+        }
+        Some(name) => Rules::load(&tileset_path(name))?,
+        None => Rules::default(),
+    };
+    let mut strategy = Strategy::default();
+    if let Some(heuristic) = params.get("heuristic") {
+        strategy.heuristic = Heuristic::from_str(heuristic, true)
+            .map_err(|_| format!("unknown heuristic {heuristic}"))?;
+    }
+    if let Some(propagation) = params.get("propagation") {
+        strategy.propagation = Propagation::from_str(propagation, true)
+            .map_err(|_| format!("unknown propagation {propagation}"))?;
+    }
+    if let Some(backtracking) = params.get("backtracking") {
+        strategy.backtracking = backtracking
+            .parse()
+            .map_err(|_| format!("invalid backtracking {backtracking}"))?;
+    }
+
+    let mut solver = HeadlessSolver::new(&rules, &strategy, &Locks::default(), seed);
+    solver.run(MAX_STEPS);
+    let grid = solver.grid();
+    let theme = Theme::default();
+    match params.get("format").map_or("json", String::as_str) {
+        "json" => Ok((
+            "200 OK",
+            "application/json",
+            map_json(seed, &rules.name, &theme, &grid)?.into_bytes(),
+        )),
+        "png" => Ok(("200 OK", "image/png", map_png(&theme, &grid)?)),
+        format => Err(format!("unknown format {format}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_values_are_percent_decoded() {
+        assert_eq!(decode("my%20set.ron").unwrap(), "my set.ron");
+        assert_eq!(decode("a+b%2Fc").unwrap(), "a b/c");
+        assert_eq!(decode("caf%C3%A9").unwrap(), "café");
+        assert!(decode("100%").is_err());
+        assert!(decode("%zz").is_err());
+    }
+}
@@ -0,0 +1,180 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use crate::events::SpawnPointChosen;
+use crate::generation::GenerationState;
+use crate::variation::cell_hash;
+use crate::{
+    GRID_H, GRID_W, Seed, TILE_SIZE, TileType, WfcGrid, cell_center, layers, neighbor_coords,
+};
+
+/// Keeps the order spawn points are tried in independent of other rolls.
+const SPAWN_SALT: u64 = 0x7370_6177_6e70_7473;
+/// Fewest steps between neighbors from a spawn point to the nearest water.
+const MIN_WATER_DISTANCE: usize = 5;
+/// Fewest steps between neighbors from one spawn point to another.
+const MIN_SPACING: usize = 8;
+const MARKER_COLOR: Color = Color::srgb(0.4, 0.2, 0.85);
+const MARKER_SIZE: f32 = TILE_SIZE * 0.4;
+
+/// A cell of a finished map picked for gameplay to spawn enemies or points of
+/// interest at: grass, away from water and from the other spawn points.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct SpawnPoint {
+    pub x: usize,
+    pub y: usize,
+}
+
+pub struct SpawnPointPlugin;
+
+impl Plugin for SpawnPointPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GenerationState::Complete), place_spawn_points)
+            .add_systems(OnExit(GenerationState::Complete), clear_spawn_points);
+    }
+}
+
+/// Spawn points of a map, tried in an order rolled from the seed so the same
+/// map always gets the same ones, each kept if it's far enough from those
+/// kept before it.
+fn choose_spawn_points(tiles: &[Option<TileType>], seed: u64) -> Vec<(usize, usize)> {
+    let water = water_distances(tiles);
+    let mut candidates: Vec<(usize, usize)> = (0..GRID_H)
+        .flat_map(|y| (0..GRID_W).map(move |x| (x, y)))
+        .filter(|&(x, y)| {
+            let i = y * GRID_W + x;
+            tiles[i] == Some(TileType::Grass) && water[i] >= MIN_WATER_DISTANCE
+        })
+        .collect();
+    candidates.sort_by_key(|&(x, y)| cell_hash(seed, SPAWN_SALT, x, y));
+
+    let mut chosen: Vec<(usize, usize)> = Vec::new();
+    for (x, y) in candidates {
+        if chosen
+            .iter()
+            .all(|&(cx, cy)| x.abs_diff(cx) + y.abs_diff(cy) >= MIN_SPACING)
+        {
+            chosen.push((x, y));
+        }
+    }
+    chosen
+}
+
+/// Steps from every cell to the nearest water by a breadth-first search out
+/// of all water cells at once, `usize::MAX` on maps without water.
+fn water_distances(tiles: &[Option<TileType>]) -> Vec<usize> {
+    let mut distances = vec![usize::MAX; GRID_W * GRID_H];
+    let mut frontier = VecDeque::new();
+    for (i, tile) in tiles.iter().enumerate() {
+        if *tile == Some(TileType::Water) {
+            distances[i] = 0;
+            frontier.push_back((i % GRID_W, i / GRID_W));
+        }
+    }
+    while let Some((x, y)) = frontier.pop_front() {
+        let next = distances[y * GRID_W + x] + 1;
+        for (nx, ny) in neighbor_coords(x, y) {
+            if distances[ny * GRID_W + nx] > next {
+                distances[ny * GRID_W + nx] = next;
+                frontier.push_back((nx, ny));
+            }
+        }
+    }
+    distances
+}
+
+fn place_spawn_points(
+    mut commands: Commands,
+    seed: Res<Seed>,
+    grid: Res<WfcGrid>,
+    mut chosen: MessageWriter<SpawnPointChosen>,
+) {
+    for (x, y) in choose_spawn_points(&grid.collapsed_grid(), seed.0) {
+        commands.spawn((
+            SpawnPoint { x, y },
+            Sprite {
+                color: MARKER_COLOR,
+                custom_size: Some(Vec2::splat(MARKER_SIZE)),
+                ..default()
+            },
+            // Turned into a diamond to tell it from the square decorations.
+            Transform::from_translation(cell_center(x, y).extend(layers::SPAWN_POINT))
+                .with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_4)),
+        ));
+        chosen.write(SpawnPointChosen { seed: seed.0, x, y });
+    }
+}
+
+fn clear_spawn_points(mut commands: Commands, points: Query<Entity, With<SpawnPoint>>) {
+    for point in points.iter() {
+        commands.entity(point).despawn();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// All `tile`, with water at the given cells.
+    fn grid_with_water(tile: TileType, water: &[(usize, usize)]) -> Vec<Option<TileType>> {
+        let mut tiles = vec![Some(tile); GRID_W * GRID_H];
+        for &(x, y) in water {
+            tiles[y * GRID_W + x] = Some(TileType::Water);
+        }
+        tiles
+    }
+
+    #[test]
+    fn water_distances_count_steps_to_the_nearest_water() {
+        let distances = water_distances(&grid_with_water(TileType::Grass, &[(0, 0), (9, 0)]));
+        assert_eq!(distances[0], 0);
+        assert_eq!(distances[1], 1);
+        assert_eq!(distances[GRID_W + 3], 4);
+        assert_eq!(distances[6], 3);
+        assert_eq!(distances[2 * GRID_W + 9], 2);
+    }
+
+    #[test]
+    fn maps_without_water_are_infinitely_far_from_it() {
+        let distances = water_distances(&grid_with_water(TileType::Grass, &[]));
+        assert!(distances.iter().all(|&distance| distance == usize::MAX));
+    }
+
+    #[test]
+    fn spawn_points_keep_their_distance() {
+        let water = [(0, 0), (GRID_W / 2, GRID_H / 2)];
+        let tiles = grid_with_water(TileType::Grass, &water);
+        let distances = water_distances(&tiles);
+        let chosen = choose_spawn_points(&tiles, 7);
+        assert!(!chosen.is_empty());
+        for (i, &(x, y)) in chosen.iter().enumerate() {
+            assert!(distances[y * GRID_W + x] >= MIN_WATER_DISTANCE);
+            for &(ox, oy) in &chosen[..i] {
+                assert!(x.abs_diff(ox) + y.abs_diff(oy) >= MIN_SPACING);
+            }
+        }
+    }
+
+    #[test]
+    fn spawn_points_only_go_on_grass() {
+        assert!(choose_spawn_points(&grid_with_water(TileType::Sand, &[]), 7).is_empty());
+
+        let mut tiles = grid_with_water(TileType::Sand, &[]);
+        tiles[3 * GRID_W + 4] = Some(TileType::Grass);
+        assert_eq!(choose_spawn_points(&tiles, 7), vec![(4, 3)]);
+    }
+
+    #[test]
+    fn the_same_seed_picks_the_same_points() {
+        let tiles = grid_with_water(TileType::Grass, &[(10, 10)]);
+        assert_eq!(
+            choose_spawn_points(&tiles, 3),
+            choose_spawn_points(&tiles, 3)
+        );
+        assert_ne!(
+            choose_spawn_points(&tiles, 3),
+            choose_spawn_points(&tiles, 4)
+        );
+    }
+}
@@ -0,0 +1,228 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::ui::RelativeCursorPosition;
+
+use crate::cli::Cli;
+use crate::keybindings::{Action, Keybindings};
+
+const MIN_SPEED: f32 = 1.0;
+const MAX_SPEED: f32 = 10_000.0;
+const DEFAULT_SPEED: f32 = 60.0;
+/// Speed factor applied by each press of the speed keys.
+const KEY_FACTOR: f32 = 2.0;
+/// Longest frame time credited to the solver, so a stall doesn't turn into a
+/// burst of catch-up collapses.
+const MAX_CATCH_UP_SECS: f32 = 0.1;
+
+const SLIDER_WIDTH: f32 = 160.0;
+const SLIDER_HEIGHT: f32 = 10.0;
+const SLIDER_MARGIN: f32 = 8.0;
+const TRACK_COLOR: Color = Color::srgba(0.1, 0.1, 0.1, 0.8);
+const FILL_COLOR: Color = Color::srgb(0.9, 0.9, 0.9);
+
+/// Collapses per second, independent of the frame rate. Set with the slider
+/// in the bottom-left corner or the speed keys.
+#[derive(Resource)]
+pub struct GenerationSpeed {
+    pub collapses_per_second: f32,
+    /// Time the solver may take each frame instead, running as many
+    /// collapses as fit in it whatever they cost. Set with `--frame-budget`
+    /// or `frame_budget` in the config; the rate is ignored while it is.
+    pub frame_budget: Option<Duration>,
+    carry: f32,
+    /// Recent time per collapse, smoothed, to plan how many fit in the frame
+    /// budget. Zero until the first is measured.
+    secs_per_collapse: f32,
+}
+
+impl Default for GenerationSpeed {
+    fn default() -> Self {
+        Self {
+            collapses_per_second: DEFAULT_SPEED,
+            frame_budget: None,
+            carry: 0.0,
+            secs_per_collapse: 0.0,
+        }
+    }
+}
+
+impl GenerationSpeed {
+    /// Whole collapses due after `delta_secs`, keeping the fraction for later.
+    /// With a frame budget, the collapses expected to fit in it plus one, so
+    /// the solver runs into the deadline rather than stopping short of it.
+    pub fn take_steps(&mut self, delta_secs: f32) -> usize {
+        if let Some(budget) = self.frame_budget {
+            if self.secs_per_collapse <= 0.0 {
+                return 1;
+            }
+            return (budget.as_secs_f32() / self.secs_per_collapse) as usize + 1;
+        }
+        self.carry += self.collapses_per_second * delta_secs.min(MAX_CATCH_UP_SECS);
+        let steps = self.carry.floor();
+        self.carry -= steps;
+        steps as usize
+    }
+
+    /// Takes in how long the solver took for `steps` collapses this frame.
+    pub fn measure(&mut self, steps: usize, elapsed: Duration) {
+        if steps == 0 {
+            return;
+        }
+        let sample = elapsed.as_secs_f32() / steps as f32;
+        self.secs_per_collapse = if self.secs_per_collapse > 0.0 {
+            self.secs_per_collapse * 0.8 + sample * 0.2
+        } else {
+            sample
+        };
+    }
+
+    /// Sets the frame budget from milliseconds, or clears it.
+    pub fn set_frame_budget(&mut self, millis: Option<f32>) {
+        self.frame_budget = millis
+            .filter(|&millis| millis > 0.0)
+            .map(|millis| Duration::from_secs_f32(millis / 1000.0));
+    }
+
+    /// Sets the speed, clamped to the slider's range.
+    pub fn set(&mut self, collapses_per_second: f32) {
+        self.collapses_per_second = collapses_per_second.clamp(MIN_SPEED, MAX_SPEED);
+    }
+
+    /// Multiplies the speed, staying within the slider's range.
+    pub fn scale(&mut self, factor: f32) {
+        self.collapses_per_second =
+            (self.collapses_per_second * factor).clamp(MIN_SPEED, MAX_SPEED);
+    }
+
+    /// Slider position in 0..=1, logarithmic so slow motion and turbo both
+    /// get a usable share of the track.
+    fn fraction(&self) -> f32 {
+        (self.collapses_per_second / MIN_SPEED).ln() / (MAX_SPEED / MIN_SPEED).ln()
+    }
+
+    fn set_fraction(&mut self, fraction: f32) {
+        self.collapses_per_second =
+            MIN_SPEED * (MAX_SPEED / MIN_SPEED).powf(fraction.clamp(0.0, 1.0));
+    }
+}
+
+#[derive(Component)]
+struct SpeedSlider;
+
+#[derive(Component)]
+struct SpeedFill;
+
+#[derive(Component)]
+struct SpeedLabel;
+
+pub struct SpeedControlPlugin;
+
+impl Plugin for SpeedControlPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GenerationSpeed>()
+            .add_systems(Startup, (spawn_slider, budget_from_cli))
+            .add_systems(
+                Update,
+                (
+                    speed_keys,
+                    drag_slider,
+                    update_slider.run_if(resource_changed::<GenerationSpeed>),
+                )
+                    .chain(),
+            );
+    }
+}
+
+fn spawn_slider(mut commands: Commands) {
+    commands
+        .spawn(Node {
+            position_type: PositionType::Absolute,
+            left: Val::Px(SLIDER_MARGIN),
+            bottom: Val::Px(SLIDER_MARGIN),
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(4.0),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn((SpeedLabel, Text::default(), TextFont::from_font_size(14.0)));
+            parent
+                .spawn((
+                    SpeedSlider,
+                    Button,
+                    RelativeCursorPosition::default(),
+                    Node {
+                        width: Val::Px(SLIDER_WIDTH),
+                        height: Val::Px(SLIDER_HEIGHT),
+                        ..default()
+                    },
+                    BackgroundColor(TRACK_COLOR),
+                ))
+                .with_child((
+                    SpeedFill,
+                    Node {
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    BackgroundColor(FILL_COLOR),
+                ));
+        });
+}
+
+fn budget_from_cli(cli: Res<Cli>, mut speed: ResMut<GenerationSpeed>) {
+    if cli.frame_budget.is_some() {
+        speed.set_frame_budget(cli.frame_budget);
+    }
+}
+
+fn speed_keys(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keybindings: Res<Keybindings>,
+    mut speed: ResMut<GenerationSpeed>,
+) {
+    if keybindings.just_pressed(Action::SpeedUp, &keyboard_input) {
+        speed.scale(KEY_FACTOR);
+    }
+    if keybindings.just_pressed(Action::SlowDown, &keyboard_input) {
+        speed.scale(1.0 / KEY_FACTOR);
+    }
+}
+
+/// How far along a slider the cursor is dragging it, from 0 at the left edge
+/// to 1 at the right, or `None` while it isn't pressed.
+pub fn slider_fraction(interaction: &Interaction, cursor: &RelativeCursorPosition) -> Option<f32> {
+    if *interaction != Interaction::Pressed {
+        return None;
+    }
+    // Normalized positions run from -0.5 at the left edge to 0.5 at the right.
+    cursor
+        .normalized
+        .map(|position| (position.x + 0.5).clamp(0.0, 1.0))
+}
+
+fn drag_slider(
+    mut speed: ResMut<GenerationSpeed>,
+    sliders: Query<(&Interaction, &RelativeCursorPosition), With<SpeedSlider>>,
+) {
+    for (interaction, cursor) in sliders.iter() {
+        if let Some(fraction) = slider_fraction(interaction, cursor) {
+            speed.set_fraction(fraction);
+        }
+    }
+}
+
+fn update_slider(
+    speed: Res<GenerationSpeed>,
+    mut fills: Query<&mut Node, With<SpeedFill>>,
+    mut labels: Query<&mut Text, With<SpeedLabel>>,
+) {
+    for mut node in fills.iter_mut() {
+        node.width = Val::Percent(speed.fraction() * 100.0);
+    }
+    for mut text in labels.iter_mut() {
+        text.0 = match speed.frame_budget {
+            Some(budget) => format!("{:.1} ms/frame", budget.as_secs_f32() * 1000.0),
+            None => format!("{:.0} collapses/s", speed.collapses_per_second),
+        };
+    }
+}
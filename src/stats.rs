@@ -0,0 +1,145 @@
+use bevy::prelude::*;
+
+use crate::generation::GenerationState;
+use crate::keybindings::{Action, Keybindings};
+use crate::{Contradicted, GRID_H, GRID_W, Seed, WfcGrid, collapse_step};
+
+const HUD_MARGIN: f32 = 8.0;
+const HUD_FONT_SIZE: f32 = 14.0;
+const HUD_BACKGROUND: Color = Color::srgba(0.05, 0.05, 0.05, 0.7);
+/// How often the measured collapse rate is refreshed.
+const RATE_WINDOW_SECS: f32 = 0.5;
+
+/// Running totals for the statistics HUD, toggled with `I`.
+#[derive(Resource, Default)]
+pub struct GenerationStats {
+    pub visible: bool,
+    pub collapsed: usize,
+    pub contradictions: usize,
+    /// Contradictions on the current map alone.
+    pub map_contradictions: usize,
+    pub restarts: usize,
+    /// Unpaused time spent on the current map until it finished.
+    pub elapsed_secs: f32,
+    pub collapses_per_second: f32,
+    window_secs: f32,
+    window_collapses: usize,
+}
+
+#[derive(Component)]
+struct StatsHud;
+
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GenerationStats>()
+            .add_systems(Startup, spawn_hud)
+            .add_systems(
+                Update,
+                (
+                    toggle_hud,
+                    reset_on_regenerate.run_if(resource_changed::<Seed>),
+                    measure.after(collapse_step),
+                    update_hud,
+                )
+                    .chain(),
+            );
+    }
+}
+
+fn spawn_hud(mut commands: Commands) {
+    commands.spawn((
+        StatsHud,
+        Text::default(),
+        TextFont::from_font_size(HUD_FONT_SIZE),
+        Node {
+            position_type: PositionType::Absolute,
+            right: Val::Px(HUD_MARGIN),
+            top: Val::Px(HUD_MARGIN),
+            padding: UiRect::all(Val::Px(4.0)),
+            ..default()
+        },
+        BackgroundColor(HUD_BACKGROUND),
+        Visibility::Hidden,
+    ));
+}
+
+fn toggle_hud(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keybindings: Res<Keybindings>,
+    mut stats: ResMut<GenerationStats>,
+) {
+    if keybindings.just_pressed(Action::ToggleStats, &keyboard_input) {
+        stats.visible = !stats.visible;
+    }
+}
+
+/// Contradictions and restarts add up across maps; the rest starts over.
+fn reset_on_regenerate(seed: Res<Seed>, mut stats: ResMut<GenerationStats>) {
+    if !seed.is_added() {
+        stats.restarts += 1;
+    }
+    stats.collapsed = 0;
+    stats.map_contradictions = 0;
+    stats.elapsed_secs = 0.0;
+    stats.collapses_per_second = 0.0;
+    stats.window_secs = 0.0;
+    stats.window_collapses = 0;
+}
+
+pub fn measure(
+    time: Res<Time>,
+    state: Res<State<GenerationState>>,
+    mut stats: ResMut<GenerationStats>,
+    grid: Res<WfcGrid>,
+    contradicted: Query<(), Added<Contradicted>>,
+) {
+    let collapsed = grid.cells.iter().filter(|cell| cell.collapsed).count();
+
+    let contradictions = contradicted.iter().count();
+    stats.contradictions += contradictions;
+    stats.map_contradictions += contradictions;
+    // Undo can lower the count; only forward progress counts towards the rate.
+    stats.window_collapses += collapsed.saturating_sub(stats.collapsed);
+    stats.collapsed = collapsed;
+    if *state.get() == GenerationState::Generating {
+        stats.elapsed_secs += time.delta_secs();
+    }
+
+    stats.window_secs += time.delta_secs();
+    if stats.window_secs >= RATE_WINDOW_SECS {
+        stats.collapses_per_second = stats.window_collapses as f32 / stats.window_secs;
+        stats.window_secs = 0.0;
+        stats.window_collapses = 0;
+    }
+}
+
+fn update_hud(
+    stats: Res<GenerationStats>,
+    state: Res<State<GenerationState>>,
+    mut huds: Query<(&mut Text, &mut Visibility), With<StatsHud>>,
+) {
+    for (mut text, mut visibility) in huds.iter_mut() {
+        visibility.set_if_neq(if stats.visible {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        });
+        if !stats.visible {
+            continue;
+        }
+        let total = GRID_W * GRID_H;
+        text.0 = format!(
+            "{}\ncollapsed: {} / {} ({:.0}%)\ncontradictions: {}\nrestarts: {}\ntime: {:.1}s\nrate: {:.0} collapses/s",
+            state.get().label(),
+            stats.collapsed,
+            total,
+            stats.collapsed as f32 / total as f32 * 100.0,
+            stats.contradictions,
+            stats.restarts,
+            stats.elapsed_secs,
+            stats.collapses_per_second,
+        );
+    }
+}
@@ -0,0 +1,63 @@
+use bevy::prelude::*;
+
+use crate::WfcSet;
+use crate::keybindings::{Action, Keybindings};
+use crate::speed::GenerationSpeed;
+
+/// Whether generation runs freely or one step at a time. While paused, the
+/// step binding runs a single observation and propagation step.
+#[derive(Resource, Default)]
+pub struct Stepping {
+    pub paused: bool,
+    step_requested: bool,
+}
+
+/// Collapses the solver runs this frame.
+#[derive(Resource, Default)]
+pub struct StepBudget(pub usize);
+
+pub struct SteppingPlugin;
+
+impl Plugin for SteppingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Stepping>()
+            .init_resource::<StepBudget>()
+            .add_systems(
+                Update,
+                (stepping_input, plan_steps).chain().in_set(WfcSet::Prepare),
+            );
+    }
+}
+
+fn stepping_input(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keybindings: Res<Keybindings>,
+    mut stepping: ResMut<Stepping>,
+) {
+    if keybindings.just_pressed(Action::PauseResume, &keyboard_input) {
+        stepping.paused = !stepping.paused;
+        info!(
+            "generation {}",
+            if stepping.paused { "paused" } else { "resumed" }
+        );
+    }
+    if stepping.paused && keybindings.just_pressed(Action::Step, &keyboard_input) {
+        stepping.step_requested = true;
+    }
+}
+
+pub fn plan_steps(
+    time: Res<Time>,
+    mut stepping: ResMut<Stepping>,
+    mut speed: ResMut<GenerationSpeed>,
+    mut budget: ResMut<StepBudget>,
+) {
+    budget.0 = if stepping.paused {
+        usize::from(std::mem::take(&mut stepping.step_requested))
+    } else {
+        // Pacing state isn't a setting change, so don't wake the slider.
+        speed
+            .bypass_change_detection()
+            .take_steps(time.delta_secs())
+    };
+}
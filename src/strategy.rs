@@ -0,0 +1,209 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::history::GridState;
+use crate::inspector::Provenance;
+use crate::rules::Rules;
+use crate::{Seed, TileType, WfcGrid, regenerate};
+
+/// Oldest observations are dropped beyond this many backtracking steps.
+const MAX_BACKTRACK: usize = 256;
+const PANEL_LEFT: f32 = 8.0;
+/// Below the save and load buttons.
+const PANEL_TOP: f32 = 124.0;
+const PANEL_FONT_SIZE: f32 = 14.0;
+const BUTTON_BACKGROUND: Color = Color::srgba(0.05, 0.05, 0.05, 0.8);
+const BUTTON_HOVERED: Color = Color::srgba(0.3, 0.3, 0.3, 0.9);
+
+/// How a collapse narrows the other domains.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+pub enum Propagation {
+    /// Only the direct neighbors of the collapsed cell.
+    #[default]
+    Naive,
+    /// Arc consistency over the whole grid.
+    Ac4,
+}
+
+/// Which uncollapsed cell is observed next.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+pub enum Heuristic {
+    /// Fewest remaining tiles.
+    #[default]
+    MinRemaining,
+    /// Lowest Shannon entropy under the rules' tile weights.
+    Entropy,
+    /// Row by row from the bottom-left corner.
+    Scanline,
+    Random,
+}
+
+/// Solver strategy, switched from the panel below the save buttons. Each
+/// change regenerates the map with the same seed so strategies can be
+/// compared directly.
+#[derive(Resource, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct Strategy {
+    pub propagation: Propagation,
+    pub backtracking: bool,
+    pub heuristic: Heuristic,
+}
+
+/// Grid states before each observation while backtracking is on, with the
+/// observed cell and its chosen tile. Replaced for every map.
+#[derive(Resource, Default)]
+pub struct Backtrack(VecDeque<(GridState, (usize, usize), TileType)>);
+
+impl Backtrack {
+    pub fn push(&mut self, state: GridState, cell: (usize, usize), choice: TileType) {
+        if self.0.len() == MAX_BACKTRACK {
+            self.0.pop_front();
+        }
+        self.0.push_back((state, cell, choice));
+    }
+
+    /// Rewinds to the state before the latest observation and rules out the
+    /// tile chosen there, going further back while that leaves the cell with
+    /// nothing. Returns false once there is nothing left to rewind.
+    pub fn rewind(
+        &mut self,
+        commands: &mut Commands,
+        rules: &Rules,
+        provenance: &mut Provenance,
+        grid: &mut WfcGrid,
+    ) -> bool {
+        while let Some((state, (x, y), choice)) = self.0.pop_back() {
+            state.restore(commands, rules, provenance, grid);
+            let tile = grid.cell_mut(x, y);
+            tile.possible.retain(|&possible| possible != choice);
+            if !tile.possible.is_empty() {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+/// Which setting a panel button cycles.
+#[derive(Component, Clone, Copy)]
+enum StrategyButton {
+    Propagation,
+    Backtracking,
+    Heuristic,
+}
+
+pub struct StrategyPlugin;
+
+impl Plugin for StrategyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Strategy>()
+            .add_systems(Startup, spawn_panel)
+            .add_systems(
+                Update,
+                (
+                    cycle_setting.before(regenerate),
+                    update_labels.run_if(resource_changed::<Strategy>),
+                )
+                    .chain(),
+            );
+    }
+}
+
+fn spawn_panel(mut commands: Commands) {
+    commands
+        .spawn(Node {
+            position_type: PositionType::Absolute,
+            left: Val::Px(PANEL_LEFT),
+            top: Val::Px(PANEL_TOP),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Start,
+            row_gap: Val::Px(2.0),
+            ..default()
+        })
+        .with_children(|parent| {
+            for button in [
+                StrategyButton::Propagation,
+                StrategyButton::Backtracking,
+                StrategyButton::Heuristic,
+            ] {
+                parent.spawn((
+                    button,
+                    Button,
+                    Node {
+                        padding: UiRect::axes(Val::Px(6.0), Val::Px(2.0)),
+                        ..default()
+                    },
+                    BackgroundColor(BUTTON_BACKGROUND),
+                    children![(Text::default(), TextFont::from_font_size(PANEL_FONT_SIZE))],
+                ));
+            }
+        });
+}
+
+fn cycle_setting(
+    mut buttons: Query<(&Interaction, &StrategyButton, &mut BackgroundColor), Changed<Interaction>>,
+    mut strategy: ResMut<Strategy>,
+    mut seed: ResMut<Seed>,
+) {
+    for (interaction, button, mut background) in buttons.iter_mut() {
+        background.0 = match interaction {
+            Interaction::None => BUTTON_BACKGROUND,
+            Interaction::Hovered | Interaction::Pressed => BUTTON_HOVERED,
+        };
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button {
+            StrategyButton::Propagation => {
+                strategy.propagation = match strategy.propagation {
+                    Propagation::Naive => Propagation::Ac4,
+                    Propagation::Ac4 => Propagation::Naive,
+                };
+            }
+            StrategyButton::Backtracking => strategy.backtracking = !strategy.backtracking,
+            StrategyButton::Heuristic => {
+                strategy.heuristic = match strategy.heuristic {
+                    Heuristic::MinRemaining => Heuristic::Entropy,
+                    Heuristic::Entropy => Heuristic::Scanline,
+                    Heuristic::Scanline => Heuristic::Random,
+                    Heuristic::Random => Heuristic::MinRemaining,
+                };
+            }
+        }
+        seed.set_changed();
+    }
+}
+
+fn update_labels(
+    strategy: Res<Strategy>,
+    buttons: Query<(&StrategyButton, &Children)>,
+    mut texts: Query<&mut Text>,
+) {
+    for (button, children) in buttons.iter() {
+        let label = match button {
+            StrategyButton::Propagation => match strategy.propagation {
+                Propagation::Naive => "propagation: naive",
+                Propagation::Ac4 => "propagation: AC-4",
+            },
+            StrategyButton::Backtracking => {
+                if strategy.backtracking {
+                    "backtracking: on"
+                } else {
+                    "backtracking: off"
+                }
+            }
+            StrategyButton::Heuristic => match strategy.heuristic {
+                Heuristic::MinRemaining => "heuristic: fewest tiles",
+                Heuristic::Entropy => "heuristic: entropy",
+                Heuristic::Scanline => "heuristic: scanline",
+                Heuristic::Random => "heuristic: random",
+            },
+        };
+        for child in children.iter() {
+            if let Ok(mut text) = texts.get_mut(child) {
+                text.0 = label.to_string();
+            }
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use bevy::prelude::*;
+
+use crate::theme::Theme;
+use crate::{TILE_SIZE, Tile, TileType, WfcGrid, collapse_step, layers};
+
+const SWATCH_MARGIN: f32 = 2.0;
+
+/// Child sprite showing one tile type that is still in its parent's domain.
+#[derive(Component)]
+struct SwatchBar(TileType);
+
+pub struct SwatchPlugin;
+
+impl Plugin for SwatchPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                spawn_swatches,
+                update_swatches.run_if(resource_changed::<WfcGrid>),
+                recolor_swatches.run_if(resource_changed::<Theme>),
+            )
+                .chain()
+                .after(collapse_step),
+        );
+    }
+}
+
+fn spawn_swatches(mut commands: Commands, theme: Res<Theme>, tiles: Query<Entity, Added<Tile>>) {
+    for tile_entity in tiles.iter() {
+        commands.entity(tile_entity).with_children(|parent| {
+            for tile_type in TileType::ALL {
+                parent.spawn((
+                    SwatchBar(tile_type),
+                    Sprite::from_color(theme.color(tile_type), Vec2::ZERO),
+                    Transform::from_xyz(0.0, 0.0, layers::SWATCH),
+                    Visibility::Hidden,
+                ));
+            }
+        });
+    }
+}
+
+/// Splits uncollapsed cells into equal vertical bars, one per remaining
+/// possibility, so the domain shrinking is visible as propagation runs.
+fn update_swatches(
+    grid: Res<WfcGrid>,
+    tiles: Query<(&Tile, &Children)>,
+    mut bars: Query<(&SwatchBar, &mut Sprite, &mut Transform, &mut Visibility)>,
+) {
+    for (tile, children) in tiles.iter() {
+        let tile = grid.cell(tile.x, tile.y);
+        let count = if tile.collapsed {
+            0
+        } else {
+            tile.possible.len()
+        };
+        let inner = TILE_SIZE - SWATCH_MARGIN * 2.0;
+
+        for &child in children {
+            let Ok((bar, mut sprite, mut transform, mut visibility)) = bars.get_mut(child) else {
+                continue;
+            };
+
+            let slot = if count > 0 {
+                tile.possible.iter().position(|&t| t == bar.0)
+            } else {
+                None
+            };
+            let Some(slot) = slot else {
+                *visibility = Visibility::Hidden;
+                continue;
+            };
+
+            let width = inner / count as f32;
+            sprite.custom_size = Some(Vec2::new(width, inner));
+            transform.translation.x = -inner / 2.0 + width * (slot as f32 + 0.5);
+            *visibility = Visibility::Inherited;
+        }
+    }
+}
+
+fn recolor_swatches(theme: Res<Theme>, mut bars: Query<(&SwatchBar, &mut Sprite)>) {
+    for (bar, mut sprite) in bars.iter_mut() {
+        sprite.color = theme.color(bar.0);
+    }
+}
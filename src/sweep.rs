@@ -0,0 +1,260 @@
+use std::num::NonZero;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+use std::time::Instant;
+
+use serde::Serialize;
+
+use crate::cli::{Cli, map_locks};
+use crate::headless::{HeadlessSolver, MAX_STEPS};
+use crate::heightmap::WeightBias;
+use crate::lock::Locks;
+use crate::rules::Rules;
+use crate::strategy::Strategy;
+use crate::{GRID_H, GRID_W, TileType};
+
+/// Outcome of one map of a sweep.
+#[derive(Serialize)]
+struct MapStats {
+    seed: u64,
+    /// Gave up after [`MAX_STEPS`] or finished with contradictions left in
+    /// the map.
+    failed: bool,
+    contradictions: usize,
+    /// Rewinds by backtracking.
+    retries: usize,
+    seconds: f32,
+    /// Collapsed cells of each tile, in the order of [`TileType::ALL`].
+    tiles: [usize; TileType::ALL.len()],
+}
+
+/// Aggregate statistics of the maps of a seed range, for judging a ruleset
+/// or strategy change by more than a handful of maps.
+#[derive(Serialize)]
+struct Sweep {
+    rules: String,
+    first_seed: u64,
+    maps: usize,
+    failed: usize,
+    failure_rate: f32,
+    contradictions_per_map: f32,
+    retries_per_map: f32,
+    seconds_per_map: f32,
+    tiles: Vec<TileDistribution>,
+    /// Every map, by seed.
+    results: Vec<MapStats>,
+}
+
+/// How much of a map a tile covers across the sweep, as fractions of all
+/// cells.
+#[derive(Serialize)]
+struct TileDistribution {
+    name: String,
+    mean_share: f32,
+    min_share: f32,
+    max_share: f32,
+    std_dev: f32,
+}
+
+impl Sweep {
+    fn new(rules_name: &str, first_seed: u64, results: Vec<MapStats>) -> Self {
+        let maps = results.len();
+        let per_map = |total: f32| total / maps as f32;
+        let failed = results.iter().filter(|map| map.failed).count();
+        let tiles = TileType::ALL
+            .iter()
+            .map(|tile| {
+                let shares: Vec<f32> = results
+                    .iter()
+                    .map(|map| map.tiles[tile.index()] as f32 / (GRID_W * GRID_H) as f32)
+                    .collect();
+                let mean = per_map(shares.iter().sum());
+                let variance = per_map(shares.iter().map(|share| (share - mean).powi(2)).sum());
+                TileDistribution {
+                    name: format!("{tile:?}"),
+                    mean_share: mean,
+                    min_share: shares.iter().copied().fold(f32::INFINITY, f32::min),
+                    max_share: shares.iter().copied().fold(0.0, f32::max),
+                    std_dev: variance.sqrt(),
+                }
+            })
+            .collect();
+        Self {
+            rules: rules_name.to_string(),
+            first_seed,
+            maps,
+            failed,
+            failure_rate: per_map(failed as f32),
+            contradictions_per_map: per_map(
+                results.iter().map(|map| map.contradictions as f32).sum(),
+            ),
+            retries_per_map: per_map(results.iter().map(|map| map.retries as f32).sum()),
+            seconds_per_map: per_map(results.iter().map(|map| map.seconds).sum()),
+            tiles,
+            results,
+        }
+    }
+
+    fn summary(&self) -> String {
+        let mut lines = vec![
+            format!(
+                "{} maps from seed {} with {}",
+                self.maps, self.first_seed, self.rules
+            ),
+            format!(
+                "failed: {} ({:.1}%)",
+                self.failed,
+                self.failure_rate * 100.0
+            ),
+            format!(
+                "per map: {:.2} contradictions, {:.2} retries, {:.1} ms",
+                self.contradictions_per_map,
+                self.retries_per_map,
+                self.seconds_per_map * 1000.0
+            ),
+        ];
+        for tile in &self.tiles {
+            lines.push(format!(
+                "{}: {:.1}% (min {:.1}%, max {:.1}%, sd {:.1}%)",
+                tile.name,
+                tile.mean_share * 100.0,
+                tile.min_share * 100.0,
+                tile.max_share * 100.0,
+                tile.std_dev * 100.0
+            ));
+        }
+        lines.join("\n")
+    }
+
+    fn csv(&self) -> String {
+        let mut columns: Vec<String> = ["seed", "failed", "contradictions", "retries", "seconds"]
+            .map(String::from)
+            .to_vec();
+        columns.extend(
+            TileType::ALL.map(|tile| format!("{}_cells", format!("{tile:?}").to_lowercase())),
+        );
+        let mut rows = vec![columns.join(",")];
+        for map in &self.results {
+            let mut values = vec![
+                map.seed.to_string(),
+                map.failed.to_string(),
+                map.contradictions.to_string(),
+                map.retries.to_string(),
+                format!("{:.3}", map.seconds),
+            ];
+            values.extend(map.tiles.map(|cells| cells.to_string()));
+            rows.push(values.join(","));
+        }
+        rows.join("\n") + "\n"
+    }
+}
+
+/// Solves `count` maps with seeds counting up from `--seed` (0 if not given)
+/// on every core, prints their statistics and writes them to each `--output`
+/// path. Returns false if the import or any of the files failed.
+pub fn run_sweep(cli: &Cli, count: u64) -> bool {
+    let first_seed = cli.seed.unwrap_or_default();
+    let rules = cli.rules();
+    let locks = match map_locks(cli) {
+        Ok(locks) => locks,
+        Err(err) => {
+            eprintln!("{err}");
+            return false;
+        }
+    };
+    let started = Instant::now();
+    let results = solve_all(
+        &rules,
+        &cli.strategy(),
+        &cli.bias(),
+        &locks,
+        first_seed,
+        count,
+    );
+    let sweep = Sweep::new(&rules.name, first_seed, results);
+    println!("{}", sweep.summary());
+    println!("took {:.1} s", started.elapsed().as_secs_f32());
+
+    let mut ok = true;
+    for path in &cli.output {
+        let text = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => serde_json::to_string_pretty(&sweep).map_err(|err| err.to_string()),
+            Some("csv") => Ok(sweep.csv()),
+            _ => Err("unknown format; use a .json or .csv path".to_string()),
+        };
+        match text.and_then(|text| std::fs::write(path, text).map_err(|err| err.to_string())) {
+            Ok(()) => println!("wrote sweep to {}", path.display()),
+            Err(err) => {
+                eprintln!("could not write {}: {err}", path.display());
+                ok = false;
+            }
+        }
+    }
+    ok
+}
+
+/// Solves the maps of `count` seeds from `first_seed` on a thread per core,
+/// each taking the next seed when it's done with one. Returned in seed order.
+fn solve_all(
+    rules: &Rules,
+    strategy: &Strategy,
+    bias: &WeightBias,
+    locks: &Locks,
+    first_seed: u64,
+    count: u64,
+) -> Vec<MapStats> {
+    let next = AtomicU64::new(0);
+    let threads = thread::available_parallelism()
+        .map_or(1, NonZero::get)
+        .min(count as usize);
+    let mut results: Vec<MapStats> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut results = Vec::new();
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        if i >= count {
+                            break results;
+                        }
+                        let seed = first_seed.wrapping_add(i);
+                        results.push(solve(rules, strategy, bias, locks, seed));
+                    }
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap())
+            .collect()
+    });
+    results.sort_by_key(|map| map.seed.wrapping_sub(first_seed));
+    results
+}
+
+/// Solves one map headlessly, stopping at [`MAX_STEPS`].
+fn solve(
+    rules: &Rules,
+    strategy: &Strategy,
+    bias: &WeightBias,
+    locks: &Locks,
+    seed: u64,
+) -> MapStats {
+    let started = Instant::now();
+    let mut solver = HeadlessSolver::new(rules, strategy, locks, seed);
+    solver.set_bias(bias.clone());
+    let stats = solver.run(MAX_STEPS);
+    let mut tiles = [0; TileType::ALL.len()];
+    for tile in solver.grid().into_iter().flatten() {
+        tiles[tile.index()] += 1;
+    }
+    let contradicted = solver.into_state().contradicted;
+    MapStats {
+        seed,
+        failed: !stats.done || !contradicted.is_empty(),
+        contradictions: stats.contradictions,
+        retries: stats.retries,
+        seconds: started.elapsed().as_secs_f32(),
+        tiles,
+    }
+}
@@ -0,0 +1,138 @@
+use bevy::asset::RenderAssetUsages;
+use bevy::image::ImageSampler;
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+
+use crate::chunked::RenderBackend;
+use crate::theme::Theme;
+use crate::{
+    Cell, GRID_H, GRID_W, TILE_SIZE, TileType, WfcGrid, collapse_step, grid_center, layers,
+};
+
+/// Drawn for cells that aren't collapsed yet, like their blank sprites.
+const UNCOLLAPSED_COLOR: Color = Color::WHITE;
+
+/// The whole grid as one image with a texel per cell, drawn on a single
+/// sprite by [`RenderBackend::Texture`].
+#[derive(Resource)]
+struct TextureMap {
+    image: Handle<Image>,
+    sprite: Entity,
+    /// What each texel shows, indexed by `y * GRID_W + x`, so only cells
+    /// whose collapsed tile changed are written again. `None` for texels
+    /// never written, which holds every one after switching to the texture.
+    written: Vec<Option<Option<TileType>>>,
+}
+
+pub struct TextureMapPlugin;
+
+impl Plugin for TextureMapPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_texture_map).add_systems(
+            Update,
+            (
+                show_texture_map.run_if(resource_changed::<RenderBackend>),
+                sync_texels
+                    .run_if(
+                        resource_equals(RenderBackend::Texture).and(
+                            resource_changed::<WfcGrid>
+                                .or(resource_changed::<Theme>)
+                                .or(resource_changed::<RenderBackend>),
+                        ),
+                    )
+                    .after(collapse_step),
+            )
+                .chain(),
+        );
+    }
+}
+
+fn spawn_texture_map(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    let mut image = Image::new_fill(
+        Extent3d {
+            width: GRID_W as u32,
+            height: GRID_H as u32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &UNCOLLAPSED_COLOR.to_srgba().to_u8_array(),
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    image.sampler = ImageSampler::nearest();
+    let image = images.add(image);
+
+    let sprite = commands
+        .spawn((
+            Sprite {
+                image: image.clone(),
+                custom_size: Some(Vec2::new(GRID_W as f32, GRID_H as f32) * TILE_SIZE),
+                ..default()
+            },
+            Transform::from_translation(grid_center().extend(layers::TILE)),
+            Visibility::Hidden,
+        ))
+        .id();
+    commands.insert_resource(TextureMap {
+        image,
+        sprite,
+        written: vec![None; GRID_W * GRID_H],
+    });
+}
+
+/// Shows the sprite only with the texture backend. Its texels aren't kept up
+/// to date in the other modes, so they're all written again on the way back.
+fn show_texture_map(
+    backend: Res<RenderBackend>,
+    mut texture_map: ResMut<TextureMap>,
+    mut visibility: Query<&mut Visibility>,
+) {
+    let shown = *backend == RenderBackend::Texture;
+    if let Ok(mut visibility) = visibility.get_mut(texture_map.sprite) {
+        *visibility = if shown {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+    if !shown {
+        texture_map.written.fill(None);
+    }
+}
+
+/// Writes the texels of cells whose collapsed tile changed, or all of them
+/// for a new theme. The image is only touched when one did, since changing it
+/// uploads it to the GPU again. Image rows run top to bottom, grid rows
+/// bottom to top.
+fn sync_texels(
+    theme: Res<Theme>,
+    grid: Res<WfcGrid>,
+    texture_map: ResMut<TextureMap>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let TextureMap { image, written, .. } = texture_map.into_inner();
+    if theme.is_changed() {
+        written.fill(None);
+    }
+    let shown = |cell: &Cell| Some(cell.collapsed.then(|| cell.possible[0]));
+    if grid
+        .iter()
+        .all(|(x, y, cell)| written[y * GRID_W + x] == shown(cell))
+    {
+        return;
+    }
+    let Some(image) = images.get_mut(&*image) else {
+        return;
+    };
+    for (x, y, cell) in grid.iter() {
+        let texel = &mut written[y * GRID_W + x];
+        if *texel == shown(cell) {
+            continue;
+        }
+        *texel = shown(cell);
+        let color = texel
+            .flatten()
+            .map_or(UNCOLLAPSED_COLOR, |tile| theme.color(tile));
+        let _ = image.set_color_at(x as u32, (GRID_H - 1 - y) as u32, color);
+    }
+}
@@ -0,0 +1,147 @@
+use bevy::prelude::*;
+
+use crate::keybindings::{Action, Keybindings};
+use crate::variation::TileVariation;
+use crate::{Tile, TileType, WfcGrid, collapse_step};
+
+/// One set of tile colors.
+pub struct Palette {
+    pub name: &'static str,
+    pub sand: Color,
+    pub water: Color,
+    pub grass: Color,
+    pub wall: Color,
+    pub floor: Color,
+    pub door: Color,
+    pub corridor: Color,
+    pub stairs_up: Color,
+    pub stairs_down: Color,
+}
+
+impl Palette {
+    pub fn color(&self, tile: TileType) -> Color {
+        match tile {
+            TileType::Sand => self.sand,
+            TileType::Water => self.water,
+            TileType::Grass => self.grass,
+            TileType::Wall => self.wall,
+            TileType::Floor => self.floor,
+            TileType::Door => self.door,
+            TileType::Corridor => self.corridor,
+            TileType::StairsUp => self.stairs_up,
+            TileType::StairsDown => self.stairs_down,
+        }
+    }
+}
+
+/// Built-in palettes. The color-blind friendly ones are drawn from the
+/// Okabe-Ito and Tol sets and keep the tiles of a ruleset apart by lightness
+/// as well as hue.
+pub const PALETTES: [Palette; 4] = [
+    Palette {
+        name: "Classic",
+        sand: Color::srgb(0.9, 0.8, 0.5),
+        water: Color::srgb(0.2, 0.4, 0.9),
+        grass: Color::srgb(0.2, 0.8, 0.3),
+        wall: Color::srgb(0.3, 0.28, 0.3),
+        floor: Color::srgb(0.65, 0.58, 0.48),
+        door: Color::srgb(0.6, 0.32, 0.1),
+        corridor: Color::srgb(0.45, 0.45, 0.5),
+        stairs_up: Color::srgb(0.85, 0.8, 0.55),
+        stairs_down: Color::srgb(0.25, 0.2, 0.15),
+    },
+    Palette {
+        name: "Deuteranopia",
+        sand: Color::srgb(0.941, 0.894, 0.259),
+        water: Color::srgb(0.0, 0.447, 0.698),
+        grass: Color::srgb(0.0, 0.620, 0.451),
+        wall: Color::srgb(0.0, 0.0, 0.0),
+        floor: Color::srgb(0.8, 0.475, 0.655),
+        door: Color::srgb(0.835, 0.369, 0.0),
+        corridor: Color::srgb(0.337, 0.706, 0.914),
+        stairs_up: Color::srgb(1.0, 1.0, 1.0),
+        stairs_down: Color::srgb(0.902, 0.624, 0.0),
+    },
+    Palette {
+        name: "Protanopia",
+        sand: Color::srgb(0.902, 0.624, 0.0),
+        water: Color::srgb(0.337, 0.706, 0.914),
+        grass: Color::srgb(0.067, 0.467, 0.2),
+        wall: Color::srgb(0.2, 0.133, 0.533),
+        floor: Color::srgb(0.867, 0.8, 0.467),
+        door: Color::srgb(0.533, 0.133, 0.333),
+        corridor: Color::srgb(0.533, 0.8, 0.933),
+        stairs_up: Color::srgb(1.0, 1.0, 1.0),
+        stairs_down: Color::srgb(0.533, 0.133, 0.333),
+    },
+    Palette {
+        name: "Grayscale",
+        sand: Color::srgb(0.85, 0.85, 0.85),
+        water: Color::srgb(0.2, 0.2, 0.2),
+        grass: Color::srgb(0.5, 0.5, 0.5),
+        wall: Color::srgb(0.1, 0.1, 0.1),
+        floor: Color::srgb(0.75, 0.75, 0.75),
+        door: Color::srgb(0.4, 0.4, 0.4),
+        corridor: Color::srgb(0.6, 0.6, 0.6),
+        stairs_up: Color::srgb(1.0, 1.0, 1.0),
+        stairs_down: Color::srgb(0.3, 0.3, 0.3),
+    },
+];
+
+/// The active palette. Cycled with `H`.
+#[derive(Resource, Default)]
+pub struct Theme {
+    index: usize,
+}
+
+impl Theme {
+    pub fn palette(&self) -> &'static Palette {
+        &PALETTES[self.index]
+    }
+
+    pub fn color(&self, tile: TileType) -> Color {
+        self.palette().color(tile)
+    }
+}
+
+pub struct ThemePlugin;
+
+impl Plugin for ThemePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Theme>().add_systems(
+            Update,
+            (
+                cycle_theme.before(collapse_step),
+                recolor_tiles
+                    .run_if(resource_changed::<Theme>)
+                    .after(collapse_step),
+            ),
+        );
+    }
+}
+
+fn cycle_theme(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keybindings: Res<Keybindings>,
+    mut theme: ResMut<Theme>,
+) {
+    if keybindings.just_pressed(Action::CycleTheme, &keyboard_input) {
+        theme.index = (theme.index + 1) % PALETTES.len();
+        info!("theme: {}", theme.palette().name);
+    }
+}
+
+fn recolor_tiles(
+    theme: Res<Theme>,
+    grid: Res<WfcGrid>,
+    mut tiles: Query<(&Tile, &mut Sprite, Option<&TileVariation>)>,
+) {
+    for (tile, mut sprite, variation) in tiles.iter_mut() {
+        let tile = grid.cell(tile.x, tile.y);
+        if !tile.collapsed {
+            continue;
+        }
+        let color = theme.color(tile.possible[0]);
+        sprite.color = variation.map_or(color, |v| v.tint(color));
+    }
+}
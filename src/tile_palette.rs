@@ -0,0 +1,128 @@
+use bevy::prelude::*;
+
+use crate::TileType;
+use crate::keybindings::{Action, Keybindings};
+use crate::rules::Rules;
+use crate::seed_field::SeedEntry;
+use crate::theme::Theme;
+
+const PALETTE_MARGIN: f32 = 8.0;
+const SWATCH_PX: f32 = 24.0;
+const BORDER_PX: f32 = 2.0;
+const LABEL_FONT_SIZE: f32 = 12.0;
+const SELECTED_BORDER: Color = Color::WHITE;
+const UNSELECTED_BORDER: Color = Color::srgba(0.0, 0.0, 0.0, 0.6);
+
+/// Palette slots that have a selection binding.
+const SLOTS: usize = 9;
+
+/// Tile picked in the on-screen palette with the number keys, used by tools
+/// that place tiles by hand. Pressing the selected tile's key again clears it.
+#[derive(Resource, Default)]
+pub struct SelectedTile(pub Option<TileType>);
+
+/// Palette entry for the tile at this position among the ruleset's tiles,
+/// hidden when the ruleset has fewer.
+#[derive(Component)]
+struct PaletteSwatch(usize);
+
+pub struct TilePalettePlugin;
+
+impl Plugin for TilePalettePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SelectedTile>()
+            .add_systems(Startup, spawn_palette)
+            .add_systems(
+                Update,
+                (
+                    select_tile,
+                    update_palette.run_if(
+                        resource_changed::<SelectedTile>
+                            .or(resource_changed::<Theme>)
+                            .or(resource_changed::<Rules>),
+                    ),
+                )
+                    .chain(),
+            );
+    }
+}
+
+fn spawn_palette(mut commands: Commands, keybindings: Res<Keybindings>) {
+    commands
+        .spawn(Node {
+            position_type: PositionType::Absolute,
+            left: Val::Px(PALETTE_MARGIN),
+            top: Val::Px(PALETTE_MARGIN),
+            column_gap: Val::Px(4.0),
+            ..default()
+        })
+        .with_children(|parent| {
+            for i in 0..SLOTS {
+                parent.spawn((
+                    PaletteSwatch(i),
+                    Node {
+                        width: Val::Px(SWATCH_PX),
+                        height: Val::Px(SWATCH_PX),
+                        border: UiRect::all(Val::Px(BORDER_PX)),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    BorderColor::all(UNSELECTED_BORDER),
+                    BackgroundColor(Color::NONE),
+                    children![(
+                        Text::new(keybindings.describe(Action::SelectTile(i + 1))),
+                        TextFont::from_font_size(LABEL_FONT_SIZE),
+                        TextShadow::default(),
+                    )],
+                ));
+            }
+        });
+}
+
+fn select_tile(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keybindings: Res<Keybindings>,
+    seed_entry: Res<SeedEntry>,
+    rules: Res<Rules>,
+    mut selected: ResMut<SelectedTile>,
+) {
+    // A new ruleset may not have the selected tile.
+    if rules.is_changed() && selected.0.is_some_and(|tile| !rules.tiles().contains(tile)) {
+        selected.0 = None;
+    }
+    if seed_entry.editing {
+        return;
+    }
+    for (i, tile) in rules.tiles().iter().take(SLOTS).enumerate() {
+        if keybindings.just_pressed(Action::SelectTile(i + 1), &keyboard_input) {
+            selected.0 = (selected.0 != Some(tile)).then_some(tile);
+        }
+    }
+}
+
+fn update_palette(
+    selected: Res<SelectedTile>,
+    theme: Res<Theme>,
+    rules: Res<Rules>,
+    mut swatches: Query<(
+        &PaletteSwatch,
+        &mut Node,
+        &mut BackgroundColor,
+        &mut BorderColor,
+    )>,
+) {
+    for (swatch, mut node, mut background, mut border) in swatches.iter_mut() {
+        let Some(tile) = rules.tiles().iter().nth(swatch.0) else {
+            node.display = Display::None;
+            continue;
+        };
+        node.display = Display::Flex;
+        background.0 = theme.color(tile);
+        *border = BorderColor::all(if selected.0 == Some(tile) {
+            SELECTED_BORDER
+        } else {
+            UNSELECTED_BORDER
+        });
+    }
+}
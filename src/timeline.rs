@@ -0,0 +1,245 @@
+use bevy::prelude::*;
+use bevy::ui::RelativeCursorPosition;
+use serde::{Deserialize, Serialize};
+
+use crate::history::GridState;
+use crate::inspector::Provenance;
+use crate::rules::Rules;
+use crate::speed::slider_fraction;
+use crate::stepping::{Stepping, plan_steps};
+use crate::strategy::{Propagation, Strategy};
+use crate::{Seed, TileType, WfcGrid, collapse_step, force_collapse, regenerate};
+
+const SLIDER_WIDTH: f32 = 320.0;
+const SLIDER_HEIGHT: f32 = 10.0;
+const SLIDER_MARGIN: f32 = 8.0;
+const TRACK_COLOR: Color = Color::srgba(0.1, 0.1, 0.1, 0.8);
+const FILL_COLOR: Color = Color::srgb(0.4, 0.7, 1.0);
+
+/// One change to the grid, as kept in the timeline's event log.
+#[derive(Clone, Serialize, Deserialize)]
+pub enum TimelineEvent {
+    Collapse(usize, usize, TileType),
+    /// Scrubbed back to this many collapses before collapsing again.
+    Seek(usize),
+    /// Replaced by this state, e.g. by an undo, a reset or a loaded save.
+    Restore(GridState),
+}
+
+/// Every collapse of the current map in order, solver and manual alike, so
+/// the grid can be rebuilt at any point by replaying them from the start.
+#[derive(Resource, Default)]
+pub struct Timeline {
+    start: Option<GridState>,
+    collapses: Vec<(usize, usize, TileType)>,
+    /// Collapses currently applied to the grid. Below the end while scrubbed
+    /// back; the next collapse then discards the ones after it.
+    position: usize,
+    /// Everything that happened to the current map, including what rebasing
+    /// and scrubbing discard. This is what replays record.
+    log: Vec<TimelineEvent>,
+}
+
+impl Timeline {
+    pub fn record(&mut self, x: usize, y: usize, tile: TileType) {
+        if self.position < self.collapses.len() {
+            self.log.push(TimelineEvent::Seek(self.position));
+        }
+        self.collapses.truncate(self.position);
+        self.collapses.push((x, y, tile));
+        self.position = self.collapses.len();
+        self.log.push(TimelineEvent::Collapse(x, y, tile));
+    }
+
+    /// Starts over from `state` after an edit that isn't a collapse, such as
+    /// an undo or a reset, since the recorded collapses no longer replay to it.
+    pub fn rebase(&mut self, state: GridState) {
+        self.log.push(TimelineEvent::Restore(state.clone()));
+        self.start = Some(state);
+        self.collapses.clear();
+        self.position = 0;
+    }
+
+    pub fn log(&self) -> &[TimelineEvent] {
+        &self.log
+    }
+
+    /// Rebuilds the grid after `target` collapses. Going back replays from
+    /// the start; going forward only replays the missing collapses.
+    pub fn seek(
+        &mut self,
+        target: usize,
+        commands: &mut Commands,
+        rules: &Rules,
+        propagation: Propagation,
+        provenance: &mut Provenance,
+        grid: &mut WfcGrid,
+    ) {
+        let target = target.min(self.collapses.len());
+        if target < self.position {
+            let Some(start) = &self.start else {
+                return;
+            };
+            start.restore(commands, rules, provenance, grid);
+            // Logs of untouched cells may still name later collapses.
+            *provenance = Provenance::default();
+            let collapsed = grid.collapsed_grid();
+            for (x, y, cell) in grid.iter() {
+                if !cell.collapsed {
+                    provenance.rebuild(rules, &collapsed, x, y);
+                }
+            }
+            self.position = 0;
+        }
+
+        for i in self.position..target {
+            let (x, y, choice) = self.collapses[i];
+            force_collapse(
+                commands,
+                rules,
+                propagation,
+                provenance,
+                grid,
+                (x, y),
+                choice,
+            );
+        }
+        self.position = target;
+    }
+}
+
+#[derive(Component)]
+struct TimelineSlider;
+
+#[derive(Component)]
+struct TimelineFill;
+
+#[derive(Component)]
+struct TimelineLabel;
+
+pub struct TimelinePlugin;
+
+impl Plugin for TimelinePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Timeline>()
+            .add_systems(Startup, spawn_slider)
+            .add_systems(
+                Update,
+                (
+                    start_timeline
+                        .run_if(resource_changed::<Seed>)
+                        .after(regenerate),
+                    scrub.before(plan_steps),
+                )
+                    .chain()
+                    .before(collapse_step),
+            )
+            .add_systems(
+                Update,
+                update_slider
+                    .run_if(resource_changed::<Timeline>)
+                    .after(collapse_step),
+            );
+    }
+}
+
+fn spawn_slider(mut commands: Commands) {
+    commands
+        .spawn(Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(SLIDER_MARGIN),
+            width: Val::Percent(100.0),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            row_gap: Val::Px(4.0),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn((
+                TimelineLabel,
+                Text::default(),
+                TextFont::from_font_size(14.0),
+            ));
+            parent
+                .spawn((
+                    TimelineSlider,
+                    Button,
+                    RelativeCursorPosition::default(),
+                    Node {
+                        width: Val::Px(SLIDER_WIDTH),
+                        height: Val::Px(SLIDER_HEIGHT),
+                        ..default()
+                    },
+                    BackgroundColor(TRACK_COLOR),
+                ))
+                .with_child((
+                    TimelineFill,
+                    Node {
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    BackgroundColor(FILL_COLOR),
+                ));
+        });
+}
+
+/// The new grid is already spawned, so its starting state (with any locked
+/// cells) can be captured as is. The log starts over with it.
+fn start_timeline(mut timeline: ResMut<Timeline>, grid: Res<WfcGrid>) {
+    *timeline = Timeline::default();
+    timeline.rebase(GridState::capture(&grid));
+}
+
+/// Dragging the slider pauses generation and rebuilds the grid after the
+/// chosen number of collapses.
+#[allow(clippy::too_many_arguments)]
+fn scrub(
+    mut commands: Commands,
+    rules: Res<Rules>,
+    strategy: Res<Strategy>,
+    mut stepping: ResMut<Stepping>,
+    mut timeline: ResMut<Timeline>,
+    mut provenance: ResMut<Provenance>,
+    sliders: Query<(&Interaction, &RelativeCursorPosition), With<TimelineSlider>>,
+    mut grid: ResMut<WfcGrid>,
+) {
+    let len = timeline.collapses.len();
+    let Some(target) = sliders
+        .iter()
+        .find_map(|(interaction, cursor)| slider_fraction(interaction, cursor))
+        .map(|fraction| (fraction * len as f32).round() as usize)
+    else {
+        return;
+    };
+    if target == timeline.position {
+        return;
+    }
+    stepping.paused = true;
+    timeline.seek(
+        target,
+        &mut commands,
+        &rules,
+        strategy.propagation,
+        &mut provenance,
+        &mut grid,
+    );
+}
+
+fn update_slider(
+    timeline: Res<Timeline>,
+    mut fills: Query<&mut Node, With<TimelineFill>>,
+    mut labels: Query<&mut Text, With<TimelineLabel>>,
+) {
+    let len = timeline.collapses.len();
+    let fraction = if len == 0 {
+        0.0
+    } else {
+        timeline.position as f32 / len as f32
+    };
+    for mut node in fills.iter_mut() {
+        node.width = Val::Percent(fraction * 100.0);
+    }
+    for mut text in labels.iter_mut() {
+        text.0 = format!("collapse {} / {}", timeline.position, len);
+    }
+}
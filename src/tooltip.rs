@@ -0,0 +1,81 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::camera::cursor_world_position;
+use crate::{Cell, WfcGrid, cell_at};
+
+const TOOLTIP_OFFSET: Vec2 = Vec2::new(16.0, 16.0);
+const TOOLTIP_FONT_SIZE: f32 = 14.0;
+const TOOLTIP_BACKGROUND: Color = Color::srgba(0.05, 0.05, 0.05, 0.85);
+
+#[derive(Component)]
+struct Tooltip;
+
+pub struct TooltipPlugin;
+
+impl Plugin for TooltipPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_tooltip)
+            .add_systems(Update, update_tooltip);
+    }
+}
+
+fn spawn_tooltip(mut commands: Commands) {
+    commands.spawn((
+        Tooltip,
+        Text::default(),
+        TextFont::from_font_size(TOOLTIP_FONT_SIZE),
+        Node {
+            position_type: PositionType::Absolute,
+            padding: UiRect::all(Val::Px(4.0)),
+            ..default()
+        },
+        BackgroundColor(TOOLTIP_BACKGROUND),
+        Visibility::Hidden,
+    ));
+}
+
+/// Shannon entropy in bits of a domain, with every tile equally likely.
+fn entropy(tile: &Cell) -> f32 {
+    (tile.possible.len() as f32).log2()
+}
+
+fn describe((x, y): (usize, usize), tile: &Cell) -> String {
+    let state = if tile.collapsed {
+        format!("collapsed: {:?}", tile.possible[0])
+    } else {
+        let names: Vec<String> = tile.possible.iter().map(|t| format!("{t:?}")).collect();
+        format!("possible: {}", names.join(", "))
+    };
+    format!("({x}, {y})\n{state}\nentropy: {:.2} bits", entropy(tile))
+}
+
+/// Follows the cursor and describes the cell under it, hiding when the
+/// cursor is off the grid or the 2D view is inactive.
+fn update_tooltip(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+    grid: Res<WfcGrid>,
+    mut tooltips: Query<(&mut Text, &mut Node, &mut Visibility), With<Tooltip>>,
+) {
+    let Ok((mut text, mut node, mut visibility)) = tooltips.single_mut() else {
+        return;
+    };
+    let Ok(window) = windows.single() else {
+        return;
+    };
+
+    let hovered = cursor_world_position(window, &cameras).and_then(cell_at);
+    let (Some(cell), Some(cursor)) = (hovered, window.cursor_position()) else {
+        visibility.set_if_neq(Visibility::Hidden);
+        return;
+    };
+
+    visibility.set_if_neq(Visibility::Inherited);
+    node.left = Val::Px(cursor.x + TOOLTIP_OFFSET.x);
+    node.top = Val::Px(cursor.y + TOOLTIP_OFFSET.y);
+    let description = describe(cell, grid.cell(cell.0, cell.1));
+    if text.0 != description {
+        text.0 = description;
+    }
+}
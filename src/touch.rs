@@ -0,0 +1,130 @@
+use bevy::input::InputSystems;
+use bevy::input::touch::Touch;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::camera::{MAX_ZOOM, MIN_ZOOM};
+
+/// Distance in logical pixels a finger may move and still count as a tap.
+const TAP_SLOP: f32 = 12.0;
+/// Holding a finger still this long acts as a right-click.
+const LONG_PRESS_SECS: f32 = 0.5;
+
+/// Touch gestures mapped onto the mouse and camera controls: a tap is a
+/// left-click, a long press a right-click, one-finger drags pan and pinches
+/// zoom. Clicks are emulated by moving the window's cursor to the touch and
+/// pressing the button for a frame, so every click tool works unchanged.
+#[derive(Resource, Default)]
+struct TouchGesture {
+    /// When the first finger of the gesture went down.
+    started_secs: f32,
+    /// Set once the gesture turned into a drag, pinch or long press, so
+    /// lifting the finger is no longer a tap.
+    handled: bool,
+    /// Emulated button to release on the next frame.
+    pressed: Option<MouseButton>,
+}
+
+pub struct TouchInputPlugin;
+
+impl Plugin for TouchInputPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TouchGesture>().add_systems(
+            PreUpdate,
+            (emulate_clicks, pan_and_zoom).chain().after(InputSystems),
+        );
+    }
+}
+
+fn emulate_clicks(
+    time: Res<Time>,
+    touches: Res<Touches>,
+    mut gesture: ResMut<TouchGesture>,
+    mut mouse: ResMut<ButtonInput<MouseButton>>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if let Some(button) = gesture.pressed.take() {
+        mouse.release(button);
+    }
+    if touches.iter_just_pressed().next().is_some() && touches.iter().count() == 1 {
+        gesture.started_secs = time.elapsed_secs();
+        gesture.handled = false;
+    }
+
+    let held = touches.iter().count();
+    let moved = |touch: &Touch| touch.distance().length() > TAP_SLOP;
+    if held > 1 || touches.iter().any(moved) {
+        gesture.handled = true;
+    }
+
+    let long_press = held == 1
+        && !gesture.handled
+        && time.elapsed_secs() - gesture.started_secs >= LONG_PRESS_SECS;
+    let click = if long_press {
+        gesture.handled = true;
+        touches
+            .iter()
+            .next()
+            .map(|touch| (touch.position(), MouseButton::Right))
+    } else if held == 0 && !gesture.handled {
+        gesture.handled = true;
+        touches
+            .iter_just_released()
+            .next()
+            .map(|touch| (touch.position(), MouseButton::Left))
+    } else {
+        None
+    };
+
+    let Some((position, button)) = click else {
+        return;
+    };
+    let Ok(mut window) = windows.single_mut() else {
+        return;
+    };
+    // Bypassed so the cursor isn't warped there, which touch-only platforms
+    // can't do anyway.
+    window
+        .bypass_change_detection()
+        .set_cursor_position(Some(position));
+    mouse.press(button);
+    gesture.pressed = Some(button);
+}
+
+fn pan_and_zoom(
+    touches: Res<Touches>,
+    mut cameras: Query<
+        (&Camera, &GlobalTransform, &mut Transform, &mut Projection),
+        With<Camera2d>,
+    >,
+) {
+    let held: Vec<&Touch> = touches.iter().collect();
+    for (camera, global, mut transform, mut projection) in cameras.iter_mut() {
+        if !camera.is_active {
+            continue;
+        }
+        match held.as_slice() {
+            [touch] if touch.distance().length() > TAP_SLOP => {
+                let to_world = |position| camera.viewport_to_world_2d(global, position).ok();
+                if let (Some(previous), Some(current)) = (
+                    to_world(touch.previous_position()),
+                    to_world(touch.position()),
+                ) {
+                    // Keeps the world point under the finger.
+                    transform.translation -= (current - previous).extend(0.0);
+                }
+            }
+            [a, b] => {
+                let previous = a.previous_position().distance(b.previous_position());
+                let current = a.position().distance(b.position());
+                if let Projection::Orthographic(ortho) = projection.as_mut()
+                    && previous > 0.0
+                    && current > 0.0
+                {
+                    ortho.scale = (ortho.scale * previous / current).clamp(MIN_ZOOM, MAX_ZOOM);
+                }
+            }
+            _ => {}
+        }
+    }
+}
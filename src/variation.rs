@@ -0,0 +1,131 @@
+use bevy::prelude::*;
+
+use crate::{Seed, Tile, WfcGrid, collapse_step};
+
+/// Largest brightness change applied to a tile, either way.
+const MAX_TINT: f32 = 0.06;
+
+/// Deterministic per-cell look, derived from the seed and the cell
+/// coordinate so the same map always looks the same.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct TileVariation {
+    brightness: f32,
+    /// Counter-clockwise quarter turns.
+    quarter_turns: u8,
+    flip_x: bool,
+}
+
+impl TileVariation {
+    fn new(seed: u64, x: usize, y: usize) -> Self {
+        let hash = cell_hash(seed, 0, x, y);
+        Self {
+            brightness: 1.0 + (unit(hash) * 2.0 - 1.0) * MAX_TINT,
+            quarter_turns: (hash & 0b11) as u8,
+            flip_x: hash & 0b100 != 0,
+        }
+    }
+
+    pub fn tint(&self, color: Color) -> Color {
+        let srgba = color.to_srgba();
+        Color::srgba(
+            (srgba.red * self.brightness).clamp(0.0, 1.0),
+            (srgba.green * self.brightness).clamp(0.0, 1.0),
+            (srgba.blue * self.brightness).clamp(0.0, 1.0),
+            srgba.alpha,
+        )
+    }
+
+    /// Maps an 8-bit neighbor mask (N, E, S, W, then NE, SE, SW, NW) from
+    /// world directions into this tile's flipped and rotated local frame, so
+    /// the chosen autotile variant still lines up with its neighbors.
+    pub fn local_mask(&self, world: u8) -> u8 {
+        let turns = self.quarter_turns as usize;
+        let mut local = 0;
+        for side in 0..4 {
+            // A local side ends up at world side `rotate(flip(side))`.
+            let cardinal = if self.flip_x { (4 - side) % 4 } else { side };
+            let corner = if self.flip_x { 3 - side } else { side };
+            if world & (1 << ((cardinal + 3 * turns) % 4)) != 0 {
+                local |= 1 << side;
+            }
+            if world & (1 << (4 + (corner + 3 * turns) % 4)) != 0 {
+                local |= 1 << (4 + side);
+            }
+        }
+        local
+    }
+}
+
+/// Hash of a cell for a map seed. Each use passes its own `salt`, so what it
+/// derives from the hash is independent of what others do.
+pub fn cell_hash(seed: u64, salt: u64, x: usize, y: usize) -> u64 {
+    splitmix64(seed ^ salt ^ ((x as u64) << 32 | y as u64))
+}
+
+/// The top 24 bits of a hash as a fraction in `0..1`.
+pub fn unit(hash: u64) -> f32 {
+    (hash >> 40) as f32 / (1u64 << 24) as f32
+}
+
+fn splitmix64(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+pub struct VariationPlugin;
+
+impl Plugin for VariationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                assign_variations,
+                orient_collapsed.run_if(resource_changed::<WfcGrid>),
+            )
+                .chain()
+                .after(collapse_step),
+        );
+    }
+}
+
+/// Rolls the variation of new tiles, and of tiles regeneration reused for a
+/// new seed.
+fn assign_variations(
+    mut commands: Commands,
+    seed: Res<Seed>,
+    tiles: Query<(Entity, &Tile), Changed<Tile>>,
+) {
+    for (entity, tile) in tiles.iter() {
+        commands
+            .entity(entity)
+            .insert(TileVariation::new(seed.0, tile.x, tile.y));
+    }
+}
+
+/// Uncollapsed cells stay upright so their possibility swatches read
+/// left to right; the rotation and flip only apply once a tile is chosen.
+fn orient_collapsed(
+    grid: Res<WfcGrid>,
+    mut tiles: Query<(&Tile, &TileVariation, &mut Sprite, &mut Transform)>,
+) {
+    for (tile, variation, mut sprite, mut transform) in tiles.iter_mut() {
+        let (flip_x, rotation) = if grid.cell(tile.x, tile.y).collapsed {
+            (
+                variation.flip_x,
+                Quat::from_rotation_z(variation.quarter_turns as f32 * std::f32::consts::FRAC_PI_2),
+            )
+        } else {
+            (false, Quat::IDENTITY)
+        };
+        // Unchanged cells are left alone so their transforms aren't
+        // propagated again.
+        if sprite.flip_x != flip_x {
+            sprite.flip_x = flip_x;
+        }
+        if transform.rotation != rotation {
+            transform.rotation = rotation;
+        }
+    }
+}
@@ -0,0 +1,277 @@
+use std::collections::HashSet;
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::camera::visibility::RenderLayers;
+use bevy::input::mouse::{AccumulatedMouseMotion, AccumulatedMouseScroll, MouseScrollUnit};
+use bevy::prelude::*;
+
+use crate::keybindings::{Action, Keybindings};
+use crate::theme::Theme;
+use crate::{GRID_H, GRID_W, TILE_SIZE, Tile, TileType, WfcGrid, cell_center, collapse_step};
+
+/// Render layer for the 3D scene, so tile sprites and gizmos stay out of it.
+const LAYER_3D: usize = 1;
+const ORBIT_SENSITIVITY: f32 = 0.005;
+const ZOOM_PER_LINE: f32 = 0.1;
+const PIXELS_PER_LINE: f32 = 20.0;
+const MIN_PITCH: f32 = 0.1;
+const MAX_PITCH: f32 = FRAC_PI_2 - 0.05;
+
+/// Whether the grid is shown as sprites or as 3D geometry. Toggled with `V`.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ViewMode {
+    #[default]
+    TwoD,
+    ThreeD,
+}
+
+#[derive(Resource)]
+struct Orbit {
+    yaw: f32,
+    pitch: f32,
+    distance: f32,
+}
+
+impl Default for Orbit {
+    fn default() -> Self {
+        Self {
+            yaw: 0.0,
+            pitch: 0.9,
+            distance: GRID_W.max(GRID_H) as f32 * TILE_SIZE * 1.5,
+        }
+    }
+}
+
+impl Orbit {
+    fn min_distance() -> f32 {
+        TILE_SIZE * 2.0
+    }
+
+    fn max_distance() -> f32 {
+        GRID_W.max(GRID_H) as f32 * TILE_SIZE * 5.0
+    }
+}
+
+#[derive(Resource)]
+struct VoxelAssets {
+    by_type: Vec<(TileType, Handle<Mesh>, Handle<StandardMaterial>)>,
+}
+
+/// Anything spawned for the 3D view, despawned when leaving it.
+#[derive(Component)]
+struct View3dEntity;
+
+#[derive(Component)]
+struct Voxel {
+    tile: Entity,
+    kind: TileType,
+}
+
+pub struct View3dPlugin;
+
+impl Plugin for View3dPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ViewMode>()
+            .init_resource::<Orbit>()
+            .add_systems(Startup, build_voxel_assets)
+            .add_systems(
+                Update,
+                (
+                    toggle_view,
+                    recolor_voxels.run_if(resource_changed::<Theme>),
+                    leave_3d.run_if(resource_changed::<ViewMode>),
+                    (sync_scene, orbit_camera)
+                        .chain()
+                        .run_if(resource_equals(ViewMode::ThreeD)),
+                )
+                    .chain()
+                    .after(collapse_step),
+            );
+    }
+}
+
+/// Height of a tile's box, in pixels like the rest of the scene.
+pub fn tile_height(tile: TileType) -> f32 {
+    match tile {
+        TileType::Water => TILE_SIZE * 0.15,
+        TileType::Sand => TILE_SIZE * 0.35,
+        TileType::Grass => TILE_SIZE * 0.7,
+        TileType::Floor | TileType::Corridor => TILE_SIZE * 0.2,
+        TileType::Door => TILE_SIZE * 0.25,
+        TileType::StairsDown => TILE_SIZE * 0.1,
+        TileType::StairsUp => TILE_SIZE * 0.4,
+        TileType::Wall => TILE_SIZE,
+    }
+}
+
+fn build_voxel_assets(
+    mut commands: Commands,
+    theme: Res<Theme>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let by_type = TileType::ALL
+        .iter()
+        .map(|&tile| {
+            let mesh = meshes.add(Cuboid::new(TILE_SIZE, tile_height(tile), TILE_SIZE));
+            let material = materials.add(StandardMaterial {
+                base_color: theme.color(tile),
+                perceptual_roughness: if tile == TileType::Water { 0.1 } else { 0.9 },
+                ..default()
+            });
+            (tile, mesh, material)
+        })
+        .collect();
+    commands.insert_resource(VoxelAssets { by_type });
+}
+
+fn recolor_voxels(
+    theme: Res<Theme>,
+    assets: Res<VoxelAssets>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for (tile, _, material) in &assets.by_type {
+        if let Some(material) = materials.get_mut(material) {
+            material.base_color = theme.color(*tile);
+        }
+    }
+}
+
+fn toggle_view(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keybindings: Res<Keybindings>,
+    mut mode: ResMut<ViewMode>,
+) {
+    if keybindings.just_pressed(Action::ToggleView3d, &keyboard_input) {
+        *mode = match *mode {
+            ViewMode::TwoD => ViewMode::ThreeD,
+            ViewMode::ThreeD => ViewMode::TwoD,
+        };
+    }
+}
+
+fn leave_3d(
+    mut commands: Commands,
+    mode: Res<ViewMode>,
+    spawned: Query<Entity, With<View3dEntity>>,
+    mut cameras_2d: Query<&mut Camera, With<Camera2d>>,
+) {
+    if *mode != ViewMode::TwoD {
+        return;
+    }
+    for entity in spawned.iter() {
+        commands.entity(entity).despawn();
+    }
+    for mut camera in cameras_2d.iter_mut() {
+        camera.is_active = true;
+    }
+}
+
+/// Keeps the 3D scene in step with the grid: spawns the camera and light if
+/// missing, adds geometry for newly collapsed tiles and drops geometry for
+/// tiles that no longer exist or no longer hold the voxel's tile type.
+fn sync_scene(
+    mut commands: Commands,
+    assets: Res<VoxelAssets>,
+    grid: Res<WfcGrid>,
+    tiles: Query<(Entity, &Tile)>,
+    voxels: Query<(Entity, &Voxel)>,
+    cameras_3d: Query<(), (With<Camera3d>, With<View3dEntity>)>,
+    mut cameras_2d: Query<&mut Camera, With<Camera2d>>,
+) {
+    let layer = RenderLayers::layer(LAYER_3D);
+
+    if cameras_3d.is_empty() {
+        commands.spawn((
+            View3dEntity,
+            Camera3d::default(),
+            Camera {
+                order: 1,
+                ..default()
+            },
+            Transform::default(),
+            layer.clone(),
+        ));
+        commands.spawn((
+            View3dEntity,
+            DirectionalLight {
+                shadows_enabled: true,
+                ..default()
+            },
+            Transform::from_xyz(1.0, 2.0, 0.5).looking_at(Vec3::ZERO, Vec3::Y),
+            layer.clone(),
+        ));
+    }
+    for mut camera in cameras_2d.iter_mut() {
+        camera.is_active = false;
+    }
+
+    let mut voxelized = HashSet::new();
+    for (voxel_entity, voxel) in voxels.iter() {
+        let current = tiles.get(voxel.tile).is_ok_and(|(_, tile)| {
+            let tile = grid.cell(tile.x, tile.y);
+            tile.collapsed && tile.possible[0] == voxel.kind
+        });
+        if current {
+            voxelized.insert(voxel.tile);
+        } else {
+            commands.entity(voxel_entity).despawn();
+        }
+    }
+
+    for (tile_entity, tile) in tiles.iter() {
+        let cell = grid.cell(tile.x, tile.y);
+        if !cell.collapsed || voxelized.contains(&tile_entity) {
+            continue;
+        }
+        let tile_type = cell.possible[0];
+        let Some((_, mesh, material)) = assets.by_type.iter().find(|(t, _, _)| *t == tile_type)
+        else {
+            continue;
+        };
+
+        let center = cell_center(tile.x, tile.y);
+        commands.spawn((
+            View3dEntity,
+            Voxel {
+                tile: tile_entity,
+                kind: tile_type,
+            },
+            Mesh3d(mesh.clone()),
+            MeshMaterial3d(material.clone()),
+            Transform::from_xyz(center.x, tile_height(tile_type) / 2.0, -center.y),
+            layer.clone(),
+        ));
+    }
+}
+
+/// Left-drag orbits around the grid center, the mouse wheel moves closer or
+/// further away.
+fn orbit_camera(
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    motion: Res<AccumulatedMouseMotion>,
+    scroll: Res<AccumulatedMouseScroll>,
+    mut orbit: ResMut<Orbit>,
+    mut cameras: Query<&mut Transform, (With<Camera3d>, With<View3dEntity>)>,
+) {
+    if mouse_buttons.pressed(MouseButton::Left) {
+        orbit.yaw -= motion.delta.x * ORBIT_SENSITIVITY;
+        orbit.pitch =
+            (orbit.pitch + motion.delta.y * ORBIT_SENSITIVITY).clamp(MIN_PITCH, MAX_PITCH);
+    }
+
+    let lines = match scroll.unit {
+        MouseScrollUnit::Line => scroll.delta.y,
+        MouseScrollUnit::Pixel => scroll.delta.y / PIXELS_PER_LINE,
+    };
+    if lines != 0.0 {
+        orbit.distance = (orbit.distance * (1.0 - ZOOM_PER_LINE).powf(lines))
+            .clamp(Orbit::min_distance(), Orbit::max_distance());
+    }
+
+    let rotation = Quat::from_euler(EulerRot::YXZ, orbit.yaw, -orbit.pitch, 0.0);
+    for mut transform in cameras.iter_mut() {
+        transform.translation = rotation * Vec3::new(0.0, 0.0, orbit.distance);
+        transform.look_at(Vec3::ZERO, Vec3::Y);
+    }
+}
@@ -0,0 +1,207 @@
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+
+use crate::TileType;
+use crate::rules::{Rules, tileset_path};
+use crate::sample::{nearest_tile, read_png};
+use crate::theme::Palette;
+
+/// Tiles in a complete set over two terrains.
+const SET_SIZE: usize = 16;
+/// Smallest tile, in pixels, whose sockets can be told apart.
+const MIN_TILE_PX: u32 = 4;
+/// First words of every ruleset file the importer writes.
+const IMPORTED: &str = "// Imported from";
+
+/// How a Wang sheet encodes each tile's sockets, after the layouts of cr31's
+/// Wang tile pages: tile `i` of the sheet, counting in reading order, has the
+/// second terrain on the sockets whose bits are set in `i`. Bit 0 is the
+/// north edge or north-east corner and the others go clockwise.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum WangLayout {
+    /// Terrain at the corners: NE, SE, SW, NW.
+    #[default]
+    Corner,
+    /// Terrain at the edges: N, E, S, W.
+    Edge,
+}
+
+impl WangLayout {
+    fn name(self) -> &'static str {
+        match self {
+            WangLayout::Corner => "2-corner",
+            WangLayout::Edge => "2-edge",
+        }
+    }
+
+    /// Pixel where socket `bit` of a `size` pixel tile is sampled, inset so
+    /// antialiased borders don't blur the color.
+    fn socket_pixel(self, bit: usize, size: u32) -> (u32, u32) {
+        let near = size / 8;
+        let far = size - 1 - near;
+        let mid = size / 2;
+        match (self, bit) {
+            (WangLayout::Corner, 0) => (far, near),
+            (WangLayout::Corner, 1) => (far, far),
+            (WangLayout::Corner, 2) => (near, far),
+            (WangLayout::Corner, _) => (near, near),
+            (WangLayout::Edge, 0) => (mid, near),
+            (WangLayout::Edge, 1) => (far, mid),
+            (WangLayout::Edge, 2) => (mid, far),
+            (WangLayout::Edge, _) => (near, mid),
+        }
+    }
+}
+
+/// Columns of tiles and the tile size of a sheet: a 4x4 grid, a single row
+/// or a single column.
+fn arrangement(width: u32, height: u32) -> Result<(u32, u32), String> {
+    let set = SET_SIZE as u32;
+    let (columns, size) = if width == height && width.is_multiple_of(4) {
+        (4, width / 4)
+    } else if width == height * set {
+        (set, height)
+    } else if height == width * set {
+        (1, width)
+    } else {
+        return Err(format!(
+            "a {width}x{height} sheet isn't a 4x4 grid, a row or a column of {SET_SIZE} square tiles"
+        ));
+    };
+    if size < MIN_TILE_PX {
+        return Err(format!("tiles must be at least {MIN_TILE_PX} pixels wide"));
+    }
+    Ok((columns, size))
+}
+
+fn rgb(color: Color) -> Vec3 {
+    let color = color.to_srgba();
+    Vec3::new(color.red, color.green, color.blue)
+}
+
+/// Slices a Wang tile sheet and writes the ruleset it implies to
+/// `assets/tilesets/<sheet name>.ron`, returning the ruleset and the file.
+///
+/// Each terrain becomes the tile type with the nearest palette color. Two
+/// terrains may neighbor each other if some tile has them on adjacent
+/// sockets, since that's where they meet; every terrain may neighbor itself,
+/// as tiles only join on matching sockets. Terrains are weighted by how many
+/// sockets they cover. Fully transparent tiles count as missing from the set.
+pub fn import_wang(
+    path: &Path,
+    layout: WangLayout,
+    palette: &Palette,
+) -> Result<(Rules, PathBuf), String> {
+    let image = read_png(path)?;
+    let (columns, size) = arrangement(image.width(), image.height())?;
+
+    let mut sheet: Vec<(usize, [Vec3; 4])> = Vec::with_capacity(SET_SIZE);
+    for index in 0..SET_SIZE {
+        let x0 = index as u32 % columns * size;
+        let y0 = index as u32 / columns * size;
+        let center = image
+            .get_color_at(x0 + size / 2, y0 + size / 2)
+            .map_err(|err| err.to_string())?;
+        if center.alpha() < 0.5 {
+            continue;
+        }
+        let mut sockets = [Vec3::ZERO; 4];
+        for (bit, socket) in sockets.iter_mut().enumerate() {
+            let (x, y) = layout.socket_pixel(bit, size);
+            let color = image
+                .get_color_at(x0 + x, y0 + y)
+                .map_err(|err| err.to_string())?;
+            *socket = rgb(color);
+        }
+        sheet.push((index, sockets));
+    }
+    let second = |index: usize, bit: usize| index & (1 << bit) != 0;
+
+    // Each terrain's color is the average of the sockets the layout gives it.
+    let mut sums = [Vec3::ZERO; 2];
+    let mut counts = [0usize; 2];
+    for (index, sockets) in &sheet {
+        for (bit, &color) in sockets.iter().enumerate() {
+            let terrain = usize::from(second(*index, bit));
+            sums[terrain] += color;
+            counts[terrain] += 1;
+        }
+    }
+    if counts.contains(&0) {
+        return Err("the sheet needs tiles of both terrains".to_string());
+    }
+    let colors = [0, 1].map(|terrain| sums[terrain] / counts[terrain] as f32);
+    for (index, sockets) in &sheet {
+        for (bit, &color) in sockets.iter().enumerate() {
+            let terrain = usize::from(second(*index, bit));
+            if color.distance_squared(colors[terrain]) > color.distance_squared(colors[1 - terrain])
+            {
+                return Err(format!(
+                    "tile {index} doesn't follow the {} layout",
+                    layout.name()
+                ));
+            }
+        }
+    }
+    let terrains =
+        colors.map(|color| nearest_tile(palette, Color::srgb(color.x, color.y, color.z)));
+    if terrains[0] == terrains[1] {
+        return Err(format!(
+            "both terrains are closest to {:?} in the palette",
+            terrains[0]
+        ));
+    }
+
+    let mut allowed = [[false; TileType::ALL.len()]; TileType::ALL.len()];
+    for terrain in terrains {
+        allowed[terrain.index()][terrain.index()] = true;
+    }
+    for (index, _) in &sheet {
+        for bit in 0..4 {
+            let a = terrains[usize::from(second(*index, bit))];
+            let b = terrains[usize::from(second(*index, (bit + 1) % 4))];
+            allowed[a.index()][b.index()] = true;
+            allowed[b.index()][a.index()] = true;
+        }
+    }
+
+    let name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mut text = format!(
+        "{IMPORTED} {}, a {} Wang set with {} of its {SET_SIZE} tiles.\n// Unused tile types get no neighbors and no weight.\n(\n    name: {name:?},\n    adjacency: {{\n",
+        path.display(),
+        layout.name(),
+        sheet.len(),
+    );
+    for tile in TileType::ALL {
+        let neighbors: Vec<String> = TileType::ALL
+            .into_iter()
+            .filter(|neighbor| allowed[tile.index()][neighbor.index()])
+            .map(|neighbor| format!("{neighbor:?}"))
+            .collect();
+        let _ = writeln!(text, "        {tile:?}: [{}],", neighbors.join(", "));
+    }
+    text.push_str("    },\n    weights: {\n");
+    let most = counts.into_iter().max().unwrap_or_default().max(1);
+    for tile in TileType::ALL {
+        let weight = terrains
+            .iter()
+            .position(|&terrain| terrain == tile)
+            .map_or(0.0, |terrain| counts[terrain] as f32 / most as f32);
+        let _ = writeln!(text, "        {tile:?}: {weight:?},");
+    }
+    text.push_str("    },\n)\n");
+
+    let file = tileset_path(&format!("{}.ron", name.to_lowercase()));
+    // Reimporting a sheet replaces its ruleset, but never a hand-written one.
+    if std::fs::read_to_string(&file).is_ok_and(|old| !old.starts_with(IMPORTED)) {
+        return Err(format!("{} already exists", file.display()));
+    }
+    std::fs::write(&file, text).map_err(|err| format!("{}: {err}", file.display()))?;
+    let rules = Rules::load(&file)?;
+    Ok((rules, file))
+}
@@ -0,0 +1,111 @@
+use bevy::prelude::*;
+use bevy::render::render_resource::{AsBindGroup, ShaderType};
+use bevy::shader::ShaderRef;
+use bevy::sprite_render::{AlphaMode2d, Material2d, Material2dPlugin};
+
+use crate::rules::Rules;
+use crate::{TILE_SIZE, Tile, TileMaterial, WfcGrid, collapse_step, layers};
+
+const WATER_SHADER: &str = "shaders/water.wgsl";
+
+/// Translucent animated highlights drawn over water tiles.
+#[derive(Asset, TypePath, AsBindGroup, Clone, Debug)]
+pub struct WaterMaterial {
+    #[uniform(0)]
+    params: WaterParams,
+}
+
+#[derive(ShaderType, Clone, Debug)]
+struct WaterParams {
+    highlight: LinearRgba,
+    /// x: wave frequency in world units, y: scroll speed.
+    wave: Vec4,
+}
+
+impl Material2d for WaterMaterial {
+    fn fragment_shader() -> ShaderRef {
+        WATER_SHADER.into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode2d {
+        AlphaMode2d::Blend
+    }
+}
+
+#[derive(Resource)]
+struct SurfaceAssets {
+    mesh: Handle<Mesh>,
+    water: Handle<WaterMaterial>,
+}
+
+/// Material overlay spawned for a tile.
+#[derive(Component)]
+struct HasSurface(Entity);
+
+pub struct WaterPlugin;
+
+impl Plugin for WaterPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(Material2dPlugin::<WaterMaterial>::default())
+            .add_systems(Startup, build_surface_assets)
+            .add_systems(
+                Update,
+                sync_surfaces
+                    .run_if(resource_changed::<WfcGrid>)
+                    .after(collapse_step),
+            );
+    }
+}
+
+fn build_surface_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<WaterMaterial>>,
+) {
+    commands.insert_resource(SurfaceAssets {
+        mesh: meshes.add(Rectangle::from_length(TILE_SIZE)),
+        water: materials.add(WaterMaterial {
+            params: WaterParams {
+                highlight: LinearRgba::new(0.8, 0.9, 1.0, 0.35),
+                wave: Vec4::new(0.08, 1.5, 0.0, 0.0),
+            },
+        }),
+    });
+}
+
+/// Attaches the ruleset's material for a tile as an overlay once it collapses,
+/// and drops it if the tile is un-collapsed or changes type.
+fn sync_surfaces(
+    mut commands: Commands,
+    assets: Res<SurfaceAssets>,
+    grid: Res<WfcGrid>,
+    rules: Res<Rules>,
+    tiles: Query<(Entity, &Tile, Option<&HasSurface>)>,
+) {
+    for (entity, tile, surface) in tiles.iter() {
+        let tile = grid.cell(tile.x, tile.y);
+        let material = if tile.collapsed {
+            rules.material(tile.possible[0])
+        } else {
+            TileMaterial::Flat
+        };
+        match (material, surface) {
+            (TileMaterial::Flat, None) | (TileMaterial::Water, Some(_)) => {}
+            (TileMaterial::Flat, Some(surface)) => {
+                commands.entity(surface.0).try_despawn();
+                commands.entity(entity).try_remove::<HasSurface>();
+            }
+            (TileMaterial::Water, None) => {
+                let overlay = commands
+                    .spawn((
+                        Mesh2d(assets.mesh.clone()),
+                        MeshMaterial2d(assets.water.clone()),
+                        Transform::from_xyz(0.0, 0.0, layers::SURFACE),
+                        ChildOf(entity),
+                    ))
+                    .id();
+                commands.entity(entity).insert(HasSurface(overlay));
+            }
+        }
+    }
+}
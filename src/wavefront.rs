@@ -0,0 +1,96 @@
+use bevy::prelude::*;
+
+use crate::keybindings::{Action, Keybindings};
+use crate::{TILE_SIZE, Tile, WfcGrid, collapse_step};
+
+const PULSE_SECONDS: f32 = 0.5;
+const PULSE_COLOR: Color = Color::srgb(1.0, 0.85, 0.1);
+
+/// Whether cells narrowed by propagation are outlined. Toggled with `P`.
+#[derive(Resource, Default)]
+pub struct Wavefront {
+    pub visible: bool,
+}
+
+/// Domain size seen last time the cell changed.
+#[derive(Component)]
+struct DomainSize(usize);
+
+#[derive(Component)]
+struct Pulse(Timer);
+
+pub struct WavefrontPlugin;
+
+impl Plugin for WavefrontPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Wavefront>().add_systems(
+            Update,
+            (
+                toggle_wavefront,
+                track_reductions
+                    .run_if(resource_changed::<WfcGrid>)
+                    .after(collapse_step),
+                draw_pulses.run_if(|wavefront: Res<Wavefront>| wavefront.visible),
+            )
+                .chain(),
+        );
+    }
+}
+
+fn toggle_wavefront(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keybindings: Res<Keybindings>,
+    mut wavefront: ResMut<Wavefront>,
+) {
+    if keybindings.just_pressed(Action::ToggleWavefront, &keyboard_input) {
+        wavefront.visible = !wavefront.visible;
+    }
+}
+
+/// Starts a pulse on every uncollapsed cell whose domain shrank this frame.
+fn track_reductions(
+    mut commands: Commands,
+    grid: Res<WfcGrid>,
+    mut tiles: Query<(Entity, &Tile, Option<&mut DomainSize>)>,
+) {
+    for (entity, tile, size) in tiles.iter_mut() {
+        let tile = grid.cell(tile.x, tile.y);
+        let current = tile.possible.len();
+        let Some(mut size) = size else {
+            commands.entity(entity).insert(DomainSize(current));
+            continue;
+        };
+
+        if current == size.0 {
+            continue;
+        }
+        if !tile.collapsed && current < size.0 {
+            commands
+                .entity(entity)
+                .insert(Pulse(Timer::from_seconds(PULSE_SECONDS, TimerMode::Once)));
+        }
+        size.0 = current;
+    }
+}
+
+fn draw_pulses(
+    mut commands: Commands,
+    mut gizmos: Gizmos,
+    time: Res<Time>,
+    mut pulses: Query<(Entity, &mut Pulse, &GlobalTransform)>,
+) {
+    for (entity, mut pulse, transform) in pulses.iter_mut() {
+        pulse.0.tick(time.delta());
+        if pulse.0.is_finished() {
+            commands.entity(entity).try_remove::<Pulse>();
+            continue;
+        }
+
+        let remaining = pulse.0.fraction_remaining();
+        gizmos.rect_2d(
+            Isometry2d::from_translation(transform.translation().truncate()),
+            Vec2::splat(TILE_SIZE * (1.0 - 0.2 * remaining)),
+            PULSE_COLOR.with_alpha(remaining),
+        );
+    }
+}
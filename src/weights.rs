@@ -0,0 +1,136 @@
+use bevy::prelude::*;
+use bevy::ui::RelativeCursorPosition;
+
+use crate::TileType;
+use crate::rules::Rules;
+use crate::speed::slider_fraction;
+
+/// Weight at the right end of a slider.
+const MAX_WEIGHT: f32 = 5.0;
+const SLIDER_WIDTH: f32 = 160.0;
+const SLIDER_HEIGHT: f32 = 10.0;
+const SLIDER_LEFT: f32 = 8.0;
+/// Above the speed slider.
+const SLIDER_BOTTOM: f32 = 48.0;
+const TRACK_COLOR: Color = Color::srgba(0.1, 0.1, 0.1, 0.8);
+const FILL_COLOR: Color = Color::srgb(0.9, 0.9, 0.9);
+
+/// Slider setting the weight of one tile type.
+#[derive(Component)]
+struct WeightSlider(TileType);
+
+#[derive(Component)]
+struct WeightFill(TileType);
+
+#[derive(Component)]
+struct WeightLabel(TileType);
+
+/// Label and slider of one tile type.
+#[derive(Component)]
+struct WeightRow(TileType);
+
+/// Sliders for the tile weights of the current rules. The solver reads the
+/// weights whenever it picks a tile, so changes steer the cells not yet
+/// collapsed without regenerating the map.
+pub struct WeightSlidersPlugin;
+
+impl Plugin for WeightSlidersPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_sliders).add_systems(
+            Update,
+            (
+                drag_slider,
+                update_sliders.run_if(resource_changed::<Rules>),
+            )
+                .chain(),
+        );
+    }
+}
+
+fn spawn_sliders(mut commands: Commands) {
+    commands
+        .spawn(Node {
+            position_type: PositionType::Absolute,
+            left: Val::Px(SLIDER_LEFT),
+            bottom: Val::Px(SLIDER_BOTTOM),
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(4.0),
+            ..default()
+        })
+        .with_children(|parent| {
+            for tile in TileType::ALL {
+                parent
+                    .spawn((
+                        WeightRow(tile),
+                        Node {
+                            flex_direction: FlexDirection::Column,
+                            row_gap: Val::Px(4.0),
+                            ..default()
+                        },
+                    ))
+                    .with_children(|row| {
+                        row.spawn((
+                            WeightLabel(tile),
+                            Text::default(),
+                            TextFont::from_font_size(14.0),
+                        ));
+                        row.spawn((
+                            WeightSlider(tile),
+                            Button,
+                            RelativeCursorPosition::default(),
+                            Node {
+                                width: Val::Px(SLIDER_WIDTH),
+                                height: Val::Px(SLIDER_HEIGHT),
+                                ..default()
+                            },
+                            BackgroundColor(TRACK_COLOR),
+                        ))
+                        .with_child((
+                            WeightFill(tile),
+                            Node {
+                                height: Val::Percent(100.0),
+                                ..default()
+                            },
+                            BackgroundColor(FILL_COLOR),
+                        ));
+                    });
+            }
+        });
+}
+
+fn drag_slider(
+    mut rules: ResMut<Rules>,
+    sliders: Query<(&Interaction, &RelativeCursorPosition, &WeightSlider)>,
+) {
+    for (interaction, cursor, slider) in sliders.iter() {
+        if let Some(fraction) = slider_fraction(interaction, cursor) {
+            let weight = fraction * MAX_WEIGHT;
+            if rules.weight(slider.0) != weight {
+                rules.set_weight(slider.0, weight);
+            }
+        }
+    }
+}
+
+/// Also runs when a ruleset or preset brings its own weights. Tiles the
+/// ruleset doesn't use have their sliders hidden.
+fn update_sliders(
+    rules: Res<Rules>,
+    mut rows: Query<(&mut Node, &WeightRow), Without<WeightFill>>,
+    mut fills: Query<(&mut Node, &WeightFill), Without<WeightRow>>,
+    mut labels: Query<(&mut Text, &WeightLabel)>,
+) {
+    for (mut node, row) in rows.iter_mut() {
+        node.display = if rules.tiles().contains(row.0) {
+            Display::Flex
+        } else {
+            Display::None
+        };
+    }
+    for (mut node, fill) in fills.iter_mut() {
+        node.width = Val::Percent((rules.weight(fill.0) / MAX_WEIGHT).min(1.0) * 100.0);
+    }
+    for (mut text, label) in labels.iter_mut() {
+        text.0 = format!("{:?} weight: {:.2}", label.0, rules.weight(label.0));
+    }
+}